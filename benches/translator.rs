@@ -0,0 +1,58 @@
+//! Compares building a translation bundle for every request against reusing cached bundles.
+
+#[allow(dead_code)]
+#[path = "../src/lang.rs"]
+mod lang;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::sync::Arc;
+
+fn resources() -> lang::ResourceMap {
+    [
+        ("de", include_str!("../res/lang/de.ftl")),
+        ("en", include_str!("../res/lang/en.ftl")),
+    ]
+    .iter()
+    .map(|(lang, src)| {
+        (
+            lang.parse().unwrap(),
+            vec![Arc::new(
+                fluent::FluentResource::try_new((*src).to_owned())
+                    .expect("Failed to parse translation"),
+            )],
+        )
+    })
+    .collect()
+}
+
+fn bench_translator(c: &mut Criterion) {
+    let de = unic_langid::langid!("de");
+    let en = unic_langid::langid!("en");
+    let languages = [&de, &en];
+
+    let mut group = c.benchmark_group("translator");
+
+    let resources = resources();
+    group.bench_function("uncached", |b| {
+        b.iter(|| {
+            let lang = lang::Translator::new(
+                Arc::new(lang::new_bundle(&resources, &languages)),
+                languages[0].clone(),
+            );
+            lang.tr(&lang::ALL).len()
+        })
+    });
+
+    let catalog = lang::Catalog::new(resources);
+    group.bench_function("cached", |b| {
+        b.iter(|| {
+            let lang = catalog.translator(&languages);
+            lang.tr(&lang::ALL).len()
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_translator);
+criterion_main!(benches);
@@ -1,7 +1,19 @@
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::io::Write;
 
 const DEFAULT_LANG_FILE: &str = "res/lang/en.ftl";
+const LANG_DIR: &str = "res/lang";
+const STRICT_ENV: &str = "HITIDE_STRICT_TRANSLATIONS";
+
+include!("src/hashed_name.rs");
+
+/// Stylesheets served from `/static`, as source path and served name
+const CSS_ASSETS: &[(&str, &str)] = &[
+    ("res/main.css", "main.css"),
+    ("res/themes/dark.css", "theme-dark.css"),
+    ("res/themes/high_contrast.css", "theme-high_contrast.css"),
+    ("res/themes/light.css", "theme-light.css"),
+];
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let out_dir = std::env::var("OUT_DIR")?;
@@ -20,13 +32,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             }
         };
 
+        let mut ids = Vec::new();
+        let mut en_args = HashMap::new();
+
         for entry in ast.body {
             if let fluent_syntax::ast::Entry::Message(msg) = entry {
                 let id = msg.id.name;
+                ids.push(id);
                 let mut args: Vec<&str> = Vec::new();
 
-                println!("finding arguments for {:?}", msg.value);
-
                 if let Some(value) = msg.value {
                     discover_args_for_pattern(&mut args, &value);
                 }
@@ -69,7 +83,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     write!(out_file, "LangKey(\"{}\", Some(fluent::fluent_args![", id)?;
                     {
                         let mut first = true;
-                        for arg in args {
+                        for arg in &args {
                             if !first {
                                 write!(out_file, ", ")?;
                             }
@@ -81,10 +95,329 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     writeln!(out_file, "]))")?;
                     writeln!(out_file, "}}")?;
                 }
+
+                en_args.insert(id, args);
+            }
+        }
+
+        writeln!(out_file, "pub const ALL_KEYS: &[&str] = &{:?};", ids)?;
+
+        check_translations(&out_dir, &ids, &en_args)?;
+    }
+
+    build_assets(&out_dir)?;
+
+    Ok(())
+}
+
+/// Minifies and precompresses the static assets, writing `assets.rs` to list them under names
+/// containing a hash of their content.
+fn build_assets(out_dir: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let out_dir = std::path::Path::new(out_dir);
+    let mut out_file = std::fs::File::create(out_dir.join("assets.rs"))?;
+
+    writeln!(out_file, "pub const BUILT_ASSETS: &[BuiltAsset] = &[")?;
+
+    for (src, name) in CSS_ASSETS {
+        println!("cargo:rerun-if-changed={}", src);
+
+        let content = minify_css(&std::fs::read_to_string(src)?);
+        let hashed_name = hashed_name(name, content.as_bytes());
+
+        let path = out_dir.join(&hashed_name);
+        std::fs::write(&path, &content)?;
+
+        let gzip_path = out_dir.join(format!("{}.gz", hashed_name));
+        {
+            let mut encoder = flate2::write::GzEncoder::new(
+                std::fs::File::create(&gzip_path)?,
+                flate2::Compression::best(),
+            );
+            encoder.write_all(content.as_bytes())?;
+            encoder.finish()?;
+        }
+
+        let brotli_path = out_dir.join(format!("{}.br", hashed_name));
+        {
+            let mut encoder =
+                brotli::CompressorWriter::new(std::fs::File::create(&brotli_path)?, 4096, 11, 22);
+            encoder.write_all(content.as_bytes())?;
+        }
+
+        writeln!(
+            out_file,
+            "BuiltAsset {{ name: {:?}, hashed_name: {:?}, content_type: \"text/css; charset=utf-8\", content: include_bytes!({:?}), gzip: include_bytes!({:?}), brotli: include_bytes!({:?}) }},",
+            name,
+            hashed_name,
+            path,
+            gzip_path,
+            brotli_path,
+        )?;
+    }
+
+    writeln!(out_file, "];")?;
+
+    Ok(())
+}
+
+/// Removes comments and unneeded whitespace from a stylesheet.
+///
+/// Whitespace is only dropped around characters where it can't be significant, so that
+/// descendant selectors like `a :hover` keep their meaning.
+fn minify_css(src: &str) -> String {
+    let mut result = String::with_capacity(src.len());
+    let mut chars = src.chars().peekable();
+    let mut pending_space = false;
+
+    fn is_separator(c: char) -> bool {
+        matches!(c, '{' | '}' | ';' | ',' | '>' | ':')
+    }
+
+    while let Some(c) = chars.next() {
+        match c {
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = None;
+                for c in chars.by_ref() {
+                    if prev == Some('*') && c == '/' {
+                        break;
+                    }
+                    prev = Some(c);
+                }
+            }
+            '"' | '\'' => {
+                if pending_space && !result.ends_with(is_separator) {
+                    result.push(' ');
+                }
+                pending_space = false;
+                result.push(c);
+                while let Some(inner) = chars.next() {
+                    result.push(inner);
+                    if inner == '\\' {
+                        if let Some(escaped) = chars.next() {
+                            result.push(escaped);
+                        }
+                    } else if inner == c {
+                        break;
+                    }
+                }
+            }
+            c if c.is_whitespace() => {
+                pending_space = !result.is_empty();
+            }
+            c => {
+                if pending_space {
+                    // `:` may start a pseudo-class in a selector, so only the space after it is dropped
+                    let before_separator = is_separator(c) && c != ':';
+                    if !result.ends_with(is_separator) && !before_separator {
+                        result.push(' ');
+                    }
+                    pending_space = false;
+                }
+                if c == '}' && result.ends_with(';') {
+                    result.pop();
+                }
+                result.push(c);
+            }
+        }
+    }
+
+    result
+}
+
+#[derive(Default)]
+struct LocaleReport<'a> {
+    translated: usize,
+    missing: Vec<&'a str>,
+    extra: Vec<&'a str>,
+    unknown_args: Vec<(&'a str, Vec<&'a str>)>,
+    unused_args: Vec<(&'a str, Vec<&'a str>)>,
+    errors: Vec<String>,
+}
+
+fn check_locale<'a>(
+    content: &'a str,
+    en_ids: &[&'a str],
+    en_args: &HashMap<&str, Vec<&'a str>>,
+) -> LocaleReport<'a> {
+    let mut report = LocaleReport::default();
+
+    let ast = match fluent_syntax::parser::parse_runtime(content) {
+        Ok(ast) => ast,
+        Err((ast, errors)) => {
+            for err in errors {
+                let line = content[..err.pos.start].matches('\n').count() + 1;
+                report.errors.push(format!("line {}: {}", line, err));
+            }
+
+            ast
+        }
+    };
+
+    let mut found = HashSet::new();
+
+    for entry in ast.body {
+        if let fluent_syntax::ast::Entry::Message(msg) = entry {
+            let id = msg.id.name;
+
+            match en_args.get(id) {
+                None => report.extra.push(id),
+                Some(expected) => {
+                    found.insert(id);
+                    report.translated += 1;
+
+                    let mut args = Vec::new();
+                    if let Some(value) = msg.value {
+                        discover_args_for_pattern(&mut args, &value);
+                    }
+
+                    let unknown: Vec<_> = args
+                        .iter()
+                        .copied()
+                        .filter(|arg| !expected.contains(arg))
+                        .collect::<BTreeSet<_>>()
+                        .into_iter()
+                        .collect();
+                    if !unknown.is_empty() {
+                        report.unknown_args.push((id, unknown));
+                    }
+
+                    let unused: Vec<_> = expected
+                        .iter()
+                        .copied()
+                        .filter(|arg| !args.contains(arg))
+                        .collect();
+                    if !unused.is_empty() {
+                        report.unused_args.push((id, unused));
+                    }
+                }
             }
         }
     }
 
+    report.missing = en_ids
+        .iter()
+        .copied()
+        .filter(|id| !found.contains(id))
+        .collect();
+
+    report
+}
+
+/// Checks the other locales against the default one, writing a coverage report to `OUT_DIR`
+fn check_translations(
+    out_dir: &str,
+    en_ids: &[&str],
+    en_args: &HashMap<&str, Vec<&str>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    println!("cargo:rerun-if-changed={}", LANG_DIR);
+    println!("cargo:rerun-if-env-changed={}", STRICT_ENV);
+
+    let strict = matches!(
+        std::env::var(STRICT_ENV).as_deref(),
+        Ok(value) if !value.is_empty() && value != "0"
+    );
+
+    let mut paths = Vec::new();
+    for entry in std::fs::read_dir(LANG_DIR)? {
+        let path = entry?.path();
+        if path.extension() == Some(std::ffi::OsStr::new("ftl"))
+            && path != std::path::Path::new(DEFAULT_LANG_FILE)
+        {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    let report_path = std::path::Path::new(out_dir).join("lang_coverage.txt");
+    let mut report_file = std::fs::File::create(&report_path)?;
+
+    let mut problems = 0;
+
+    for path in paths {
+        println!("cargo:rerun-if-changed={}", path.display());
+
+        let lang = path.file_stem().unwrap().to_string_lossy();
+        let content = std::fs::read_to_string(&path)?;
+        let report = check_locale(&content, en_ids, en_args);
+
+        writeln!(
+            report_file,
+            "{}: {}/{} ({:.1}%)",
+            lang,
+            report.translated,
+            en_ids.len(),
+            (report.translated as f64) * 100.0 / (en_ids.len() as f64),
+        )?;
+        for id in &report.missing {
+            writeln!(report_file, "  missing: {}", id)?;
+        }
+        for id in &report.extra {
+            writeln!(report_file, "  extra: {}", id)?;
+        }
+        for (id, args) in &report.unknown_args {
+            writeln!(
+                report_file,
+                "  unknown variables in {}: {}",
+                id,
+                args.join(", ")
+            )?;
+        }
+        for (id, args) in &report.unused_args {
+            writeln!(
+                report_file,
+                "  unused variables in {}: {}",
+                id,
+                args.join(", ")
+            )?;
+        }
+        for err in &report.errors {
+            writeln!(report_file, "  syntax error: {}", err)?;
+        }
+
+        for err in &report.errors {
+            println!("cargo:warning={}: {}", path.display(), err);
+        }
+        for id in &report.extra {
+            println!(
+                "cargo:warning={}: {} is not in {}",
+                path.display(),
+                id,
+                DEFAULT_LANG_FILE
+            );
+        }
+        for (id, args) in &report.unknown_args {
+            println!(
+                "cargo:warning={}: {} uses variables not present in {}: {}",
+                path.display(),
+                id,
+                DEFAULT_LANG_FILE,
+                args.join(", "),
+            );
+        }
+        problems += report.errors.len() + report.extra.len() + report.unknown_args.len();
+
+        if strict && !report.missing.is_empty() {
+            println!(
+                "cargo:warning={}: {} keys missing",
+                path.display(),
+                report.missing.len()
+            );
+            problems += report.missing.len();
+        }
+    }
+
+    println!("translation coverage report: {}", report_path.display());
+
+    if strict && problems > 0 {
+        return Err(format!(
+            "{} translation problems found, see {}",
+            problems,
+            report_path.display()
+        )
+        .into());
+    }
+
     Ok(())
 }
 
@@ -1,4 +1,4 @@
-use std::hash::{Hash, Hasher};
+use sha2::Digest;
 use std::io::Write;
 
 fn main() {
@@ -23,9 +23,11 @@ fn main() {
 
         let content = std::fs::read_to_string(&path).unwrap();
 
-        let mut hasher = std::collections::hash_map::DefaultHasher::new();
-        content.hash(&mut hasher);
-        let hash = hasher.finish();
+        let digest = sha2::Sha256::digest(content.as_bytes());
+        let hash: String = digest[..8]
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
 
         let key = format!("{}.svg", hash);
 
@@ -41,6 +41,7 @@ forgot_password_info = Wenn dein Account eine Emailadresse gesetzt hat, kannst d
 forgot_password_new_password_prompt = Neues Password:
 home_follow_prompt = Warum nicht { $part_follow }?
 home_follow_prompt_part_follow = Folge einigen Communities
+language_name = Deutsch
 liked_by = Geliked von:
 likes = Likes
 local = Lokal
@@ -98,6 +99,7 @@ submit = Absenden
 text_with_markdown = Text (Markdown unterstützt)
 thing_comment = { $part_comment } Auf { $part_post } { $part_time }:
 thing_comment_part_comment = Kommentar
+time_absolute_format = %-d. %B %Y, %H:%M
 timeago_years =
     { $years } { $years ->
         [one] Jahr
@@ -141,7 +143,6 @@ to_post = Kommentar auf
 url = URL
 user_edit_description_prompt = Profilbeschreibung:
 user_edit_not_you = Du kannst nur dein eigenes Profil bearbeiten.
-user_edit_password_prompt = Password ändern
 user_edit_submit = Speichern
 user_edit_title = Profil bearbeiten
 user_id_prompt = Nutzer ID:
@@ -5,6 +5,9 @@ about_text1 = lotide is an attempt to build a federated forum. Users can create
 about_text2 = For more information or to view the source code, check out the { $part_sourcehut }.
 about_text2_part_sourcehut = SourceHut page
 about_versions = This instance is running hitide { $hitide_version } on { $backend_name } { $backend_version }.
+accounts_add = Add another account
+accounts_switch_missing = That account is not signed in here. Log in to add it.
+accounts_switcher = Accounts
 action_flag = flag
 add = Add
 add_by_remote_id = Add by ID:
@@ -14,6 +17,7 @@ administration_edit = Edit Instance Details
 administration_edit_invitations_enabled = Invitations:
 administration_edit_signup_allowed = Public Signups:
 administration_invitation_creation_requirement = Invitations can be created by
+administration_invitations = View All Invitations
 administration_invitations_enabled = Invitations are currently { $part_enabled }
 administration_signup_allowed = Signups are currently { $part_allowed }
 all = All
@@ -21,6 +25,16 @@ all_title = The Whole Known Network
 allowed_false = not allowed
 allowed_true = allowed
 and_more = …and more
+backend_timeout = The server took too long to respond. Please try again later.
+backend_unavailable = The server is currently unavailable. Please try again later.
+backend_unavailable_title = Server Unavailable
+captcha_arithmetic = What is { $a } plus { $b }?
+captcha_expired = The CAPTCHA expired. Please try again.
+captcha_honeypot = Leave this field empty:
+captcha_image_prompt = Type the characters shown:
+captcha_incorrect = The CAPTCHA answer was incorrect. Please try again.
+captcha_use_arithmetic = Use a text question instead
+captcha_use_image = Use an image instead
 comment = Comment
 comments = Comments
 comment_attachment_prefix = Attachment:
@@ -53,10 +67,13 @@ create_invitation = Create Invitation
 create_invitation_result = Invitation created. Share this link to invite someone (it can only be used once):
 delete = delete
 delete_yes = Yes, delete
+density_comfortable = Comfortable
+density_compact = Compact
 description = Description
 edit = Edit
 enabled_true = enabled
 enabled_false = disabled
+error_request_id = Request ID: { $id }
 fetch = Fetch
 flag_comment_prompt = Add a comment:
 flag_dismiss = Dismiss
@@ -76,21 +93,43 @@ forgot_password_complete = Password successfully reset.
 forgot_password_email_prompt = Email Address:
 forgot_password_info = If your account has an attached email address, you can reset your password here.
 forgot_password_new_password_prompt = New Password:
+handle_community_not_found = No community was found with that name.
+handle_user_not_found = No user was found with that name.
 home_follow_prompt = Why not { $part_follow }?
 home_follow_prompt_part_follow = follow some communities
+invitation_created_by = Created by
+invitation_filter_all = All
+invitation_filter_unused = Unused
+invitation_filter_used = Used
+invitation_qr_code_label = QR code for the invitation link
+invitation_revoke = Revoke
+invitations_admin_title = All Invitations
+invitations_none = No invitations found.
+invitations_page_next = View More
 invite_users = Invite Users
 invitation_already_used = That invitation has already been used
+language = Language
+language_automatic = Automatic
+language_name = English
 liked_by = Liked by:
 likes = Likes
 local = Local
 local_title = Posts from Local Communities
 local_user_name_prompt = Local User Name:
 login = Login
+login_add_account_note = You are already logged in. Logging in here will add another account, which you can switch to from the header.
 login_signup = Or { $part_signup }
 login_signup_part_signup = create a new account
 logout = Log Out
+lookup_kind_comment = Comment
+lookup_kind_community = Community
+lookup_kind_post = Post
+lookup_kind_unknown = Other
+lookup_kind_user = User
+lookup_multiple = Several results matched. Which did you mean?
 lookup_nothing = Nothing found.
 lookup_title = Lookup
+lookup_unsupported = Something was found, but it is not a kind of object that can be shown here.
 missing_permission_create_invitation = You are not allowed to create invitations
 moderation_dashboard = Moderation Dashboard
 moderation_dashboard_some = Moderation Dashboard (Pending Actions)
@@ -161,8 +200,21 @@ post_submitted_by = Submitted { $part_time } by { $part_user }
 post_submitted_by_to = Submitted { $part_time } by { $part_user } to { $part_community }
 post_submitted_to = Submitted { $part_time } to { $part_community }
 post_timeframe = Posts from:
+preferences = Preferences
+preferences_comment_sort = Default comment sort:
+preferences_density = List density:
+preferences_expand_sensitive = Always show sensitive content
+preferences_links_new_tab = Open links in a new tab
+preferences_post_sort = Default post sort:
+preferences_posts_per_page = Posts per page:
+preferences_posts_per_page_default = Default
+preferences_theme = Theme:
+preferences_time_display = Show times as:
+preferences_time_zone = Time zone:
 preview = Preview
 profile = Profile
+rate_limited = Too many attempts. Please wait { $seconds } seconds and try again.
+rate_limited_title = Too Many Requests
 register = Register
 remote = Remote
 remote_url = source
@@ -188,8 +240,15 @@ sort_new = new
 sort_top = top
 submit = Submit
 text_with_markdown = Text (markdown supported)
+theme_dark = Dark
+theme_high_contrast = High contrast
+theme_light = Light
+theme_system = Same as system
 thing_comment = { $part_comment } on { $part_post } { $part_time }:
 thing_comment_part_comment = Comment
+time_absolute_format = %b %-d, %Y %H:%M
+time_display_absolute = Date and time
+time_display_relative = Relative ("3 hours ago")
 time_input_minutes = minutes
 time_input_hours = hours
 time_input_days = days
@@ -243,14 +302,50 @@ unknown = [unknown]
 upvote = Upvote
 url = URL
 user_bot_tag = bot
+user_delete = Delete Account
+user_delete_admin_info = Deleting this account cannot be undone. The user will no longer be able to log in, their posts and comments will be deleted, and other servers they were shared with will be asked to remove them.
+user_delete_complete = Your account has been deleted.
+user_delete_complete_admin = The account has been deleted.
+user_delete_info = Deleting your account cannot be undone. You will be logged out everywhere, your posts and comments will be deleted, and other servers they were shared with will be asked to remove them.
+user_delete_link = Delete your account
+user_delete_not_allowed = You can only delete your own account.
+user_delete_password_prompt = Enter your password to confirm:
+user_delete_yes = Yes, delete
 user_edit_description_prompt = Profile Description:
 user_edit_is_bot_checkbox_label = Mark this account as a bot
 user_edit_not_you = You can only edit your own profile.
-user_edit_password_prompt = Change Password
+user_edit_security_link = Change your password, email address or active sessions
 user_edit_submit = Save
 user_edit_title = Edit Profile
+user_export_download = Download my data
+user_export_heading = Your Data
+user_export_info = Download a ZIP archive of your profile, posts and comments as JSON and Markdown, along with the communities you follow. Large accounts may take a while.
 user_id_prompt = User ID:
 user_remote_note = This is a remote user, information on this page may be incomplete.
+user_security_current_password_prompt = Current Password:
+user_security_email_heading = Email Address
+user_security_email_info = Your email address is used to reset your password if you forget it.
+user_security_email_prompt = New Email Address:
+user_security_email_updated = Your email address has been changed.
+user_security_logout_everywhere = Log out everywhere
+user_security_logout_everywhere_info = Ends every session for this account, including this one.
+user_security_new_password_confirm_prompt = Confirm New Password:
+user_security_new_password_prompt = New Password:
+user_security_password_contains_username = The new password must not contain your username.
+user_security_password_heading = Change Password
+user_security_password_hint = Use at least 8 characters, mixing letters, numbers and symbols, or a passphrase of 16 or more characters.
+user_security_password_incorrect = The current password is incorrect.
+user_security_password_mismatch = The new passwords do not match.
+user_security_password_too_short = The new password must be at least 8 characters long.
+user_security_password_too_simple = The new password is too easy to guess. Mix letters, numbers and symbols, or use a longer passphrase.
+user_security_password_updated = Your password has been changed.
+user_security_session_current = (this session)
+user_security_session_revoke = Log out
+user_security_session_revoked = The session has been logged out.
+user_security_session_unknown_device = Unknown device
+user_security_sessions_heading = Active Sessions
+user_security_sessions_unavailable = This server does not support listing sessions.
+user_security_title = Account Security
 user_suspend = Suspend
 user_suspend_title = Suspend User
 user_suspend_question = Suspend this user? They will not be allowed to log in unless unsuspended.
@@ -41,6 +41,7 @@ forgot_password_info = Se via konto havas ligitan retpoŝtadreson, vi povas rest
 forgot_password_new_password_prompt = Nova Pasvorto:
 home_follow_prompt = Kial ne { $part_follow }?
 home_follow_prompt_part_follow = aboni iujn komunumojn
+language_name = Esperanto
 liked_by = Ŝatata de:
 likes = Ŝatantoj
 local = Loka
@@ -150,7 +151,6 @@ user_bot_tag = roboto
 user_edit_description_prompt = Priskribo de Profilo:
 user_edit_is_bot_checkbox_label = Marki ĉi tiun konton kiel roboto
 user_edit_not_you = Vi nur rajtas redakti vian propran profilon.
-user_edit_password_prompt = Ŝanĝi Pasvorton
 user_edit_submit = Konservi
 user_edit_title = Redakti Profilon
 user_id_prompt = Uzanto ID:
@@ -205,6 +205,7 @@ poll_new_options_prompt = Elektoj:
 poll_submit = Sendi
 profile = Profilo
 remove_upvote = Forigi ŝaton
+time_absolute_format = %Y-%m-%d %H:%M
 time_input_minutes = minutoj
 time_input_hours = horoj
 time_input_days = tagoj
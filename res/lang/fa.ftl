@@ -76,6 +76,7 @@ home_follow_prompt = چرا { $part_follow } نکنید؟
 home_follow_prompt_part_follow = انجمن‌هایی را دنبال کنید
 invite_users = از کاربرانی دعوت کنید
 invitation_already_used = این دعوت‌نامه پیش‌تر استفاده شده
+language_name = فارسی
 liked_by = پسندشان بوده:
 likes = مورد پسند بوده:
 local = محلی
@@ -182,6 +183,7 @@ submit = ثبت
 text_with_markdown = متن (از مارک‌داون هم پشتیبانی می‌شود)
 thing_comment = { $part_comment } در { $part_post } { $part_time }:
 thing_comment_part_comment = دیدگاه
+time_absolute_format = %Y/%m/%d %H:%M
 time_input_minutes = دقیقه
 time_input_hours = ساعت
 time_input_days = روز
@@ -238,7 +240,6 @@ user_bot_tag = بات
 user_edit_description_prompt = رخ‌نوشت:
 user_edit_is_bot_checkbox_label = این حساب را به عنوان بات نشانه‌گذاری کن
 user_edit_not_you = شما فقط می‌توانید رخ (پروفایل) خودتان را ویرایش کنید
-user_edit_password_prompt = ویرایش گذرواژه
 user_edit_submit = ذخیره
 user_edit_title = ویرایش رخ
 user_id_prompt = شناسه‌ی کاربری:
@@ -41,6 +41,7 @@ forgot_password_info = If your account has an attached email address, you can re
 forgot_password_new_password_prompt = Nouveau mot de passe :
 home_follow_prompt = Pourquoi pas { $part_follow }?
 home_follow_prompt_part_follow = Suivre des communautés
+language_name = Français
 liked_by = Aimé par:
 likes = j'aime
 local = Local
@@ -55,7 +56,7 @@ no_cancel = Non, annuler
 nothing = Il semble qu'il n'y ait rien ici.
 nothing_yet = Il semble qu'il n'y ait rien ici. (pour l'instant!).
 notifications = Notifications
-notification_comment_reply = Répondre à { $part_your_comment } sur { $part_port }:
+notification_comment_reply = Répondre à { $part_your_comment } sur { $part_post }:
 notification_comment_reply_part_your_comment = votre commentaire
 notification_post_reply = { $part_comment } sur votre post { $part_post }:
 notification_post_reply_part_comment = Commentaire
@@ -93,6 +94,7 @@ submit = Envoyer
 text_with_markdown = Texte (markdown supporté)
 thing_comment = { $part_comment } sur { $part_post } { $part_time }:
 thing_comment_part_comment = Commentaire
+time_absolute_format = %-d %B %Y, %H:%M
 timeago_years =
     il y a { $years } { $years ->
         [one] an
@@ -136,7 +138,6 @@ to_post = Commenter sur
 url = URL
 user_edit_description_prompt = Description de profile:
 user_edit_not_you = Vous pouvez seulement éditer votre propre profil.
-user_edit_password_prompt = Changer le mot de passe
 user_edit_submit = Sauvegarder
 user_edit_title = Editer le profil
 user_id_prompt = ID utilisateur:
@@ -0,0 +1,140 @@
+use serde_derive::Deserialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+tokio::task_local! {
+    static CURRENT_REQUEST: Arc<RequestInfo>;
+}
+
+/// Per-request values collected while handling a request, reported once it completes
+#[derive(Debug)]
+pub struct RequestInfo {
+    pub id: String,
+    backend_time_micros: AtomicU64,
+    user_id: Mutex<Option<i64>>,
+}
+
+impl Default for RequestInfo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RequestInfo {
+    pub fn new() -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_simple().to_string(),
+            backend_time_micros: AtomicU64::new(0),
+            user_id: Mutex::new(None),
+        }
+    }
+
+    pub fn backend_time(&self) -> std::time::Duration {
+        std::time::Duration::from_micros(self.backend_time_micros.load(Ordering::Relaxed))
+    }
+
+    pub fn user_id(&self) -> Option<i64> {
+        *self.user_id.lock().unwrap()
+    }
+
+    pub async fn scope<F: std::future::Future>(self: Arc<Self>, fut: F) -> F::Output {
+        CURRENT_REQUEST.scope(self, fut).await
+    }
+}
+
+/// Adds time spent waiting on the backend to the current request, if there is one
+pub fn add_backend_time(duration: std::time::Duration) {
+    let _ = CURRENT_REQUEST.try_with(|info| {
+        info.backend_time_micros
+            .fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+    });
+}
+
+/// ID assigned to a request, from the header added when it was received
+pub fn request_id_for_headers(headers: &hyper::header::HeaderMap) -> Option<&str> {
+    headers
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+}
+
+/// Adds the request ID to a plain text error response, so it can be quoted when reporting a
+/// problem. Pages with their own markup show it themselves.
+pub async fn add_request_id_to_error(
+    res: hyper::Response<hyper::Body>,
+    request_id: &str,
+) -> Result<hyper::Response<hyper::Body>, hyper::Error> {
+    let is_error = res.status().is_client_error() || res.status().is_server_error();
+    if !is_error || res.headers().contains_key(hyper::header::CONTENT_TYPE) {
+        return Ok(res);
+    }
+
+    let (parts, body) = res.into_parts();
+    let body = hyper::body::to_bytes(body).await?;
+
+    let text = format!(
+        "{}\nRequest ID: {}",
+        String::from_utf8_lossy(&body),
+        request_id
+    );
+
+    Ok(hyper::Response::from_parts(parts, text.into()))
+}
+
+pub fn set_user_id(user_id: i64) {
+    let _ = CURRENT_REQUEST.try_with(|info| {
+        *info.user_id.lock().unwrap() = Some(user_id);
+    });
+}
+
+pub fn log_request(
+    format: LogFormat,
+    info: &RequestInfo,
+    method: &hyper::Method,
+    path: &str,
+    status: hyper::StatusCode,
+    latency: std::time::Duration,
+) {
+    let latency_ms = latency.as_secs_f64() * 1000.0;
+    let backend_ms = info.backend_time().as_secs_f64() * 1000.0;
+    let user_id = info.user_id();
+
+    match format {
+        LogFormat::Text => {
+            log::info!(
+                target: "hitide::access",
+                "{} {} {} {:.1}ms backend={:.1}ms user={} request_id={}",
+                method,
+                path,
+                status.as_u16(),
+                latency_ms,
+                backend_ms,
+                user_id.map(|id| id.to_string()).as_deref().unwrap_or("-"),
+                info.id,
+            );
+        }
+        LogFormat::Json => {
+            log::info!(
+                target: "hitide::access",
+                "{}",
+                serde_json::json!({
+                    "request_id": info.id,
+                    "method": method.as_str(),
+                    "path": path,
+                    "status": status.as_u16(),
+                    "latency_ms": latency_ms,
+                    "backend_ms": backend_ms,
+                    "user_id": user_id,
+                }),
+            );
+        }
+    }
+}
@@ -0,0 +1,192 @@
+//! Several signed-in accounts per browser, with one active at a time.
+//!
+//! The active account's token is kept in [`TOKEN_COOKIE`], which is what gets sent to the
+//! backend. Every signed-in account, the active one included, is also listed in a signed
+//! [`ACCOUNTS_COOKIE`] so that the header can offer switching between them.
+
+use crate::routes::{CookieMap, COOKIE_AGE};
+use crate::signing::SigningKey;
+use serde_derive::{Deserialize, Serialize};
+
+pub const TOKEN_COOKIE: &str = "hitideToken";
+pub const ACCOUNTS_COOKIE: &str = "hitideAccounts";
+
+/// Limit on saved accounts, to keep the cookie small
+const MAX_ACCOUNTS: usize = 5;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SavedAccount {
+    pub token: String,
+    pub user_id: i64,
+    pub username: String,
+}
+
+/// A saved account as shown in the account switcher
+#[derive(Debug, Clone)]
+pub struct AccountSummary {
+    pub user_id: i64,
+    pub username: String,
+}
+
+/// Token for the account requests should be made as, if any
+pub fn active_token<'a>(cookies: &CookieMap<'a>) -> Option<&'a str> {
+    cookies.get(TOKEN_COOKIE).map(|cookie| cookie.value)
+}
+
+/// Attributes shared by both cookies, which scripts have no need to read. `Secure` is only added
+/// when the site is served over HTTPS, since browsers would otherwise drop the cookies.
+fn cookie_attributes(secure: bool) -> &'static str {
+    if secure {
+        "Path=/; HttpOnly; SameSite=Lax; Secure"
+    } else {
+        "Path=/; HttpOnly; SameSite=Lax"
+    }
+}
+
+fn clear_cookie(name: &str, secure: bool) -> String {
+    format!(
+        "{}=\"\"; {}; Expires=Thu, 01 Jan 1970 00:00:00 GMT",
+        name,
+        cookie_attributes(secure)
+    )
+}
+
+#[derive(Debug, Default)]
+pub struct Accounts {
+    list: Vec<SavedAccount>,
+}
+
+impl Accounts {
+    /// Reads the saved accounts, ignoring the cookie if it has been tampered with
+    pub fn from_cookies(key: &SigningKey, cookies: &CookieMap<'_>) -> Self {
+        Self {
+            list: cookies
+                .get(ACCOUNTS_COOKIE)
+                .and_then(|cookie| key.verify_json(cookie.value))
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn contains_token(&self, token: &str) -> bool {
+        self.list.iter().any(|account| account.token == token)
+    }
+
+    pub fn find(&self, user_id: i64) -> Option<&SavedAccount> {
+        self.list.iter().find(|account| account.user_id == user_id)
+    }
+
+    pub fn first(&self) -> Option<&SavedAccount> {
+        self.list.first()
+    }
+
+    pub fn summaries(&self) -> Vec<AccountSummary> {
+        self.list
+            .iter()
+            .map(|account| AccountSummary {
+                user_id: account.user_id,
+                username: account.username.clone(),
+            })
+            .collect()
+    }
+
+    /// Adds an account, replacing any earlier login for the same user
+    pub fn add(&mut self, account: SavedAccount) {
+        self.list
+            .retain(|existing| existing.user_id != account.user_id);
+        self.list.insert(0, account);
+        self.list.truncate(MAX_ACCOUNTS);
+    }
+
+    pub fn remove_token(&mut self, token: &str) {
+        self.list.retain(|account| account.token != token);
+    }
+
+    /// `Set-Cookie` values storing these accounts with `active` as the one in use, or logging
+    /// out entirely if there is none
+    pub fn to_set_cookies(
+        &self,
+        key: &SigningKey,
+        active: Option<&str>,
+        secure: bool,
+    ) -> Result<Vec<String>, serde_json::Error> {
+        let attributes = cookie_attributes(secure);
+
+        Ok(match active {
+            Some(token) => vec![
+                format!(
+                    "{}={}; {}; Max-Age={}",
+                    TOKEN_COOKIE, token, attributes, COOKIE_AGE
+                ),
+                format!(
+                    "{}={}; {}; Max-Age={}",
+                    ACCOUNTS_COOKIE,
+                    key.sign_json(&self.list)?,
+                    attributes,
+                    COOKIE_AGE
+                ),
+            ],
+            None => vec![
+                clear_cookie(TOKEN_COOKIE, secure),
+                clear_cookie(ACCOUNTS_COOKIE, secure),
+            ],
+        })
+    }
+
+    /// `Set-Cookie` values for after the active account has logged out, switching to another
+    /// saved account if there is one
+    pub fn logged_out_set_cookies(
+        key: &SigningKey,
+        cookies: &CookieMap<'_>,
+        secure: bool,
+    ) -> Result<Vec<String>, serde_json::Error> {
+        let mut accounts = Self::from_cookies(key, cookies);
+        if let Some(token) = active_token(cookies) {
+            accounts.remove_token(token);
+        }
+
+        let next = accounts.first().map(|account| account.token.clone());
+
+        accounts.to_set_cookies(key, next.as_deref(), secure)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Accounts, SavedAccount, ACCOUNTS_COOKIE, TOKEN_COOKIE};
+    use crate::signing::SigningKey;
+
+    fn attributes(cookie: &str) -> Vec<&str> {
+        cookie
+            .split("; ")
+            .skip(1)
+            .filter(|attr| !attr.starts_with("Max-Age="))
+            .collect()
+    }
+
+    #[test]
+    fn both_cookies_share_attributes() {
+        let key = SigningKey::generate();
+        let mut accounts = Accounts::default();
+        accounts.add(SavedAccount {
+            token: "token".to_owned(),
+            user_id: 1,
+            username: "alice".to_owned(),
+        });
+
+        for &secure in &[false, true] {
+            let cookies = accounts
+                .to_set_cookies(&key, Some("token"), secure)
+                .unwrap();
+            assert!(cookies[0].starts_with(&format!("{}=", TOKEN_COOKIE)));
+            assert!(cookies[1].starts_with(&format!("{}=", ACCOUNTS_COOKIE)));
+
+            let expected = if secure {
+                vec!["Path=/", "HttpOnly", "SameSite=Lax", "Secure"]
+            } else {
+                vec!["Path=/", "HttpOnly", "SameSite=Lax"]
+            };
+            assert_eq!(attributes(&cookies[0]), expected);
+            assert_eq!(attributes(&cookies[1]), expected);
+        }
+    }
+}
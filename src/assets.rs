@@ -0,0 +1,139 @@
+use crate::branding::{Branding, FAVICON_FILE_NAME, LOGO_FILE_NAME};
+use crate::compression::Encoding;
+use hyper::body::Bytes;
+use std::collections::HashMap;
+
+/// An asset minified and compressed by build.rs
+pub struct BuiltAsset {
+    pub name: &'static str,
+    pub hashed_name: &'static str,
+    pub content_type: &'static str,
+    pub content: &'static [u8],
+    pub gzip: &'static [u8],
+    pub brotli: &'static [u8],
+}
+
+include!(concat!(env!("OUT_DIR"), "/assets.rs"));
+
+include!("hashed_name.rs");
+
+/// A static file, served under a name containing a hash of its content so it can be cached forever
+pub struct Asset {
+    pub hashed_name: String,
+    pub content_type: &'static str,
+    pub content: Bytes,
+    pub gzip: Option<Bytes>,
+    pub brotli: Option<Bytes>,
+}
+
+impl Asset {
+    fn from_built(asset: &BuiltAsset) -> Self {
+        Self {
+            hashed_name: asset.hashed_name.to_owned(),
+            content_type: asset.content_type,
+            content: Bytes::from_static(asset.content),
+            gzip: Some(Bytes::from_static(asset.gzip)),
+            brotli: Some(Bytes::from_static(asset.brotli)),
+        }
+    }
+
+    /// Creates an asset at startup, compressing it if it's text
+    fn new(name: &str, content_type: &'static str, content: Bytes) -> Self {
+        let compress = content_type.starts_with("text/") || content_type == "image/svg+xml";
+
+        Self {
+            hashed_name: hashed_name(name, &content),
+            content_type,
+            gzip: compress.then(|| Encoding::Gzip.compress(&content).into()),
+            brotli: compress.then(|| Encoding::Brotli.compress(&content).into()),
+            content,
+        }
+    }
+
+    /// Content to send for the encoding the client accepts, and the encoding actually used
+    pub fn encoded(&self, encoding: Option<Encoding>) -> (Bytes, Option<Encoding>) {
+        let precompressed = match encoding {
+            Some(Encoding::Brotli) => self.brotli.as_ref(),
+            Some(Encoding::Gzip) => self.gzip.as_ref(),
+            None => None,
+        };
+
+        match precompressed {
+            Some(content) => (content.clone(), encoding),
+            None => (self.content.clone(), None),
+        }
+    }
+}
+
+/// All assets served from `/static`, apart from icons
+pub struct Assets {
+    by_name: HashMap<&'static str, Asset>,
+    names_by_hash: HashMap<String, &'static str>,
+}
+
+impl Assets {
+    pub fn new(branding: &Branding) -> Self {
+        let mut by_name: HashMap<_, _> = BUILT_ASSETS
+            .iter()
+            .map(|asset| (asset.name, Asset::from_built(asset)))
+            .collect();
+
+        if let Some(custom_css) = &branding.custom_css {
+            let main_css = BUILT_ASSETS
+                .iter()
+                .find(|asset| asset.name == "main.css")
+                .expect("main.css is always built");
+
+            let mut content = main_css.content.to_vec();
+            content.push(b'\n');
+            content.extend_from_slice(custom_css.as_bytes());
+
+            by_name.insert(
+                "main.css",
+                Asset::new("main.css", main_css.content_type, content.into()),
+            );
+        }
+
+        for (name, file) in [
+            (LOGO_FILE_NAME, &branding.logo),
+            (FAVICON_FILE_NAME, &branding.favicon),
+        ] {
+            if let Some(file) = file {
+                by_name.insert(
+                    name,
+                    Asset::new(name, file.content_type, file.content.clone()),
+                );
+            }
+        }
+
+        let names_by_hash = by_name
+            .iter()
+            .map(|(name, asset)| (asset.hashed_name.clone(), *name))
+            .collect();
+
+        Self {
+            by_name,
+            names_by_hash,
+        }
+    }
+
+    /// URL for the current version of an asset
+    pub fn url(&self, name: &str) -> String {
+        match self.by_name.get(name) {
+            Some(asset) => format!("/static/{}", asset.hashed_name),
+            None => format!("/static/{}", name),
+        }
+    }
+
+    /// Looks up an asset by its plain name
+    pub fn get(&self, name: &str) -> Option<&Asset> {
+        self.by_name.get(name)
+    }
+
+    /// Looks up an asset by its hashed name, as used in URLs from [`url`](Self::url)
+    pub fn get_hashed(&self, hashed_name: &str) -> Option<&Asset> {
+        self.names_by_hash
+            .get(hashed_name)
+            .and_then(|name| self.by_name.get(name))
+    }
+}
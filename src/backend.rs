@@ -0,0 +1,1237 @@
+//! Typed client for the lotide API.
+//!
+//! Requests are sent on behalf of the current user, with authentication and language forwarded
+//! by [`for_client`].
+
+use crate::query_types::{
+    CommunityListQuery, FlagListQuery, InvitationListQuery, PollVoteBody, PostListQuery,
+    ReplyListQuery, UserListQuery,
+};
+use crate::resp_types::{
+    CommunitiesCreateResponse, InvitationsCreateResponse, JustContentHTML, JustID, JustStringID,
+    JustToken, JustUser, RespCommentInfo, RespCommunityInfoMaybeYour, RespCommunityModlogEvent,
+    RespFlagInfo, RespInstanceInfo, RespInvitationInfo, RespList, RespLoginInfo, RespLoginSession,
+    RespLookupResult, RespMinimalAuthorInfo, RespMinimalCommunityInfo, RespNotification,
+    RespPostCommentInfo, RespPostInfo, RespPostListPost, RespSiteModlogEvent, RespThingInfo,
+    RespUserInfo,
+};
+use crate::routes::{for_client, CookieMap};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// How many times to try logging out the session created by a password check
+const PASSWORD_CHECK_LOGOUT_ATTEMPTS: u32 = 3;
+const PASSWORD_CHECK_LOGOUT_BACKOFF: std::time::Duration = std::time::Duration::from_millis(100);
+
+pub struct Backend<'a> {
+    host: &'a str,
+    http_client: &'a crate::HttpClient,
+    headers: &'a hyper::header::HeaderMap,
+    cookies: &'a CookieMap<'a>,
+}
+
+impl<'a> Backend<'a> {
+    pub fn new(
+        host: &'a str,
+        http_client: &'a crate::HttpClient,
+        headers: &'a hyper::header::HeaderMap,
+        cookies: &'a CookieMap<'a>,
+    ) -> Self {
+        Self {
+            host,
+            http_client,
+            headers,
+            cookies,
+        }
+    }
+
+    pub fn for_request(
+        ctx: &'a crate::RouteContext,
+        headers: &'a hyper::header::HeaderMap,
+        cookies: &'a CookieMap<'a>,
+    ) -> Self {
+        Self::new(&ctx.backend_host, &ctx.http_client, headers, cookies)
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/api/unstable/{}", self.host, path)
+    }
+
+    fn url_with_query(&self, path: &str, query: &impl Serialize) -> Result<String, crate::Error> {
+        Ok(format!(
+            "{}?{}",
+            self.url(path),
+            serde_urlencoded::to_string(query)?
+        ))
+    }
+
+    /// Sends a request as-is, without acting as the current user
+    async fn send_raw(
+        &self,
+        req: hyper::Request<hyper::Body>,
+    ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+        let res = self.http_client.request(req).await?;
+
+        let status = res.status();
+        if status.is_success() {
+            Ok(res)
+        } else {
+            let bytes = self.read_body(res).await?;
+            Err(crate::Error::RemoteError((
+                status,
+                String::from_utf8_lossy(&bytes).into_owned(),
+            )))
+        }
+    }
+
+    async fn read_body(
+        &self,
+        res: hyper::Response<hyper::Body>,
+    ) -> Result<hyper::body::Bytes, crate::Error> {
+        self.http_client.get_bytes(res.into_body()).await
+    }
+
+    /// Sends a request on behalf of the current user, mapping unsuccessful responses to
+    /// [`crate::Error::RemoteError`]
+    async fn send_request(
+        &self,
+        req: hyper::Request<hyper::Body>,
+    ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+        self.send_raw(for_client(req, self.headers, self.cookies)?)
+            .await
+    }
+
+    pub async fn send(
+        &self,
+        method: hyper::Method,
+        url: String,
+        body: hyper::Body,
+    ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+        let req = hyper::Request::builder()
+            .method(method)
+            .uri(url)
+            .body(body)?;
+
+        self.send_request(req).await
+    }
+
+    async fn send_json<T: DeserializeOwned>(
+        &self,
+        method: hyper::Method,
+        url: String,
+        body: hyper::Body,
+    ) -> Result<T, crate::Error> {
+        let res = self.send(method, url, body).await?;
+        let res = self.read_body(res).await?;
+
+        Ok(serde_json::from_slice(&res)?)
+    }
+
+    async fn get<T: DeserializeOwned>(&self, url: String) -> Result<T, crate::Error> {
+        self.send_json(hyper::Method::GET, url, hyper::Body::empty())
+            .await
+    }
+
+    async fn post<T: DeserializeOwned>(
+        &self,
+        url: String,
+        body: &impl Serialize,
+    ) -> Result<T, crate::Error> {
+        self.send_json(hyper::Method::POST, url, serde_json::to_vec(body)?.into())
+            .await
+    }
+
+    fn include_your_query(include_your: bool) -> &'static str {
+        if include_your {
+            "?include_your=true"
+        } else {
+            ""
+        }
+    }
+
+    /// Fetches an API path as untyped JSON, for passing through as-is
+    pub async fn get_json(&self, path: &str) -> Result<serde_json::Value, crate::Error> {
+        self.get(self.url(path)).await
+    }
+
+    /// Fetches the current login, or `None` if not logged in
+    pub async fn get_login_current(&self) -> Result<Option<RespLoginInfo>, crate::Error> {
+        let res = self
+            .send(
+                hyper::Method::GET,
+                self.url("logins/~current"),
+                hyper::Body::empty(),
+            )
+            .await;
+
+        match res {
+            Ok(res) => {
+                let res = self.read_body(res).await?;
+                Ok(Some(serde_json::from_slice(&res)?))
+            }
+            Err(crate::Error::RemoteError((hyper::StatusCode::UNAUTHORIZED, _))) => Ok(None),
+            Err(other) => Err(other),
+        }
+    }
+
+    /// Logs in with a username and password, without acting as the current user
+    pub async fn create_login(
+        &self,
+        body: &impl Serialize,
+    ) -> Result<JustToken<'static>, crate::Error> {
+        let res = self
+            .send_raw(
+                hyper::Request::post(self.url("logins")).body(serde_json::to_vec(body)?.into())?,
+            )
+            .await?;
+        let res = self.read_body(res).await?;
+
+        Ok(serde_json::from_slice(&res)?)
+    }
+
+    pub async fn delete_login_current(&self) -> Result<(), crate::Error> {
+        self.send(
+            hyper::Method::DELETE,
+            self.url("logins/~current"),
+            hyper::Body::empty(),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Checks a username and password by logging in with them, then logging that session out
+    /// again
+    ///
+    /// lotide has no separate way to check a password, so every check creates a real login
+    /// session. The probe login is made without the current user's token. Logging it out is
+    /// retried a few times, and if it still fails the session is left active: a warning is logged
+    /// so it can be found in the user's session list, and the error is returned.
+    pub async fn verify_password(
+        &self,
+        username: &str,
+        password: &str,
+    ) -> Result<bool, crate::Error> {
+        let res = self
+            .create_login(&serde_json::json!({
+                "username": username,
+                "password": password,
+            }))
+            .await;
+
+        let res = match res {
+            Ok(res) => res,
+            Err(crate::Error::RemoteError((status, _))) if status.is_client_error() => {
+                return Ok(false)
+            }
+            Err(other) => return Err(other),
+        };
+        let created = chrono::Utc::now();
+
+        let mut attempts = 0;
+        loop {
+            let logout = self
+                .send_raw(
+                    hyper::Request::delete(self.url("logins/~current"))
+                        .header(
+                            hyper::header::AUTHORIZATION,
+                            hyper::header::HeaderValue::from_str(&format!("Bearer {}", res.token))?,
+                        )
+                        .body(hyper::Body::empty())?,
+                )
+                .await;
+
+            match logout {
+                Ok(_) => return Ok(true),
+                Err(err) if attempts + 1 >= PASSWORD_CHECK_LOGOUT_ATTEMPTS => {
+                    log::warn!(
+                        "Failed to log out password check session for {}, created at {}, which is still active: {:?}",
+                        username,
+                        created.to_rfc3339(),
+                        err,
+                    );
+
+                    return Err(err);
+                }
+                Err(_) => {
+                    attempts += 1;
+                    tokio::time::delay_for(PASSWORD_CHECK_LOGOUT_BACKOFF * attempts).await;
+                }
+            }
+        }
+    }
+
+    /// Lists the current user's active logins, or `None` if the backend doesn't support it
+    pub async fn list_logins(
+        &self,
+    ) -> Result<Option<RespList<'static, RespLoginSession<'static>>>, crate::Error> {
+        let res = self
+            .send(hyper::Method::GET, self.url("logins"), hyper::Body::empty())
+            .await;
+
+        match res {
+            Ok(res) => {
+                let res = self.read_body(res).await?;
+                Ok(Some(serde_json::from_slice(&res)?))
+            }
+            Err(crate::Error::RemoteError((hyper::StatusCode::NOT_FOUND, _)))
+            | Err(crate::Error::RemoteError((hyper::StatusCode::METHOD_NOT_ALLOWED, _))) => {
+                Ok(None)
+            }
+            Err(other) => Err(other),
+        }
+    }
+
+    pub async fn delete_login(&self, login_id: i64) -> Result<(), crate::Error> {
+        self.send(
+            hyper::Method::DELETE,
+            self.url(&format!("logins/{}", login_id)),
+            hyper::Body::empty(),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Logs out every session of the current user, including this one
+    pub async fn delete_all_logins(&self) -> Result<(), crate::Error> {
+        self.send(
+            hyper::Method::DELETE,
+            self.url("logins"),
+            hyper::Body::empty(),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Registers a new account, without acting as the current user
+    pub async fn create_user(
+        &self,
+        body: &impl Serialize,
+    ) -> Result<JustToken<'static>, crate::Error> {
+        let res = self
+            .send_raw(
+                hyper::Request::post(self.url("users")).body(serde_json::to_vec(body)?.into())?,
+            )
+            .await?;
+        let res = self.read_body(res).await?;
+
+        Ok(serde_json::from_slice(&res)?)
+    }
+
+    pub async fn list_users(
+        &self,
+        query: &UserListQuery<'_>,
+    ) -> Result<RespList<'static, RespUserInfo<'static>>, crate::Error> {
+        self.get(self.url_with_query("users", query)?).await
+    }
+
+    pub async fn update_user_me(&self, body: &impl Serialize) -> Result<(), crate::Error> {
+        self.send(
+            hyper::Method::PATCH,
+            self.url("users/~me"),
+            serde_json::to_vec(body)?.into(),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn set_user_suspended(
+        &self,
+        user_id: i64,
+        suspended: bool,
+    ) -> Result<(), crate::Error> {
+        self.send(
+            hyper::Method::PATCH,
+            self.url(&format!("users/{}", user_id)),
+            serde_json::to_vec(&serde_json::json!({ "suspended": suspended }))?.into(),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn update_user_your_note(
+        &self,
+        user_id: i64,
+        body: &impl Serialize,
+    ) -> Result<(), crate::Error> {
+        self.send(
+            hyper::Method::PUT,
+            self.url(&format!("users/{}/your_note", user_id)),
+            serde_json::to_vec(body)?.into(),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_instance(&self) -> Result<RespInstanceInfo<'static>, crate::Error> {
+        self.get(self.url("instance")).await
+    }
+
+    pub async fn update_instance(&self, body: &impl Serialize) -> Result<(), crate::Error> {
+        self.send(
+            hyper::Method::PATCH,
+            self.url("instance"),
+            serde_json::to_vec(body)?.into(),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn list_site_modlog_events(
+        &self,
+    ) -> Result<RespList<'static, RespSiteModlogEvent<'static>>, crate::Error> {
+        self.get(self.url("instance/modlog/events")).await
+    }
+
+    pub async fn list_posts(
+        &self,
+        query: &PostListQuery<'_>,
+    ) -> Result<RespList<'static, RespPostListPost<'static>>, crate::Error> {
+        self.get(self.url_with_query("posts", query)?).await
+    }
+
+    pub async fn get_post(
+        &self,
+        post_id: i64,
+        include_your: bool,
+    ) -> Result<RespPostInfo<'static>, crate::Error> {
+        self.get(self.url(&format!(
+            "posts/{}{}",
+            post_id,
+            Self::include_your_query(include_your)
+        )))
+        .await
+    }
+
+    pub async fn list_post_replies(
+        &self,
+        post_id: i64,
+        query: &ReplyListQuery<'_>,
+    ) -> Result<RespList<'static, RespPostCommentInfo<'static>>, crate::Error> {
+        self.get(self.url_with_query(&format!("posts/{}/replies", post_id), query)?)
+            .await
+    }
+
+    /// Creates a post, returning the new post's ID
+    pub async fn create_post(&self, body: &impl Serialize) -> Result<JustID, crate::Error> {
+        self.post(self.url("posts"), body).await
+    }
+
+    pub async fn delete_post(&self, post_id: i64) -> Result<(), crate::Error> {
+        self.send(
+            hyper::Method::DELETE,
+            self.url(&format!("posts/{}", post_id)),
+            hyper::Body::empty(),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn create_post_flag(
+        &self,
+        post_id: i64,
+        body: &impl Serialize,
+    ) -> Result<(), crate::Error> {
+        self.send(
+            hyper::Method::POST,
+            self.url(&format!("posts/{}/flags", post_id)),
+            serde_json::to_vec(body)?.into(),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn like_post(&self, post_id: i64) -> Result<(), crate::Error> {
+        self.send(
+            hyper::Method::PUT,
+            self.url(&format!("posts/{}/your_vote", post_id)),
+            "{}".into(),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn unlike_post(&self, post_id: i64) -> Result<(), crate::Error> {
+        self.send(
+            hyper::Method::DELETE,
+            self.url(&format!("posts/{}/your_vote", post_id)),
+            hyper::Body::empty(),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn list_post_votes(
+        &self,
+        post_id: i64,
+    ) -> Result<RespList<'static, JustUser<'static>>, crate::Error> {
+        self.get(self.url(&format!("posts/{}/votes", post_id)))
+            .await
+    }
+
+    pub async fn vote_poll(&self, post_id: i64, body: &PollVoteBody) -> Result<(), crate::Error> {
+        self.send(
+            hyper::Method::PUT,
+            self.url(&format!("posts/{}/poll/your_vote", post_id)),
+            serde_json::to_vec(body)?.into(),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_comment(
+        &self,
+        comment_id: i64,
+        include_your: bool,
+    ) -> Result<RespCommentInfo<'static>, crate::Error> {
+        self.get(self.url(&format!(
+            "comments/{}{}",
+            comment_id,
+            Self::include_your_query(include_your)
+        )))
+        .await
+    }
+
+    pub async fn list_comment_replies(
+        &self,
+        comment_id: i64,
+        query: &ReplyListQuery<'_>,
+    ) -> Result<RespList<'static, RespPostCommentInfo<'static>>, crate::Error> {
+        self.get(self.url_with_query(&format!("comments/{}/replies", comment_id), query)?)
+            .await
+    }
+
+    pub async fn delete_comment(&self, comment_id: i64) -> Result<(), crate::Error> {
+        self.send(
+            hyper::Method::DELETE,
+            self.url(&format!("comments/{}", comment_id)),
+            hyper::Body::empty(),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn like_comment(&self, comment_id: i64) -> Result<(), crate::Error> {
+        self.send(
+            hyper::Method::PUT,
+            self.url(&format!("comments/{}/your_vote", comment_id)),
+            hyper::Body::empty(),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn unlike_comment(&self, comment_id: i64) -> Result<(), crate::Error> {
+        self.send(
+            hyper::Method::DELETE,
+            self.url(&format!("comments/{}/your_vote", comment_id)),
+            hyper::Body::empty(),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Creates a top-level comment on a post, returning the new comment's ID
+    pub async fn create_comment(
+        &self,
+        post_id: i64,
+        body: &impl Serialize,
+    ) -> Result<JustID, crate::Error> {
+        self.post(self.url(&format!("posts/{}/replies", post_id)), body)
+            .await
+    }
+
+    /// Creates a reply to a comment, returning the new comment's ID
+    pub async fn create_comment_reply(
+        &self,
+        comment_id: i64,
+        body: &impl Serialize,
+    ) -> Result<JustID, crate::Error> {
+        self.post(self.url(&format!("comments/{}/replies", comment_id)), body)
+            .await
+    }
+
+    pub async fn list_communities(
+        &self,
+        query: &CommunityListQuery<'_>,
+    ) -> Result<RespList<'static, RespMinimalCommunityInfo<'static>>, crate::Error> {
+        self.get(self.url_with_query("communities", query)?).await
+    }
+
+    /// Lists the communities the current user moderates, with their pending moderation counts
+    pub async fn list_moderated_communities(
+        &self,
+    ) -> Result<RespList<'static, RespCommunityInfoMaybeYour<'static>>, crate::Error> {
+        self.get(self.url_with_query(
+            "communities",
+            &CommunityListQuery {
+                you_are_moderator: Some(true),
+                include_your: Some(true),
+                ..Default::default()
+            },
+        )?)
+        .await
+    }
+
+    pub async fn create_community(
+        &self,
+        body: &impl Serialize,
+    ) -> Result<CommunitiesCreateResponse, crate::Error> {
+        self.post(self.url("communities"), body).await
+    }
+
+    pub async fn get_community(
+        &self,
+        community_id: i64,
+        include_your: bool,
+    ) -> Result<RespCommunityInfoMaybeYour<'static>, crate::Error> {
+        self.get(self.url(&format!(
+            "communities/{}{}",
+            community_id,
+            Self::include_your_query(include_your)
+        )))
+        .await
+    }
+
+    pub async fn update_community(
+        &self,
+        community_id: i64,
+        body: &impl Serialize,
+    ) -> Result<(), crate::Error> {
+        self.send(
+            hyper::Method::PATCH,
+            self.url(&format!("communities/{}", community_id)),
+            serde_json::to_vec(body)?.into(),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete_community(&self, community_id: i64) -> Result<(), crate::Error> {
+        self.send(
+            hyper::Method::DELETE,
+            self.url(&format!("communities/{}", community_id)),
+            hyper::Body::empty(),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Follows a community, waiting briefly for remote communities to accept
+    pub async fn follow_community(&self, community_id: i64) -> Result<(), crate::Error> {
+        self.send_request(
+            hyper::Request::post(self.url(&format!("communities/{}/follow", community_id)))
+                .header(hyper::header::CONTENT_TYPE, "application/json")
+                .body("{\"try_wait_for_accept\":true}".into())?,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn unfollow_community(&self, community_id: i64) -> Result<(), crate::Error> {
+        self.send(
+            hyper::Method::POST,
+            self.url(&format!("communities/{}/unfollow", community_id)),
+            hyper::Body::empty(),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn list_community_moderators(
+        &self,
+        community_id: i64,
+    ) -> Result<Vec<RespMinimalAuthorInfo<'static>>, crate::Error> {
+        self.get(self.url(&format!("communities/{}/moderators", community_id)))
+            .await
+    }
+
+    pub async fn add_community_moderator(
+        &self,
+        community_id: i64,
+        user_id: i64,
+    ) -> Result<(), crate::Error> {
+        self.send(
+            hyper::Method::PUT,
+            self.url(&format!(
+                "communities/{}/moderators/{}",
+                community_id, user_id
+            )),
+            hyper::Body::empty(),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn remove_community_moderator(
+        &self,
+        community_id: i64,
+        user_id: i64,
+    ) -> Result<(), crate::Error> {
+        self.send(
+            hyper::Method::DELETE,
+            self.url(&format!(
+                "communities/{}/moderators/{}",
+                community_id, user_id
+            )),
+            hyper::Body::empty(),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn list_community_modlog_events(
+        &self,
+        community_id: i64,
+    ) -> Result<RespList<'static, RespCommunityModlogEvent<'static>>, crate::Error> {
+        self.get(self.url(&format!("communities/{}/modlog/events", community_id)))
+            .await
+    }
+
+    pub async fn set_community_post_approved(
+        &self,
+        community_id: i64,
+        post_id: i64,
+        approved: bool,
+    ) -> Result<(), crate::Error> {
+        self.send(
+            hyper::Method::PATCH,
+            self.url(&format!("communities/{}/posts/{}", community_id, post_id)),
+            serde_json::to_vec(&serde_json::json!({ "approved": approved }))?.into(),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn set_community_post_sticky(
+        &self,
+        community_id: i64,
+        post_id: i64,
+        sticky: bool,
+    ) -> Result<(), crate::Error> {
+        self.send(
+            hyper::Method::PATCH,
+            self.url(&format!("communities/{}/posts/{}", community_id, post_id)),
+            serde_json::to_vec(&serde_json::json!({ "sticky": sticky }))?.into(),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_user(
+        &self,
+        user_id: i64,
+        include_your: bool,
+    ) -> Result<RespUserInfo<'static>, crate::Error> {
+        self.get(self.url(&format!(
+            "users/{}{}",
+            user_id,
+            Self::include_your_query(include_your)
+        )))
+        .await
+    }
+
+    /// Deletes a user account, either the current user's own or, for admins, a local user's
+    pub async fn delete_user(&self, user_id: i64) -> Result<(), crate::Error> {
+        self.send(
+            hyper::Method::DELETE,
+            self.url(&format!("users/{}", user_id)),
+            hyper::Body::empty(),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn list_user_things(
+        &self,
+        user_id: i64,
+    ) -> Result<RespList<'static, RespThingInfo<'static>>, crate::Error> {
+        self.get(self.url(&format!("users/{}/things", user_id)))
+            .await
+    }
+
+    pub async fn list_notifications(
+        &self,
+    ) -> Result<RespList<'static, RespNotification<'static>>, crate::Error> {
+        self.get(self.url("users/~me/notifications")).await
+    }
+
+    pub async fn list_flags(
+        &self,
+        query: &FlagListQuery,
+    ) -> Result<RespList<'static, RespFlagInfo<'static>>, crate::Error> {
+        self.get(self.url_with_query("flags", query)?).await
+    }
+
+    /// Dismisses a flag for the community it was sent to
+    pub async fn dismiss_flag(&self, flag_id: i64) -> Result<(), crate::Error> {
+        self.send(
+            hyper::Method::PATCH,
+            self.url(&format!("flags/{}", flag_id)),
+            r#"{"community_dismissed":true}"#.into(),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Blocks a remote object from the whole site, by its URL
+    pub async fn block_object(&self, remote_url: &str) -> Result<(), crate::Error> {
+        self.send(
+            hyper::Method::PUT,
+            self.url(&format!(
+                "objects:blocks/{}",
+                percent_encoding::utf8_percent_encode(
+                    remote_url,
+                    percent_encoding::NON_ALPHANUMERIC
+                )
+            )),
+            hyper::Body::empty(),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Requests a password reset code by email
+    pub async fn create_forgot_password_key(
+        &self,
+        body: &impl Serialize,
+    ) -> Result<(), crate::Error> {
+        self.send(
+            hyper::Method::POST,
+            self.url("forgot_password/keys"),
+            serde_json::to_vec(body)?.into(),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Checks that a password reset code exists and hasn't expired
+    pub async fn check_forgot_password_key(&self, key: &str) -> Result<(), crate::Error> {
+        self.send(
+            hyper::Method::GET,
+            self.url(&format!(
+                "forgot_password/keys/{}",
+                urlencoding::encode(key)
+            )),
+            hyper::Body::empty(),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn reset_forgot_password(
+        &self,
+        key: &str,
+        new_password: &str,
+    ) -> Result<(), crate::Error> {
+        self.send(
+            hyper::Method::POST,
+            self.url(&format!(
+                "forgot_password/keys/{}/reset",
+                urlencoding::encode(key)
+            )),
+            serde_json::to_vec(&serde_json::json!({ "new_password": new_password }))?.into(),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Resolves a community or user by handle or URL, fetching it from its server if needed
+    pub async fn lookup_actors(&self, query: &str) -> Result<Vec<RespLookupResult>, crate::Error> {
+        self.get(self.url(&format!("actors:lookup/{}", urlencoding::encode(query))))
+            .await
+    }
+
+    /// Resolves the URL of any federated object, including posts and comments
+    pub async fn lookup_objects(&self, query: &str) -> Result<Vec<RespLookupResult>, crate::Error> {
+        self.get(self.url(&format!("objects:lookup/{}", urlencoding::encode(query))))
+            .await
+    }
+
+    pub async fn list_invitations(
+        &self,
+        query: &InvitationListQuery<'_>,
+    ) -> Result<RespList<'static, RespInvitationInfo<'static>>, crate::Error> {
+        self.get(self.url_with_query("invitations", query)?).await
+    }
+
+    pub async fn create_invitation(
+        &self,
+    ) -> Result<InvitationsCreateResponse<'static>, crate::Error> {
+        self.send_json(
+            hyper::Method::POST,
+            self.url("invitations"),
+            hyper::Body::empty(),
+        )
+        .await
+    }
+
+    pub async fn delete_invitation(&self, invitation_id: i32) -> Result<(), crate::Error> {
+        self.send(
+            hyper::Method::DELETE,
+            self.url(&format!("invitations/{}", invitation_id)),
+            hyper::Body::empty(),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Uploads a media file, returning its ID for use in `local-media://` URLs
+    pub async fn upload_media(
+        &self,
+        content_type: &str,
+        body: hyper::Body,
+    ) -> Result<String, crate::Error> {
+        let req = hyper::Request::post(self.url("media"))
+            .header(hyper::header::CONTENT_TYPE, content_type)
+            .body(body)?;
+
+        let res = self.send_request(req).await?;
+        let res = self.read_body(res).await?;
+        let res: JustStringID = serde_json::from_slice(&res)?;
+
+        Ok(res.id.to_owned())
+    }
+
+    pub async fn render_markdown(
+        &self,
+        content_markdown: &str,
+    ) -> Result<JustContentHTML<'static>, crate::Error> {
+        self.post(
+            self.url("misc/render_markdown"),
+            &serde_json::json!({ "content_markdown": content_markdown }),
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Backend;
+    use crate::http_client::tests::stub_backend;
+    use crate::http_client::{HttpClient, HttpClientConfig};
+    use crate::routes::get_cookie_map_for_headers;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    fn test_client(read_timeout: Duration) -> HttpClient {
+        HttpClient::new(HttpClientConfig {
+            connect_timeout: Duration::from_secs(1),
+            read_timeout,
+            retries: 0,
+            retry_backoff: Duration::from_millis(10),
+            circuit_failure_threshold: 0,
+            circuit_reset: Duration::from_secs(1),
+        })
+    }
+
+    fn json_response(status: hyper::StatusCode, body: &str) -> hyper::Response<hyper::Body> {
+        hyper::Response::builder()
+            .status(status)
+            .header(hyper::header::CONTENT_TYPE, "application/json")
+            .body(body.to_owned().into())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn get_instance_parses_response() {
+        let host = stub_backend(|req| {
+            if req.method() == hyper::Method::GET && req.uri().path() == "/api/unstable/instance" {
+                json_response(
+                    hyper::StatusCode::OK,
+                    r#"{
+                        "description": {"content_text": "Test instance"},
+                        "software": {"name": "lotide", "version": "0.15.0"},
+                        "signup_allowed": true,
+                        "invitations_enabled": false,
+                        "community_creation_requirement": null,
+                        "invitation_creation_requirement": "site_admin",
+                        "web_push_vapid_key": "key"
+                    }"#,
+                )
+            } else {
+                json_response(hyper::StatusCode::NOT_FOUND, "No such route")
+            }
+        });
+
+        let http_client = test_client(Duration::from_secs(5));
+        let headers = hyper::header::HeaderMap::new();
+        let cookies = Default::default();
+        let backend = Backend::new(&host, &http_client, &headers, &cookies);
+
+        let instance = backend.get_instance().await.unwrap();
+
+        assert_eq!(
+            instance.description.content_text.as_deref(),
+            Some("Test instance")
+        );
+        assert_eq!(instance.software.name, "lotide");
+        assert!(instance.signup_allowed);
+        assert!(!instance.invitations_enabled);
+        assert_eq!(
+            instance.invitation_creation_requirement.as_deref(),
+            Some("site_admin")
+        );
+    }
+
+    #[tokio::test]
+    async fn error_status_becomes_remote_error() {
+        let host = stub_backend(|_| json_response(hyper::StatusCode::FORBIDDEN, "Not a moderator"));
+
+        let http_client = test_client(Duration::from_secs(5));
+        let headers = hyper::header::HeaderMap::new();
+        let cookies = Default::default();
+        let backend = Backend::new(&host, &http_client, &headers, &cookies);
+
+        match backend.delete_community(5).await {
+            Err(crate::Error::RemoteError((status, message))) => {
+                assert_eq!(status, hyper::StatusCode::FORBIDDEN);
+                assert_eq!(message, "Not a moderator");
+            }
+            other => panic!("Expected a remote error, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn forwards_login_token() {
+        let host = stub_backend(|req| {
+            let authorization = req
+                .headers()
+                .get(hyper::header::AUTHORIZATION)
+                .and_then(|value| value.to_str().ok());
+
+            if authorization == Some("Bearer secret") {
+                json_response(
+                    hyper::StatusCode::OK,
+                    r#"{
+                        "user": {
+                            "id": 7,
+                            "is_site_admin": false,
+                            "has_unread_notifications": true,
+                            "has_pending_moderation_actions": false
+                        },
+                        "permissions": {
+                            "create_community": {"allowed": true},
+                            "create_invitation": {"allowed": false}
+                        }
+                    }"#,
+                )
+            } else {
+                json_response(hyper::StatusCode::UNAUTHORIZED, "Login required")
+            }
+        });
+
+        let http_client = test_client(Duration::from_secs(5));
+
+        let headers = hyper::header::HeaderMap::new();
+        let cookies = Default::default();
+        let login = Backend::new(&host, &http_client, &headers, &cookies)
+            .get_login_current()
+            .await
+            .unwrap();
+
+        assert!(login.is_none());
+
+        let mut headers = hyper::header::HeaderMap::new();
+        headers.insert(
+            hyper::header::COOKIE,
+            hyper::header::HeaderValue::from_static("hitideToken=secret"),
+        );
+        let cookies = get_cookie_map_for_headers(&headers).unwrap();
+        let login = Backend::new(&host, &http_client, &headers, &cookies)
+            .get_login_current()
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(login.user.id, 7);
+        assert!(login.user.has_unread_notifications);
+        assert!(login.permissions.create_community.allowed);
+    }
+
+    #[tokio::test]
+    async fn stalled_body_times_out() {
+        let host = stub_backend(|_| {
+            let (sender, body) = hyper::Body::channel();
+
+            // hold the body open without ever finishing it
+            tokio::spawn(async move {
+                tokio::time::delay_for(Duration::from_secs(10)).await;
+                drop(sender);
+            });
+
+            hyper::Response::new(body)
+        });
+
+        let http_client = test_client(Duration::from_millis(200));
+        let headers = hyper::header::HeaderMap::new();
+        let cookies = Default::default();
+        let backend = Backend::new(&host, &http_client, &headers, &cookies);
+
+        match backend.get_instance().await {
+            Err(crate::Error::BackendTimeout) => {}
+            other => panic!("Expected a timeout, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn verify_password_uses_its_own_session() {
+        let logouts = Arc::new(AtomicU32::new(0));
+        let host = stub_backend({
+            let logouts = logouts.clone();
+            move |req| {
+                let authorization = req
+                    .headers()
+                    .get(hyper::header::AUTHORIZATION)
+                    .and_then(|value| value.to_str().ok())
+                    .map(ToOwned::to_owned);
+
+                match (req.method(), req.uri().path()) {
+                    (&hyper::Method::POST, "/api/unstable/logins") if authorization.is_none() => {
+                        json_response(hyper::StatusCode::OK, r#"{"token": "probe"}"#)
+                    }
+                    (&hyper::Method::DELETE, "/api/unstable/logins/~current")
+                        if authorization.as_deref() == Some("Bearer probe") =>
+                    {
+                        logouts.fetch_add(1, Ordering::SeqCst);
+                        json_response(hyper::StatusCode::INTERNAL_SERVER_ERROR, "Database error")
+                    }
+                    _ => json_response(hyper::StatusCode::BAD_REQUEST, "Unexpected request"),
+                }
+            }
+        });
+
+        let http_client = test_client(Duration::from_secs(5));
+
+        let mut headers = hyper::header::HeaderMap::new();
+        headers.insert(
+            hyper::header::COOKIE,
+            hyper::header::HeaderValue::from_static("hitideToken=secret"),
+        );
+        let cookies = get_cookie_map_for_headers(&headers).unwrap();
+        let backend = Backend::new(&host, &http_client, &headers, &cookies);
+
+        match backend.verify_password("user", "password").await {
+            Err(crate::Error::RemoteError((status, _))) => {
+                assert_eq!(status, hyper::StatusCode::INTERNAL_SERVER_ERROR);
+            }
+            other => panic!("Expected the failed logout to be reported, got {:?}", other),
+        }
+        assert_eq!(
+            logouts.load(Ordering::SeqCst),
+            super::PASSWORD_CHECK_LOGOUT_ATTEMPTS
+        );
+    }
+
+    #[tokio::test]
+    async fn verify_password_retries_logout() {
+        let logouts = Arc::new(AtomicU32::new(0));
+        let host = stub_backend({
+            let logouts = logouts.clone();
+            move |req| match (req.method(), req.uri().path()) {
+                (&hyper::Method::POST, "/api/unstable/logins") => {
+                    json_response(hyper::StatusCode::OK, r#"{"token": "probe"}"#)
+                }
+                (&hyper::Method::DELETE, "/api/unstable/logins/~current") => {
+                    if logouts.fetch_add(1, Ordering::SeqCst) == 0 {
+                        json_response(hyper::StatusCode::INTERNAL_SERVER_ERROR, "Database error")
+                    } else {
+                        json_response(hyper::StatusCode::NO_CONTENT, "")
+                    }
+                }
+                _ => json_response(hyper::StatusCode::BAD_REQUEST, "Unexpected request"),
+            }
+        });
+
+        let http_client = test_client(Duration::from_secs(5));
+        let headers = hyper::header::HeaderMap::new();
+        let cookies = Default::default();
+        let backend = Backend::new(&host, &http_client, &headers, &cookies);
+
+        assert!(backend.verify_password("user", "password").await.unwrap());
+        assert_eq!(logouts.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn lists_and_revokes_logins() {
+        let host = stub_backend(|req| match (req.method(), req.uri().path()) {
+            (&hyper::Method::GET, "/api/unstable/logins") => json_response(
+                hyper::StatusCode::OK,
+                r#"{
+                    "items": [
+                        {"id": 1, "created": "2026-10-01T12:00:00Z", "user_agent": "Firefox", "current": true},
+                        {"id": 2, "created": "2026-09-01T12:00:00Z", "user_agent": null}
+                    ],
+                    "next_page": null
+                }"#,
+            ),
+            (&hyper::Method::DELETE, "/api/unstable/logins/2") => {
+                json_response(hyper::StatusCode::NO_CONTENT, "")
+            }
+            _ => json_response(hyper::StatusCode::NOT_FOUND, "No such route"),
+        });
+
+        let http_client = test_client(Duration::from_secs(5));
+        let headers = hyper::header::HeaderMap::new();
+        let cookies = Default::default();
+        let backend = Backend::new(&host, &http_client, &headers, &cookies);
+
+        let sessions = backend.list_logins().await.unwrap().unwrap();
+        assert_eq!(sessions.items.len(), 2);
+        assert!(sessions.items[0].current);
+        assert_eq!(sessions.items[0].user_agent.as_deref(), Some("Firefox"));
+        assert!(!sessions.items[1].current);
+        assert!(sessions.items[1].user_agent.is_none());
+
+        backend.delete_login(2).await.unwrap();
+
+        match backend.delete_login(3).await {
+            Err(crate::Error::RemoteError((status, _))) => {
+                assert_eq!(status, hyper::StatusCode::NOT_FOUND);
+            }
+            other => panic!("Expected a remote error, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn missing_login_list_is_unsupported() {
+        let host = stub_backend(|_| json_response(hyper::StatusCode::NOT_FOUND, "No such route"));
+
+        let http_client = test_client(Duration::from_secs(5));
+        let headers = hyper::header::HeaderMap::new();
+        let cookies = Default::default();
+        let backend = Backend::new(&host, &http_client, &headers, &cookies);
+
+        assert!(backend.list_logins().await.unwrap().is_none());
+    }
+}
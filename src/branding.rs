@@ -0,0 +1,89 @@
+use crate::config::Config;
+use std::path::Path;
+
+pub const LOGO_FILE_NAME: &str = "site-logo";
+pub const FAVICON_FILE_NAME: &str = "favicon";
+
+/// A file loaded at startup, served from `/static`
+#[derive(Debug)]
+pub struct StaticFile {
+    pub content: hyper::body::Bytes,
+    pub content_type: &'static str,
+}
+
+impl StaticFile {
+    fn load(path: &Path) -> Result<Self, std::io::Error> {
+        let content_type = match path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase())
+            .as_deref()
+        {
+            Some("svg") => "image/svg+xml",
+            Some("png") => "image/png",
+            Some("jpg") | Some("jpeg") => "image/jpeg",
+            Some("gif") => "image/gif",
+            Some("webp") => "image/webp",
+            Some("ico") => "image/x-icon",
+            _ => "application/octet-stream",
+        };
+
+        Ok(Self {
+            content: std::fs::read(path)?.into(),
+            content_type,
+        })
+    }
+}
+
+/// Instance-specific name, images, styling and links shown on every page
+#[derive(Debug)]
+pub struct Branding {
+    pub site_name: String,
+    pub logo: Option<StaticFile>,
+    pub favicon: Option<StaticFile>,
+    pub custom_css: Option<String>,
+    pub footer_links: Vec<(String, String)>,
+}
+
+/// Parses links written as `Label=URL`, separated by `;`
+fn parse_footer_links(src: &str) -> Vec<(String, String)> {
+    src.split(';')
+        .map(|item| item.trim())
+        .filter(|item| !item.is_empty())
+        .filter_map(|item| match item.split_once('=') {
+            Some((label, url)) => Some((label.trim().to_owned(), url.trim().to_owned())),
+            None => {
+                log::warn!("Ignoring footer link without a URL: {}", item);
+                None
+            }
+        })
+        .collect()
+}
+
+impl Branding {
+    pub fn load(config: &Config) -> Result<Self, std::io::Error> {
+        Ok(Self {
+            site_name: config.site_name.clone(),
+            logo: config
+                .logo_path
+                .as_deref()
+                .map(StaticFile::load)
+                .transpose()?,
+            favicon: config
+                .favicon_path
+                .as_deref()
+                .map(StaticFile::load)
+                .transpose()?,
+            custom_css: config
+                .custom_css_path
+                .as_deref()
+                .map(std::fs::read_to_string)
+                .transpose()?,
+            footer_links: config
+                .footer_links
+                .as_deref()
+                .map(parse_footer_links)
+                .unwrap_or_default(),
+        })
+    }
+}
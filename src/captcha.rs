@@ -0,0 +1,625 @@
+use crate::lang;
+use crate::signing::SigningKey;
+use rand::Rng;
+use serde_derive::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::Mutex;
+
+pub const TOKEN_FIELD: &str = "captcha_token";
+pub const ANSWER_FIELD: &str = "captcha_answer";
+/// Hidden field that people won't fill in, but bots filling every field will
+pub const HONEYPOT_FIELD: &str = "website";
+
+const LIFETIME_SECS: i64 = 60 * 10;
+
+const IMAGE_LENGTH: usize = 5;
+pub const IMAGE_WIDTH: usize = 200;
+pub const IMAGE_HEIGHT: usize = 70;
+/// Pixels per glyph grid unit
+const GLYPH_SCALE: f32 = 7.0;
+/// Distance between points drawn along a line, in pixels
+const LINE_STEP: f32 = 0.5;
+
+type Stroke = &'static [(f32, f32)];
+
+/// Strokes for each character that may appear in an image, on a 4x6 grid.
+///
+/// Characters easily confused with each other, like `0` and `O`, are left out.
+const GLYPHS: &[(char, &[Stroke])] = &[
+    (
+        '2',
+        &[&[
+            (0.0, 1.0),
+            (1.0, 0.0),
+            (3.0, 0.0),
+            (4.0, 1.0),
+            (4.0, 2.0),
+            (0.0, 6.0),
+            (4.0, 6.0),
+        ]],
+    ),
+    (
+        '3',
+        &[&[
+            (0.0, 0.0),
+            (4.0, 0.0),
+            (2.0, 2.5),
+            (3.5, 3.0),
+            (4.0, 4.5),
+            (3.0, 6.0),
+            (0.0, 6.0),
+        ]],
+    ),
+    ('4', &[&[(3.0, 6.0), (3.0, 0.0), (0.0, 4.0), (4.0, 4.0)]]),
+    (
+        '5',
+        &[&[
+            (4.0, 0.0),
+            (0.0, 0.0),
+            (0.0, 2.5),
+            (3.0, 2.5),
+            (4.0, 3.5),
+            (4.0, 5.0),
+            (3.0, 6.0),
+            (0.0, 6.0),
+        ]],
+    ),
+    (
+        '6',
+        &[&[
+            (4.0, 0.0),
+            (1.0, 0.0),
+            (0.0, 2.0),
+            (0.0, 5.0),
+            (1.0, 6.0),
+            (3.0, 6.0),
+            (4.0, 5.0),
+            (4.0, 3.5),
+            (3.0, 2.5),
+            (0.0, 2.5),
+        ]],
+    ),
+    ('7', &[&[(0.0, 0.0), (4.0, 0.0), (1.0, 6.0)]]),
+    (
+        '8',
+        &[&[
+            (1.0, 0.0),
+            (3.0, 0.0),
+            (4.0, 1.0),
+            (4.0, 2.0),
+            (3.0, 3.0),
+            (1.0, 3.0),
+            (0.0, 4.0),
+            (0.0, 5.0),
+            (1.0, 6.0),
+            (3.0, 6.0),
+            (4.0, 5.0),
+            (4.0, 4.0),
+            (3.0, 3.0),
+            (1.0, 3.0),
+            (0.0, 2.0),
+            (0.0, 1.0),
+            (1.0, 0.0),
+        ]],
+    ),
+    (
+        '9',
+        &[&[
+            (4.0, 3.0),
+            (1.0, 3.0),
+            (0.0, 2.0),
+            (0.0, 1.0),
+            (1.0, 0.0),
+            (3.0, 0.0),
+            (4.0, 1.0),
+            (4.0, 5.0),
+            (3.0, 6.0),
+            (0.0, 6.0),
+        ]],
+    ),
+    (
+        'A',
+        &[
+            &[(0.0, 6.0), (2.0, 0.0), (4.0, 6.0)],
+            &[(1.0, 3.5), (3.0, 3.5)],
+        ],
+    ),
+    (
+        'C',
+        &[&[
+            (4.0, 0.5),
+            (3.0, 0.0),
+            (1.0, 0.0),
+            (0.0, 1.0),
+            (0.0, 5.0),
+            (1.0, 6.0),
+            (3.0, 6.0),
+            (4.0, 5.5),
+        ]],
+    ),
+    (
+        'E',
+        &[
+            &[(4.0, 0.0), (0.0, 0.0), (0.0, 6.0), (4.0, 6.0)],
+            &[(0.0, 3.0), (3.0, 3.0)],
+        ],
+    ),
+    (
+        'F',
+        &[
+            &[(4.0, 0.0), (0.0, 0.0), (0.0, 6.0)],
+            &[(0.0, 3.0), (3.0, 3.0)],
+        ],
+    ),
+    (
+        'H',
+        &[
+            &[(0.0, 0.0), (0.0, 6.0)],
+            &[(4.0, 0.0), (4.0, 6.0)],
+            &[(0.0, 3.0), (4.0, 3.0)],
+        ],
+    ),
+    (
+        'K',
+        &[
+            &[(0.0, 0.0), (0.0, 6.0)],
+            &[(4.0, 0.0), (0.0, 3.5)],
+            &[(1.5, 2.5), (4.0, 6.0)],
+        ],
+    ),
+    ('L', &[&[(0.0, 0.0), (0.0, 6.0), (4.0, 6.0)]]),
+    (
+        'P',
+        &[&[
+            (0.0, 6.0),
+            (0.0, 0.0),
+            (3.0, 0.0),
+            (4.0, 1.0),
+            (4.0, 2.0),
+            (3.0, 3.0),
+            (0.0, 3.0),
+        ]],
+    ),
+    (
+        'R',
+        &[
+            &[
+                (0.0, 6.0),
+                (0.0, 0.0),
+                (3.0, 0.0),
+                (4.0, 1.0),
+                (4.0, 2.0),
+                (3.0, 3.0),
+                (0.0, 3.0),
+            ],
+            &[(2.0, 3.0), (4.0, 6.0)],
+        ],
+    ),
+    ('T', &[&[(0.0, 0.0), (4.0, 0.0)], &[(2.0, 0.0), (2.0, 6.0)]]),
+    ('X', &[&[(0.0, 0.0), (4.0, 6.0)], &[(4.0, 0.0), (0.0, 6.0)]]),
+];
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptchaKind {
+    /// Distorted characters to type in
+    Image,
+    /// A sum to solve, for anyone who can't read the image
+    Arithmetic,
+}
+
+impl Default for CaptchaKind {
+    fn default() -> Self {
+        CaptchaKind::Image
+    }
+}
+
+impl CaptchaKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CaptchaKind::Image => "image",
+            CaptchaKind::Arithmetic => "arithmetic",
+        }
+    }
+
+    /// The kind to offer as an alternative
+    pub fn other(&self) -> Self {
+        match self {
+            CaptchaKind::Image => CaptchaKind::Arithmetic,
+            CaptchaKind::Arithmetic => CaptchaKind::Image,
+        }
+    }
+}
+
+pub enum Prompt {
+    /// PNG image, as a `data:` URL
+    Image(String),
+    Arithmetic(u32, u32),
+}
+
+pub struct Challenge {
+    pub kind: CaptchaKind,
+    pub token: String,
+    pub prompt: Prompt,
+}
+
+/// Contents of a challenge token. The answer is only included as a signature, so it can't be read
+/// from the token.
+#[derive(Deserialize, Serialize)]
+struct TokenData {
+    kind: CaptchaKind,
+    nonce: String,
+    expires: i64,
+    answer: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CaptchaError {
+    Incorrect,
+    Expired,
+}
+
+impl CaptchaError {
+    pub fn lang_key(&self) -> lang::LangKey<'static> {
+        match self {
+            CaptchaError::Incorrect => lang::captcha_incorrect(),
+            CaptchaError::Expired => lang::captcha_expired(),
+        }
+    }
+}
+
+/// Normalizes a typed answer so that case and spacing don't matter
+fn normalize_answer(answer: &str) -> String {
+    answer
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(|c| c.to_uppercase())
+        .collect()
+}
+
+/// Grayscale drawing surface, holding how much ink covers each pixel
+struct Canvas {
+    ink: Vec<f32>,
+    /// Wave applied to everything drawn, as amplitude, frequency and phase for each axis
+    wave: [(f32, f32, f32); 2],
+}
+
+impl Canvas {
+    fn new(rng: &mut impl Rng) -> Self {
+        Self {
+            ink: vec![0.0; IMAGE_WIDTH * IMAGE_HEIGHT],
+            wave: [
+                (
+                    rng.gen_range(2.0..4.0),
+                    rng.gen_range(0.05..0.12),
+                    rng.gen_range(0.0..std::f32::consts::TAU),
+                ),
+                (
+                    rng.gen_range(3.0..6.0),
+                    rng.gen_range(0.03..0.08),
+                    rng.gen_range(0.0..std::f32::consts::TAU),
+                ),
+            ],
+        }
+    }
+
+    fn distort(&self, x: f32, y: f32) -> (f32, f32) {
+        let [(amp_x, freq_x, phase_x), (amp_y, freq_y, phase_y)] = self.wave;
+
+        (
+            x + amp_x * (y * freq_x + phase_x).sin(),
+            y + amp_y * (x * freq_y + phase_y).sin(),
+        )
+    }
+
+    /// Inks a round dot, with softened edges
+    fn dot(&mut self, x: f32, y: f32, radius: f32) {
+        let min_x = (x - radius - 1.0).max(0.0) as usize;
+        let max_x = ((x + radius + 1.0).max(0.0) as usize).min(IMAGE_WIDTH - 1);
+        let min_y = (y - radius - 1.0).max(0.0) as usize;
+        let max_y = ((y + radius + 1.0).max(0.0) as usize).min(IMAGE_HEIGHT - 1);
+
+        for py in min_y..=max_y {
+            for px in min_x..=max_x {
+                let dist = (px as f32 + 0.5 - x).hypot(py as f32 + 0.5 - y);
+                let coverage = (radius + 0.5 - dist).min(1.0);
+
+                let ink = &mut self.ink[py * IMAGE_WIDTH + px];
+                if coverage > *ink {
+                    *ink = coverage;
+                }
+            }
+        }
+    }
+
+    /// Draws a line through the distortion, so that straight strokes come out curved
+    fn line(&mut self, from: (f32, f32), to: (f32, f32), radius: f32) {
+        let length = (to.0 - from.0).hypot(to.1 - from.1);
+        let steps = (length / LINE_STEP).ceil().max(1.0) as usize;
+
+        for step in 0..=steps {
+            let t = step as f32 / steps as f32;
+            let (x, y) = self.distort(from.0 + (to.0 - from.0) * t, from.1 + (to.1 - from.1) * t);
+
+            self.dot(x, y, radius);
+        }
+    }
+
+    /// Grayscale pixel values, dark ink on a light background with some grain
+    fn pixels(&self, rng: &mut impl Rng) -> Vec<u8> {
+        self.ink
+            .iter()
+            .map(|ink| {
+                let value = 240.0 - ink * 200.0 + rng.gen_range(-20.0..20.0);
+
+                value.clamp(0.0, 255.0) as u8
+            })
+            .collect()
+    }
+}
+
+/// Draws `text` as distorted strokes, with noise of the same kind across it
+fn render_image(rng: &mut impl Rng, text: &str) -> Vec<u8> {
+    let mut canvas = Canvas::new(rng);
+
+    let advance = (IMAGE_WIDTH as f32 - 20.0) / IMAGE_LENGTH as f32;
+
+    for (idx, c) in text.chars().enumerate() {
+        let strokes = GLYPHS
+            .iter()
+            .find(|(glyph, _)| *glyph == c)
+            .map(|(_, strokes)| *strokes)
+            .unwrap_or(&[]);
+
+        let scale = GLYPH_SCALE * rng.gen_range(0.85..1.15);
+        let angle: f32 = rng.gen_range(-0.3..0.3);
+        let (sin, cos) = angle.sin_cos();
+        let center_x = 10.0 + advance * (idx as f32 + 0.5) + rng.gen_range(-3.0..3.0);
+        let center_y = IMAGE_HEIGHT as f32 / 2.0 + rng.gen_range(-6.0..6.0);
+        let radius = rng.gen_range(1.2..1.7);
+
+        for stroke in strokes {
+            let points: Vec<_> = stroke
+                .iter()
+                .map(|(x, y)| {
+                    let x = (x - 2.0 + rng.gen_range(-0.3..0.3)) * scale;
+                    let y = (y - 3.0 + rng.gen_range(-0.3..0.3)) * scale;
+
+                    (center_x + x * cos - y * sin, center_y + x * sin + y * cos)
+                })
+                .collect();
+
+            for pair in points.windows(2) {
+                canvas.line(pair[0], pair[1], radius);
+            }
+        }
+    }
+
+    // noise is drawn with the same pen as the text, so it can't be filtered out by width
+    for _ in 0..2 {
+        let start = rng.gen_range(0.0..IMAGE_HEIGHT as f32);
+        let end = rng.gen_range(0.0..IMAGE_HEIGHT as f32);
+        let control = (
+            rng.gen_range(0.0..IMAGE_WIDTH as f32),
+            rng.gen_range(0.0..IMAGE_HEIGHT as f32),
+        );
+        let radius = rng.gen_range(1.0..1.6);
+
+        let point = |t: f32| {
+            let u = 1.0 - t;
+
+            (
+                2.0 * u * t * control.0 + t * t * IMAGE_WIDTH as f32,
+                u * u * start + 2.0 * u * t * control.1 + t * t * end,
+            )
+        };
+
+        for step in 0..20 {
+            canvas.line(
+                point(step as f32 / 20.0),
+                point((step + 1) as f32 / 20.0),
+                radius,
+            );
+        }
+    }
+
+    // loose strokes about the size of a glyph's, to get in the way of splitting out characters
+    for _ in 0..4 {
+        let from = (
+            rng.gen_range(0.0..IMAGE_WIDTH as f32),
+            rng.gen_range(0.0..IMAGE_HEIGHT as f32),
+        );
+        let length = rng.gen_range(8.0..20.0);
+        let angle: f32 = rng.gen_range(0.0..std::f32::consts::TAU);
+        let to = (from.0 + length * angle.cos(), from.1 + length * angle.sin());
+
+        canvas.line(from, to, rng.gen_range(1.0..1.6));
+    }
+
+    for _ in 0..120 {
+        canvas.dot(
+            rng.gen_range(0.0..IMAGE_WIDTH as f32),
+            rng.gen_range(0.0..IMAGE_HEIGHT as f32),
+            rng.gen_range(0.3..1.0),
+        );
+    }
+
+    encode_png(IMAGE_WIDTH, IMAGE_HEIGHT, &canvas.pixels(rng))
+}
+
+fn write_png_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    let mut crc = flate2::Crc::new();
+    crc.update(kind);
+    crc.update(data);
+
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    out.extend_from_slice(&crc.sum().to_be_bytes());
+}
+
+/// Encodes 8-bit grayscale pixels as a PNG file
+fn encode_png(width: usize, height: usize, pixels: &[u8]) -> Vec<u8> {
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    // bit depth 8, grayscale, default compression and filtering, no interlacing
+    header.extend_from_slice(&[8, 0, 0, 0, 0]);
+
+    let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::best());
+    for row in pixels.chunks(width) {
+        // each row starts with its filter type, and none is used
+        encoder.write_all(&[0]).unwrap();
+        encoder.write_all(row).unwrap();
+    }
+    let data = encoder.finish().expect("writing to a Vec can't fail");
+
+    let mut out = b"\x89PNG\r\n\x1a\n".to_vec();
+    write_png_chunk(&mut out, b"IHDR", &header);
+    write_png_chunk(&mut out, b"IDAT", &data);
+    write_png_chunk(&mut out, b"IEND", &[]);
+
+    out
+}
+
+/// Generates and checks challenges, which are self-contained apart from remembering which tokens
+/// have already been used
+pub struct Captcha {
+    key: SigningKey,
+    enabled: bool,
+    used: Mutex<HashMap<String, i64>>,
+}
+
+impl Captcha {
+    pub fn new(key: SigningKey, enabled: bool) -> Self {
+        Self {
+            key,
+            enabled,
+            used: Default::default(),
+        }
+    }
+
+    fn answer_signature(&self, nonce: &str, answer: &str) -> String {
+        self.key
+            .signature(&format!("captcha:{}:{}", nonce, normalize_answer(answer)))
+    }
+
+    /// Creates a new challenge, or `None` if CAPTCHAs are disabled
+    pub fn generate(&self, kind: CaptchaKind) -> Option<Challenge> {
+        if !self.enabled {
+            return None;
+        }
+
+        let mut rng = rand::thread_rng();
+
+        let (answer, prompt) = match kind {
+            CaptchaKind::Image => {
+                let text: String = (0..IMAGE_LENGTH)
+                    .map(|_| GLYPHS[rng.gen_range(0..GLYPHS.len())].0)
+                    .collect();
+                let png = render_image(&mut rng, &text);
+
+                (
+                    text,
+                    Prompt::Image(format!("data:image/png;base64,{}", base64::encode(&png))),
+                )
+            }
+            CaptchaKind::Arithmetic => {
+                let a = rng.gen_range(1..=20);
+                let b = rng.gen_range(1..=20);
+
+                ((a + b).to_string(), Prompt::Arithmetic(a, b))
+            }
+        };
+
+        let nonce = {
+            let mut bytes = [0; 16];
+            rng.fill(&mut bytes);
+            base64::encode_config(bytes, base64::URL_SAFE_NO_PAD)
+        };
+
+        let token = self
+            .key
+            .sign_json(&TokenData {
+                kind,
+                answer: self.answer_signature(&nonce, &answer),
+                nonce,
+                expires: chrono::Utc::now().timestamp() + LIFETIME_SECS,
+            })
+            .expect("token data always serializes");
+
+        Some(Challenge {
+            kind,
+            token,
+            prompt,
+        })
+    }
+
+    /// Kind of challenge that was submitted in a form, to show the same kind again
+    pub fn submitted_kind(&self, form: &HashMap<Cow<'_, str>, serde_json::Value>) -> CaptchaKind {
+        form.get(TOKEN_FIELD)
+            .and_then(|token| token.as_str())
+            .and_then(|token| self.key.verify_json::<TokenData>(token))
+            .map(|data| data.kind)
+            .unwrap_or_default()
+    }
+
+    /// Checks the CAPTCHA and honeypot fields in a submitted form, removing them so they aren't
+    /// passed on to the backend.
+    ///
+    /// Each challenge can only be attempted once.
+    pub fn verify(
+        &self,
+        form: &mut HashMap<Cow<'_, str>, serde_json::Value>,
+    ) -> Result<(), CaptchaError> {
+        let token = form.remove(TOKEN_FIELD);
+        let answer = form.remove(ANSWER_FIELD);
+        let honeypot = form.remove(HONEYPOT_FIELD);
+
+        if honeypot
+            .as_ref()
+            .and_then(|value| value.as_str())
+            .map(|value| !value.is_empty())
+            .unwrap_or(false)
+        {
+            return Err(CaptchaError::Incorrect);
+        }
+
+        if !self.enabled {
+            return Ok(());
+        }
+
+        let data: TokenData = token
+            .as_ref()
+            .and_then(|token| token.as_str())
+            .and_then(|token| self.key.verify_json(token))
+            .ok_or(CaptchaError::Incorrect)?;
+
+        let now = chrono::Utc::now().timestamp();
+        if data.expires < now {
+            return Err(CaptchaError::Expired);
+        }
+
+        {
+            let mut used = self.used.lock().unwrap();
+            used.retain(|_, expires| *expires >= now);
+
+            if used.insert(data.nonce.clone(), data.expires).is_some() {
+                return Err(CaptchaError::Expired);
+            }
+        }
+
+        let answer = answer
+            .as_ref()
+            .and_then(|answer| answer.as_str())
+            .unwrap_or("");
+
+        if self.answer_signature(&data.nonce, answer) == data.answer {
+            Ok(())
+        } else {
+            Err(CaptchaError::Incorrect)
+        }
+    }
+}
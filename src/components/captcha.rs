@@ -0,0 +1,56 @@
+use crate::captcha::{
+    Challenge, Prompt, ANSWER_FIELD, HONEYPOT_FIELD, IMAGE_HEIGHT, IMAGE_WIDTH, TOKEN_FIELD,
+};
+use crate::lang;
+
+/// Form fields for a CAPTCHA challenge, if there is one, and the honeypot field
+#[render::component]
+pub fn CaptchaField<'a>(
+    challenge: Option<&'a Challenge>,
+    switch_href: String,
+    lang: &'a crate::Translator,
+) {
+    render::rsx! {
+        <>
+            <div class={"honeypot"} aria-hidden={"true"}>
+                <label>
+                    {lang.tr(&lang::CAPTCHA_HONEYPOT)}{" "}
+                    <input type={"text"} name={HONEYPOT_FIELD} tabindex={"-1"} autocomplete={"off"} />
+                </label>
+            </div>
+            {
+                challenge.map(|challenge| {
+                    let (prompt, image, switch_label) = match &challenge.prompt {
+                        Prompt::Image(url) => (
+                            lang.tr(&lang::CAPTCHA_IMAGE_PROMPT),
+                            Some(url.as_str()),
+                            lang.tr(&lang::CAPTCHA_USE_ARITHMETIC),
+                        ),
+                        Prompt::Arithmetic(a, b) => (
+                            lang.tr(&lang::captcha_arithmetic(*a, *b)),
+                            None,
+                            lang.tr(&lang::CAPTCHA_USE_IMAGE),
+                        ),
+                    };
+
+                    render::rsx! {
+                        <div class={"captcha"}>
+                            <input type={"hidden"} name={TOKEN_FIELD} value={challenge.token.as_str()} />
+                            {
+                                image.map(|url| render::rsx! {
+                                    <img class={"captchaImage"} src={url} alt={lang.tr(&lang::CAPTCHA_IMAGE_PROMPT)} width={IMAGE_WIDTH.to_string()} height={IMAGE_HEIGHT.to_string()} />
+                                })
+                            }
+                            <label>
+                                {prompt}{" "}
+                                <input type={"text"} name={ANSWER_FIELD} required={""} autocomplete={"off"} />
+                            </label>
+                            {" "}
+                            <a href={switch_href}>{switch_label}</a>
+                        </div>
+                    }
+                })
+            }
+        </>
+    }
+}
@@ -1,3 +1,4 @@
+pub mod captcha;
 pub mod timeago;
 
 use std::borrow::{Borrow, Cow};
@@ -13,6 +14,7 @@ use crate::resp_types::{
 use crate::util::{abbreviate_link, author_is_me};
 use crate::PageBaseData;
 
+pub use captcha::CaptchaField;
 pub use timeago::TimeAgo;
 
 #[render::component]
@@ -64,14 +66,27 @@ pub fn Comment<'a>(
                     <div class={"commentContent"}>
                         {
                             sensitive_hide.then(|| {
-                                render::rsx! {
-                                    <details>
-                                        <summary>
-                                            {hitide_icons::SENSITIVE.img_aria_hidden()}
-                                            {lang.tr(&lang::SENSITIVE)}
-                                        </summary>
-                                        <ContentView src={comment} />
-                                    </details>
+                                let summary = render::rsx! {
+                                    <summary>
+                                        {hitide_icons::SENSITIVE.img_aria_hidden()}
+                                        {lang.tr(&lang::SENSITIVE)}
+                                    </summary>
+                                };
+
+                                if base_data.preferences.expand_sensitive {
+                                    render::rsx! {
+                                        <details open={"open"}>
+                                            {summary}
+                                            <ContentView src={comment} />
+                                        </details>
+                                    }
+                                } else {
+                                    render::rsx! {
+                                        <details>
+                                            {summary}
+                                            <ContentView src={comment} />
+                                        </details>
+                                    }
                                 }
                             })
                         }
@@ -88,7 +103,7 @@ pub fn Comment<'a>(
                                 <div>
                                     <strong>{lang.tr(&lang::COMMENT_ATTACHMENT_PREFIX)}</strong>
                                     {" "}
-                                    <em><a href={href.as_ref()}>{abbreviate_link(href)}{" ↗"}</a></em>
+                                    <em><a href={href.as_ref()} target={base_data.preferences.external_link_target()}>{abbreviate_link(href)}{" ↗"}</a></em>
                                 </div>
                             }
                         })
@@ -108,7 +123,7 @@ pub fn Comment<'a>(
                             if !comment.local {
                                 if let Some(remote_url) = &comment.as_ref().remote_url {
                                     Some(render::rsx! {
-                                        <a href={remote_url.as_ref()}>{lang.tr(&lang::remote_url()).into_owned()}</a>
+                                        <a href={remote_url.as_ref()} target={base_data.preferences.external_link_target()}>{lang.tr(&lang::remote_url()).into_owned()}</a>
                                     })
                                 } else {
                                     None
@@ -187,7 +202,15 @@ impl<'community> render::Render for CommunityLink<'community> {
             })
             .render_into(writer)
         } else {
-            let href = format!("/communities/{}", community.id);
+            let href = if community.local {
+                format!("/c/{}", urlencoding::encode(&community.name))
+            } else {
+                format!(
+                    "/c/{}@{}",
+                    urlencoding::encode(&community.name),
+                    community.host
+                )
+            };
             (render::rsx! {
                 <a href={&href}>
                 {
@@ -380,6 +403,17 @@ pub fn HTPageAdvanced<'a, HeadItems: render::Render, Children: render::Render>(
         </>
     };
 
+    let (theme_stylesheet, theme_media) = base_data.preferences.theme.stylesheet();
+
+    let branding = &base_data.branding;
+    let assets = &base_data.assets;
+
+    let languages: Vec<_> = crate::translations::current()
+        .native_names()
+        .into_iter()
+        .map(|(id, name)| (id.to_string(), name, id == lang.primary_language()))
+        .collect();
+
     render::rsx! {
         <>
             <render::html::HTML5Doctype />
@@ -393,11 +427,19 @@ pub fn HTPageAdvanced<'a, HeadItems: render::Render, Children: render::Render>(
                 <head>
                     <meta charset={"utf-8"} />
                     <meta name={"viewport"} content={"width=device-width, initial-scale=1"} />
-                    <link rel={"stylesheet"} href={"/static/main.css"} />
+                    <link rel={"stylesheet"} href={assets.url("main.css")} />
+                    <link rel={"stylesheet"} href={assets.url(theme_stylesheet)} media={theme_media} />
+                    {
+                        branding.favicon.as_ref().map(|favicon| {
+                            render::rsx! {
+                                <link rel={"icon"} href={assets.url(crate::branding::FAVICON_FILE_NAME)} type={favicon.content_type} />
+                            }
+                        })
+                    }
                     <title>{title}</title>
                     {head_items}
                 </head>
-                <body>
+                <body class={base_data.preferences.density.as_str()}>
                     <header class={"mainHeader"}>
                         <nav aria-label={"Main Navigation"} class={"left"}>
                             <details class={"leftLinksMobile"}>
@@ -406,7 +448,16 @@ pub fn HTPageAdvanced<'a, HeadItems: render::Render, Children: render::Render>(
                                     {left_links.clone()}
                                 </div>
                             </details>
-                            <a href={"/"} class={"siteName"}>{"lotide"}</a>
+                            <a href={"/"} class={"siteName"}>
+                                {
+                                    branding.logo.as_ref().map(|_| {
+                                        render::rsx! {
+                                            <img src={assets.url(crate::branding::LOGO_FILE_NAME)} alt={""} class={"siteLogo"} />
+                                        }
+                                    })
+                                }
+                                {branding.site_name.as_str()}
+                            </a>
                             <div class={"actionList leftLinks"}>
                                 {left_links}
                             </div>
@@ -453,6 +504,32 @@ pub fn HTPageAdvanced<'a, HeadItems: render::Render, Children: render::Render>(
                                                     }
                                                 })
                                             }
+                                            <details class={"accountSwitcher"}>
+                                                <summary>
+                                                    {
+                                                        base_data.accounts.iter()
+                                                            .find(|account| account.user_id == login.user.id)
+                                                            .map(|account| Cow::Borrowed(account.username.as_str()))
+                                                            .unwrap_or_else(|| lang.tr(&lang::ACCOUNTS_SWITCHER))
+                                                    }
+                                                </summary>
+                                                <div>
+                                                    {
+                                                        base_data.accounts.iter()
+                                                            .filter(|account| account.user_id != login.user.id)
+                                                            .map(|account| {
+                                                                render::rsx! {
+                                                                    <form method={"POST"} action={"/accounts/switch"}>
+                                                                        <input type={"hidden"} name={"user_id"} value={account.user_id.to_string()} />
+                                                                        <button type={"submit"} class={"linkButton"}>{account.username.as_str()}</button>
+                                                                    </form>
+                                                                }
+                                                            })
+                                                            .collect::<Vec<_>>()
+                                                    }
+                                                    <a href={"/login"}>{lang.tr(&lang::ACCOUNTS_ADD)}</a>
+                                                </div>
+                                            </details>
                                             <form method={"POST"} action={"/logout"} class={"inline"}>
                                                 <button type={"submit"} class={"iconbutton"}>
                                                     {hitide_icons::LOGOUT.img(lang.tr(&lang::logout()).into_owned())}
@@ -476,6 +553,43 @@ pub fn HTPageAdvanced<'a, HeadItems: render::Render, Children: render::Render>(
                     <main>
                         {children}
                     </main>
+                    <footer class={"mainFooter"}>
+                        <form method={"POST"} action={"/language"}>
+                            <label>
+                                {lang.tr(&lang::LANGUAGE)}{" "}
+                                <select name={"lang"}>
+                                    <option value={""}>{lang.tr(&lang::LANGUAGE_AUTOMATIC)}</option>
+                                    {
+                                        languages.into_iter().map(|(id, name, selected)| {
+                                            if selected {
+                                                render::rsx! {
+                                                    <option value={id} selected={""}>{name}</option>
+                                                }
+                                            } else {
+                                                render::rsx! {
+                                                    <option value={id}>{name}</option>
+                                                }
+                                            }
+                                        })
+                                        .collect::<Vec<_>>()
+                                    }
+                                </select>
+                            </label>
+                            {" "}
+                            <button type={"submit"}>{lang.tr(&lang::SAVE)}</button>
+                        </form>
+                        <div class={"actionList"}>
+                            <a href={"/preferences"}>{lang.tr(&lang::PREFERENCES)}</a>
+                            {
+                                branding.footer_links.iter().map(|(label, url)| {
+                                    render::rsx! {
+                                        <a href={url.as_str()}>{label.as_str()}</a>
+                                    }
+                                })
+                                .collect::<Vec<_>>()
+                            }
+                        </div>
+                    </footer>
                 </body>
             </html>
         </>
@@ -487,11 +601,12 @@ pub fn PostItem<'a>(
     post: &'a RespPostListPost<'a>,
     in_community: bool,
     no_user: bool,
+    base_data: &'a PageBaseData,
     lang: &'a crate::Translator,
 ) {
     render::rsx! {
         <li class={if post.as_ref().sticky { "sticky" } else { "" }}>
-            <PostItemContent post in_community no_user lang />
+            <PostItemContent post in_community no_user base_data lang />
         </li>
     }
 }
@@ -500,6 +615,7 @@ pub struct PostItemContent<'a> {
     post: &'a RespPostListPost<'a>,
     in_community: bool,
     no_user: bool,
+    base_data: &'a PageBaseData,
     lang: &'a crate::Translator,
 }
 
@@ -509,6 +625,7 @@ impl<'a> render::Render for PostItemContent<'a> {
             post,
             in_community,
             no_user,
+            base_data,
             lang,
         } = self;
 
@@ -524,7 +641,7 @@ impl<'a> render::Render for PostItemContent<'a> {
                     {
                         post.as_ref().href.as_ref().map(|href| {
                             render::rsx! {
-                                <em><a href={href.as_ref()}>{abbreviate_link(href)}{" ↗"}</a></em>
+                                <em><a href={href.as_ref()} target={base_data.preferences.external_link_target()}>{abbreviate_link(href)}{" ↗"}</a></em>
                             }
                         })
                     }
@@ -563,6 +680,7 @@ impl<'a> render::Render for PostItemContent<'a> {
 }
 
 pub struct ThingItem<'a> {
+    pub base_data: &'a PageBaseData,
     pub lang: &'a crate::Translator,
     pub thing: &'a RespThingInfo<'a>,
 }
@@ -573,7 +691,7 @@ impl<'a> render::Render for ThingItem<'a> {
 
         match self.thing {
             RespThingInfo::Post(post) => {
-                (PostItem { post, in_community: false, no_user: true, lang: self.lang }).render_into(writer)
+                (PostItem { post, in_community: false, no_user: true, base_data: self.base_data, lang: self.lang }).render_into(writer)
             },
             RespThingInfo::Comment(comment) => {
                 (render::rsx! {
@@ -622,7 +740,11 @@ impl<'user> render::Render for UserLink<'user> {
         match self.user {
             None => "[unknown]".render_into(writer),
             Some(user) => {
-                let href = format!("/users/{}", user.id);
+                let href = if user.local {
+                    format!("/u/{}", urlencoding::encode(&user.username))
+                } else {
+                    format!("/u/{}@{}", urlencoding::encode(&user.username), user.host)
+                };
                 (render::rsx! {
                     <a href={&href}>
                         {
@@ -815,6 +937,16 @@ pub fn BoolCheckbox<'a>(name: &'a str, value: bool) {
     }
 }
 
+/// Request ID for an error page, so it can be quoted when reporting a problem
+#[render::component]
+pub fn RequestIdNote<'a>(headers: &'a hyper::header::HeaderMap, lang: &'a crate::Translator) {
+    crate::access_log::request_id_for_headers(headers).map(|request_id| {
+        render::rsx! {
+            <p><small>{lang.tr(&lang::error_request_id(request_id)).into_owned()}</small></p>
+        }
+    })
+}
+
 pub struct NotificationItem<'a> {
     pub notification: &'a RespNotification<'a>,
     pub lang: &'a crate::Translator,
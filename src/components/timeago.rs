@@ -1,5 +1,40 @@
 use crate::lang;
 
+fn relative_key(since: &chrono::DateTime<chrono::offset::FixedOffset>) -> lang::LangKey<'static> {
+    let duration = chrono::offset::Utc::now().signed_duration_since(*since);
+
+    let days = duration.num_days();
+    if days >= 365 {
+        lang::timeago_years(days / 365)
+    } else if days >= 30 {
+        lang::timeago_months(days / 30)
+    } else if days >= 7 {
+        lang::timeago_weeks(days / 7)
+    } else if days > 0 {
+        lang::timeago_days(days)
+    } else {
+        let hours = duration.num_hours();
+        if hours > 0 {
+            lang::timeago_hours(hours)
+        } else {
+            let minutes = duration.num_minutes();
+            if minutes > 0 {
+                lang::timeago_minutes(minutes)
+            } else {
+                let seconds = duration.num_seconds();
+
+                if seconds > 0 {
+                    lang::timeago_seconds(seconds)
+                } else if seconds < 0 {
+                    lang::timeago_future()
+                } else {
+                    lang::timeago_now()
+                }
+            }
+        }
+    }
+}
+
 #[render::component]
 pub fn TimeAgo<'a>(
     since: chrono::DateTime<chrono::offset::FixedOffset>,
@@ -7,48 +42,16 @@ pub fn TimeAgo<'a>(
 ) {
     let since_str = since.to_rfc3339();
 
-    let duration = chrono::offset::Utc::now().signed_duration_since(since);
+    let relative = lang.tr(&relative_key(&since)).into_owned();
+    let absolute = lang.format_datetime(&since);
 
-    let arg = {
-        let weeks = duration.num_weeks();
-        if weeks > 52 {
-            let years = ((weeks as f32) / 52.18).floor() as u32;
-            lang::timeago_years(years)
-        } else if weeks > 5 {
-            let months = (f32::from(weeks as i8) / 4.35).floor() as u8;
-            lang::timeago_months(months)
-        } else if weeks > 0 {
-            lang::timeago_weeks(weeks)
-        } else {
-            let days = duration.num_days();
-            if days > 0 {
-                lang::timeago_days(days)
-            } else {
-                let hours = duration.num_hours();
-                if hours > 0 {
-                    lang::timeago_hours(hours)
-                } else {
-                    let minutes = duration.num_minutes();
-                    if minutes > 0 {
-                        lang::timeago_minutes(minutes)
-                    } else {
-                        let seconds = duration.num_seconds();
-
-                        if seconds > 0 {
-                            lang::timeago_seconds(seconds)
-                        } else if seconds < 0 {
-                            lang::timeago_future()
-                        } else {
-                            lang::timeago_now()
-                        }
-                    }
-                }
-            }
-        }
+    // whichever form isn't shown is still available on hover
+    let (text, title) = match lang.time_display() {
+        lang::TimeDisplay::Relative => (relative, absolute),
+        lang::TimeDisplay::Absolute => (absolute, relative),
     };
-    let text = lang.tr(&arg).into_owned();
 
     render::rsx! {
-        <span title={since_str}>{text}</span>
+        <time datetime={since_str} title={title}>{text}</time>
     }
 }
@@ -0,0 +1,204 @@
+use std::io::Write;
+
+/// Content encodings supported for responses, in order of preference
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Encoding {
+    Brotli,
+    Gzip,
+}
+
+impl Encoding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Encoding::Brotli => "br",
+            Encoding::Gzip => "gzip",
+        }
+    }
+
+    /// Compresses as much as possible, for content that is only compressed once
+    pub fn compress(&self, content: &[u8]) -> Vec<u8> {
+        self.compress_with_level(content, 11, flate2::Compression::best())
+    }
+
+    /// Compresses quickly enough to do for every response
+    pub fn compress_fast(&self, content: &[u8]) -> Vec<u8> {
+        self.compress_with_level(content, 5, flate2::Compression::default())
+    }
+
+    fn compress_with_level(
+        &self,
+        content: &[u8],
+        brotli_quality: u32,
+        gzip_level: flate2::Compression,
+    ) -> Vec<u8> {
+        match self {
+            Encoding::Brotli => {
+                let mut encoder =
+                    brotli::CompressorWriter::new(Vec::new(), 4096, brotli_quality, 22);
+                encoder
+                    .write_all(content)
+                    .expect("writing to a Vec can't fail");
+                encoder.into_inner()
+            }
+            Encoding::Gzip => {
+                let mut encoder = flate2::write::GzEncoder::new(Vec::new(), gzip_level);
+                encoder
+                    .write_all(content)
+                    .expect("writing to a Vec can't fail");
+                encoder.finish().expect("writing to a Vec can't fail")
+            }
+        }
+    }
+}
+
+/// Picks the encoding to use based on the `Accept-Encoding` request header
+pub fn negotiate(headers: &hyper::header::HeaderMap) -> Option<Encoding> {
+    let mut brotli = false;
+    let mut gzip = false;
+
+    for value in headers.get_all(hyper::header::ACCEPT_ENCODING) {
+        let value = match value.to_str() {
+            Ok(value) => value,
+            Err(_) => continue,
+        };
+
+        for item in value.split(',') {
+            let mut parts = item.split(';');
+            let name = parts.next().unwrap_or("").trim();
+
+            let rejected = parts.any(|param| {
+                let param = param.trim();
+                match param.strip_prefix("q=") {
+                    Some(q) => q.trim().parse::<f32>().map(|q| q <= 0.0).unwrap_or(false),
+                    None => false,
+                }
+            });
+            if rejected {
+                continue;
+            }
+
+            if name.eq_ignore_ascii_case("br") {
+                brotli = true;
+            } else if name.eq_ignore_ascii_case("gzip") || name == "*" {
+                gzip = true;
+            }
+        }
+    }
+
+    if brotli {
+        Some(Encoding::Brotli)
+    } else if gzip {
+        Some(Encoding::Gzip)
+    } else {
+        None
+    }
+}
+
+/// Whether responses of this type are text, and so should declare a charset
+fn is_text(essence: &str) -> bool {
+    essence.starts_with("text/")
+}
+
+/// Whether responses of this type are worth compressing
+fn is_compressible(essence: &str) -> bool {
+    is_text(essence)
+        || matches!(
+            essence,
+            "image/svg+xml"
+                | "application/atom+xml"
+                | "application/rss+xml"
+                | "application/xml"
+                | "application/json"
+        )
+}
+
+/// Adds `charset=utf-8` to text responses, and compresses the body if the client supports it and
+/// it's at least `min_bytes` long
+pub async fn compress_response(
+    res: hyper::Response<hyper::Body>,
+    req_headers: &hyper::header::HeaderMap,
+    min_bytes: usize,
+) -> Result<hyper::Response<hyper::Body>, hyper::Error> {
+    let (mut parts, body) = res.into_parts();
+
+    let essence = match parts
+        .headers
+        .get(hyper::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+    {
+        None => return Ok(hyper::Response::from_parts(parts, body)),
+        Some(content_type) => {
+            let essence = content_type
+                .split(';')
+                .next()
+                .unwrap_or("")
+                .trim()
+                .to_ascii_lowercase();
+
+            if is_text(&essence) && !content_type.contains("charset=") {
+                let value = format!("{}; charset=utf-8", content_type);
+                parts.headers.insert(
+                    hyper::header::CONTENT_TYPE,
+                    hyper::header::HeaderValue::from_str(&value)
+                        .expect("value was already a valid header"),
+                );
+            }
+
+            essence
+        }
+    };
+
+    if !is_compressible(&essence)
+        || parts.headers.contains_key(hyper::header::CONTENT_ENCODING)
+        || parts.status == hyper::StatusCode::NO_CONTENT
+        || parts.status == hyper::StatusCode::NOT_MODIFIED
+    {
+        return Ok(hyper::Response::from_parts(parts, body));
+    }
+
+    let varies_by_encoding = parts
+        .headers
+        .get_all(hyper::header::VARY)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|name| name.trim().eq_ignore_ascii_case("accept-encoding"));
+    if !varies_by_encoding {
+        parts.headers.append(
+            hyper::header::VARY,
+            hyper::header::HeaderValue::from_static("Accept-Encoding"),
+        );
+    }
+
+    let encoding = match negotiate(req_headers) {
+        Some(encoding) => encoding,
+        None => return Ok(hyper::Response::from_parts(parts, body)),
+    };
+
+    let body = hyper::body::to_bytes(body).await?;
+    if body.len() < min_bytes {
+        return Ok(hyper::Response::from_parts(parts, body.into()));
+    }
+
+    parts.headers.insert(
+        hyper::header::CONTENT_ENCODING,
+        hyper::header::HeaderValue::from_static(encoding.as_str()),
+    );
+    parts.headers.remove(hyper::header::CONTENT_LENGTH);
+
+    // the compressed bytes differ from what a strong ETag identifies
+    if let Some(etag) = parts.headers.get(hyper::header::ETAG) {
+        if !etag.as_bytes().starts_with(b"W/") {
+            let mut weak = b"W/".to_vec();
+            weak.extend_from_slice(etag.as_bytes());
+            let weak = hyper::header::HeaderValue::from_bytes(&weak)
+                .expect("value was already a valid header");
+            parts.headers.insert(hyper::header::ETAG, weak);
+        }
+    }
+
+    Ok(hyper::Response::from_parts(
+        parts,
+        encoding.compress_fast(&body).into(),
+    ))
+}
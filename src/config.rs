@@ -1,10 +1,105 @@
+use crate::access_log::LogFormat;
+use crate::http_client::HttpClientConfig;
+use crate::page_cache::PageCacheConfig;
+use crate::rate_limit::{BucketConfig, RateLimitConfig};
+use crate::security_headers::SecurityHeadersConfig;
+use crate::signing::SigningKey;
 use serde_derive::Deserialize;
 use std::collections::HashMap;
+use std::time::Duration;
+
+fn default_site_name() -> String {
+    "lotide".to_owned()
+}
 
 fn default_port() -> u16 {
     4333
 }
 
+fn default_log_format() -> LogFormat {
+    LogFormat::Text
+}
+
+fn default_backend_connect_timeout_ms() -> u64 {
+    5000
+}
+
+fn default_backend_read_timeout_ms() -> u64 {
+    30000
+}
+
+fn default_backend_retries() -> u32 {
+    2
+}
+
+fn default_backend_retry_backoff_ms() -> u64 {
+    100
+}
+
+fn default_backend_circuit_failure_threshold() -> u32 {
+    5
+}
+
+fn default_backend_circuit_reset_ms() -> u64 {
+    30000
+}
+
+fn default_frame_options() -> String {
+    "DENY".to_owned()
+}
+
+fn default_referrer_policy() -> String {
+    "same-origin".to_owned()
+}
+
+fn default_rate_limit_login_burst() -> u32 {
+    10
+}
+
+fn default_rate_limit_login_per_minute() -> u32 {
+    5
+}
+
+fn default_rate_limit_signup_burst() -> u32 {
+    3
+}
+
+fn default_rate_limit_signup_per_minute() -> u32 {
+    1
+}
+
+fn default_rate_limit_forgot_password_burst() -> u32 {
+    3
+}
+
+fn default_rate_limit_forgot_password_per_minute() -> u32 {
+    1
+}
+
+fn default_rate_limit_submit_burst() -> u32 {
+    10
+}
+
+fn default_rate_limit_submit_per_minute() -> u32 {
+    10
+}
+
+fn default_compression_min_bytes() -> usize {
+    1024
+}
+
+fn default_page_cache_max_entry_bytes() -> usize {
+    1024 * 1024
+}
+
+fn default_page_cache_ttl_list_secs() -> u64 {
+    30
+}
+
+fn default_page_cache_ttl_post_secs() -> u64 {
+    15
+}
+
 #[derive(Deserialize)]
 pub struct Config {
     pub backend_host: String,
@@ -13,6 +108,107 @@ pub struct Config {
 
     #[serde(default = "default_port")]
     pub port: u16,
+
+    #[serde(default = "default_log_format")]
+    pub log_format: LogFormat,
+
+    pub translations_dir: Option<std::path::PathBuf>,
+
+    pub cookie_secret: Option<String>,
+
+    #[serde(default = "default_site_name")]
+    pub site_name: String,
+
+    pub logo_path: Option<std::path::PathBuf>,
+
+    pub favicon_path: Option<std::path::PathBuf>,
+
+    pub custom_css_path: Option<std::path::PathBuf>,
+
+    pub footer_links: Option<String>,
+
+    #[serde(default = "default_backend_connect_timeout_ms")]
+    pub backend_connect_timeout_ms: u64,
+
+    #[serde(default = "default_backend_read_timeout_ms")]
+    pub backend_read_timeout_ms: u64,
+
+    #[serde(default = "default_backend_retries")]
+    pub backend_retries: u32,
+
+    #[serde(default = "default_backend_retry_backoff_ms")]
+    pub backend_retry_backoff_ms: u64,
+
+    #[serde(default = "default_backend_circuit_failure_threshold")]
+    pub backend_circuit_failure_threshold: u32,
+
+    #[serde(default = "default_backend_circuit_reset_ms")]
+    pub backend_circuit_reset_ms: u64,
+
+    pub content_security_policy: Option<String>,
+
+    pub csp_img_src: Option<String>,
+
+    #[serde(default)]
+    pub csp_report_only: bool,
+
+    pub csp_report_uri: Option<String>,
+
+    #[serde(default = "default_frame_options")]
+    pub frame_options: String,
+
+    #[serde(default = "default_referrer_policy")]
+    pub referrer_policy: String,
+
+    #[serde(default)]
+    pub captcha: bool,
+
+    pub trusted_proxies: Option<String>,
+
+    #[serde(default = "default_rate_limit_login_burst")]
+    pub rate_limit_login_burst: u32,
+
+    #[serde(default = "default_rate_limit_login_per_minute")]
+    pub rate_limit_login_per_minute: u32,
+
+    #[serde(default = "default_rate_limit_signup_burst")]
+    pub rate_limit_signup_burst: u32,
+
+    #[serde(default = "default_rate_limit_signup_per_minute")]
+    pub rate_limit_signup_per_minute: u32,
+
+    #[serde(default = "default_rate_limit_forgot_password_burst")]
+    pub rate_limit_forgot_password_burst: u32,
+
+    #[serde(default = "default_rate_limit_forgot_password_per_minute")]
+    pub rate_limit_forgot_password_per_minute: u32,
+
+    #[serde(default = "default_rate_limit_submit_burst")]
+    pub rate_limit_submit_burst: u32,
+
+    #[serde(default = "default_rate_limit_submit_per_minute")]
+    pub rate_limit_submit_per_minute: u32,
+
+    #[serde(default = "default_compression_min_bytes")]
+    pub compression_min_bytes: usize,
+
+    #[serde(default)]
+    pub page_cache_max_bytes: usize,
+
+    #[serde(default = "default_page_cache_max_entry_bytes")]
+    pub page_cache_max_entry_bytes: usize,
+
+    #[serde(default = "default_page_cache_ttl_list_secs")]
+    pub page_cache_ttl_all_secs: u64,
+
+    #[serde(default = "default_page_cache_ttl_list_secs")]
+    pub page_cache_ttl_local_secs: u64,
+
+    #[serde(default = "default_page_cache_ttl_list_secs")]
+    pub page_cache_ttl_community_secs: u64,
+
+    #[serde(default = "default_page_cache_ttl_post_secs")]
+    pub page_cache_ttl_post_secs: u64,
 }
 
 impl Config {
@@ -33,6 +229,82 @@ impl Config {
 
         src.try_into()
     }
+
+    pub fn http_client_config(&self) -> HttpClientConfig {
+        HttpClientConfig {
+            connect_timeout: Duration::from_millis(self.backend_connect_timeout_ms),
+            read_timeout: Duration::from_millis(self.backend_read_timeout_ms),
+            retries: self.backend_retries,
+            retry_backoff: Duration::from_millis(self.backend_retry_backoff_ms),
+            circuit_failure_threshold: self.backend_circuit_failure_threshold,
+            circuit_reset: Duration::from_millis(self.backend_circuit_reset_ms),
+        }
+    }
+
+    pub fn cookie_signing_key(&self) -> SigningKey {
+        match &self.cookie_secret {
+            Some(secret) => SigningKey::new(secret.as_bytes()),
+            None => {
+                log::warn!(
+                    "COOKIE_SECRET is not set, signed cookies will be invalidated on restart"
+                );
+                SigningKey::generate()
+            }
+        }
+    }
+
+    pub fn security_headers_config(&self) -> SecurityHeadersConfig {
+        SecurityHeadersConfig {
+            content_security_policy: self.content_security_policy.clone(),
+            img_src: self.csp_img_src.clone(),
+            report_only: self.csp_report_only,
+            report_uri: self.csp_report_uri.clone(),
+            frame_options: self.frame_options.clone(),
+            referrer_policy: self.referrer_policy.clone(),
+        }
+    }
+
+    pub fn rate_limit_config(&self) -> Result<RateLimitConfig, String> {
+        let trusted_proxies = match &self.trusted_proxies {
+            None => Vec::new(),
+            Some(src) => src
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|item| !item.is_empty())
+                .map(str::parse)
+                .collect::<Result<_, _>>()?,
+        };
+
+        Ok(RateLimitConfig {
+            login: BucketConfig {
+                burst: self.rate_limit_login_burst,
+                per_minute: self.rate_limit_login_per_minute,
+            },
+            signup: BucketConfig {
+                burst: self.rate_limit_signup_burst,
+                per_minute: self.rate_limit_signup_per_minute,
+            },
+            forgot_password: BucketConfig {
+                burst: self.rate_limit_forgot_password_burst,
+                per_minute: self.rate_limit_forgot_password_per_minute,
+            },
+            submit: BucketConfig {
+                burst: self.rate_limit_submit_burst,
+                per_minute: self.rate_limit_submit_per_minute,
+            },
+            trusted_proxies,
+        })
+    }
+
+    pub fn page_cache_config(&self) -> PageCacheConfig {
+        PageCacheConfig {
+            max_bytes: self.page_cache_max_bytes,
+            max_entry_bytes: self.page_cache_max_entry_bytes,
+            ttl_all: Duration::from_secs(self.page_cache_ttl_all_secs),
+            ttl_local: Duration::from_secs(self.page_cache_ttl_local_secs),
+            ttl_community: Duration::from_secs(self.page_cache_ttl_community_secs),
+            ttl_post: Duration::from_secs(self.page_cache_ttl_post_secs),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -0,0 +1,348 @@
+//! Builds a user's "download my data" archive, streaming it as content is fetched.
+//!
+//! Every post and comment is included as the JSON returned by the API along with a Markdown
+//! rendering. Anything the backend can't provide is left out and noted in the README.
+
+use crate::backend::Backend;
+use crate::query_types::CommunityListQuery;
+use crate::resp_types::RespList;
+use crate::zip_writer::ZipWriter;
+use serde_derive::Deserialize;
+use std::fmt::Write;
+
+#[derive(Deserialize)]
+struct ExportCommunity {
+    name: String,
+    host: String,
+}
+
+#[derive(Deserialize)]
+struct ExportPostRef {
+    id: i64,
+    title: String,
+}
+
+#[derive(Deserialize)]
+struct ExportPost {
+    title: String,
+    href: Option<String>,
+    created: String,
+    community: ExportCommunity,
+    remote_url: Option<String>,
+    content_markdown: Option<String>,
+    content_text: Option<String>,
+    content_html: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ExportAttachment {
+    url: String,
+}
+
+#[derive(Deserialize)]
+struct ExportComment {
+    id: i64,
+    created: String,
+    post: Option<ExportPostRef>,
+    remote_url: Option<String>,
+    content_markdown: Option<String>,
+    content_text: Option<String>,
+    content_html: Option<String>,
+    #[serde(default)]
+    attachments: Vec<ExportAttachment>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ExportThing {
+    Post { id: i64 },
+    Comment { id: i64 },
+}
+
+/// Picks the closest thing to the original source, since Markdown can also hold plain text and
+/// HTML
+fn content_source<'a>(
+    markdown: &'a Option<String>,
+    text: &'a Option<String>,
+    html: &'a Option<String>,
+) -> &'a str {
+    markdown
+        .as_deref()
+        .or(text.as_deref())
+        .or(html.as_deref())
+        .unwrap_or("")
+}
+
+fn write_attachments(dest: &mut String, attachments: &[&str]) {
+    if attachments.is_empty() {
+        return;
+    }
+
+    dest.push_str("\n## Attachments\n\n");
+    for url in attachments {
+        writeln!(dest, "- <{}>", url).unwrap();
+    }
+}
+
+fn post_markdown(post: &ExportPost) -> String {
+    let mut dest = String::new();
+
+    writeln!(dest, "# {}\n", post.title).unwrap();
+    writeln!(
+        dest,
+        "- Community: {}@{}",
+        post.community.name, post.community.host
+    )
+    .unwrap();
+    writeln!(dest, "- Posted: {}", post.created).unwrap();
+    if let Some(remote_url) = &post.remote_url {
+        writeln!(dest, "- Original: <{}>", remote_url).unwrap();
+    }
+
+    let content = content_source(
+        &post.content_markdown,
+        &post.content_text,
+        &post.content_html,
+    );
+    if !content.is_empty() {
+        writeln!(dest, "\n{}", content).unwrap();
+    }
+
+    write_attachments(
+        &mut dest,
+        &post.href.as_deref().into_iter().collect::<Vec<_>>(),
+    );
+
+    dest
+}
+
+fn comment_markdown(comment: &ExportComment) -> String {
+    let mut dest = String::new();
+
+    match &comment.post {
+        Some(post) => writeln!(dest, "# Comment on \"{}\"\n", post.title).unwrap(),
+        None => writeln!(dest, "# Comment {}\n", comment.id).unwrap(),
+    }
+    if let Some(post) = &comment.post {
+        writeln!(dest, "- Post ID: {}", post.id).unwrap();
+    }
+    writeln!(dest, "- Posted: {}", comment.created).unwrap();
+    if let Some(remote_url) = &comment.remote_url {
+        writeln!(dest, "- Original: <{}>", remote_url).unwrap();
+    }
+
+    writeln!(
+        dest,
+        "\n{}",
+        content_source(
+            &comment.content_markdown,
+            &comment.content_text,
+            &comment.content_html
+        )
+    )
+    .unwrap();
+
+    write_attachments(
+        &mut dest,
+        &comment
+            .attachments
+            .iter()
+            .map(|attachment| attachment.url.as_str())
+            .collect::<Vec<_>>(),
+    );
+
+    dest
+}
+
+fn page_path(path: &str, page: Option<&str>) -> String {
+    match page {
+        None => path.to_owned(),
+        Some(page) => format!(
+            "{}{}page={}",
+            path,
+            if path.contains('?') { '&' } else { '?' },
+            urlencoding::encode(page)
+        ),
+    }
+}
+
+/// Fetches a single page of a list, or `None` if the backend doesn't provide it
+async fn fetch_list_page(
+    backend: &Backend<'_>,
+    path: &str,
+    page: Option<&str>,
+) -> Result<Option<RespList<'static, serde_json::Value>>, crate::Error> {
+    match backend.get_json(&page_path(path, page)).await {
+        Ok(value) => Ok(Some(serde_json::from_value(value)?)),
+        Err(crate::Error::RemoteError((hyper::StatusCode::NOT_FOUND, _))) => Ok(None),
+        Err(other) => Err(other),
+    }
+}
+
+/// Fetches every page of a list, or `None` if the backend doesn't provide it
+async fn fetch_list_all(
+    backend: &Backend<'_>,
+    path: &str,
+) -> Result<Option<Vec<serde_json::Value>>, crate::Error> {
+    let mut items = Vec::new();
+    let mut page: Option<String> = None;
+
+    loop {
+        let list = match fetch_list_page(backend, path, page.as_deref()).await? {
+            Some(list) => list,
+            None => return Ok(None),
+        };
+
+        items.extend(list.items);
+
+        match list.next_page {
+            Some(next_page) => page = Some(next_page.into_owned()),
+            None => return Ok(Some(items)),
+        }
+    }
+}
+
+/// Fetches the full version of an item, falling back to the summary if it can't be found
+async fn fetch_full(
+    backend: &Backend<'_>,
+    path: &str,
+    summary: serde_json::Value,
+) -> Result<serde_json::Value, crate::Error> {
+    match backend.get_json(path).await {
+        Ok(value) => Ok(value),
+        Err(crate::Error::RemoteError((hyper::StatusCode::NOT_FOUND, _))) => Ok(summary),
+        Err(other) => Err(other),
+    }
+}
+
+struct ExportWriter<'a> {
+    zip: ZipWriter,
+    sender: &'a mut hyper::body::Sender,
+}
+
+impl<'a> ExportWriter<'a> {
+    async fn add_file(&mut self, name: &str, content: &[u8]) -> Result<(), crate::Error> {
+        let data = self.zip.add_file(name, content)?;
+        self.sender.send_data(data).await?;
+
+        Ok(())
+    }
+
+    async fn add_json(
+        &mut self,
+        name: &str,
+        value: &serde_json::Value,
+    ) -> Result<(), crate::Error> {
+        self.add_file(name, &serde_json::to_vec_pretty(value)?)
+            .await
+    }
+
+    async fn finish(self) -> Result<(), crate::Error> {
+        let data = self.zip.finish();
+        self.sender.send_data(data).await?;
+
+        Ok(())
+    }
+}
+
+/// Writes the archive for `user_id` to `sender`, with `backend` logged in as that user
+pub async fn write_export(
+    backend: &Backend<'_>,
+    user_id: i64,
+    sender: &mut hyper::body::Sender,
+) -> Result<(), crate::Error> {
+    let mut writer = ExportWriter {
+        zip: ZipWriter::new(chrono::Utc::now().naive_utc()),
+        sender,
+    };
+
+    let mut readme = String::new();
+    writeln!(
+        readme,
+        "Data export for user {}, created {}.\n",
+        user_id,
+        chrono::Utc::now().to_rfc3339()
+    )
+    .unwrap();
+    readme.push_str("- profile.json: your profile\n");
+    readme.push_str("- posts/: your posts, as JSON and Markdown\n");
+    readme.push_str("- comments/: your comments, as JSON and Markdown\n");
+
+    let profile = backend.get_json(&format!("users/{}", user_id)).await?;
+    writer.add_json("profile.json", &profile).await?;
+
+    let things_path = format!("users/{}/things", user_id);
+    let mut page: Option<String> = None;
+    loop {
+        let list = fetch_list_page(backend, &things_path, page.as_deref())
+            .await?
+            .ok_or(crate::Error::InternalStrStatic(
+                "Backend does not provide user things",
+            ))?;
+
+        for item in list.items {
+            let thing: ExportThing = serde::Deserialize::deserialize(&item)?;
+            match thing {
+                ExportThing::Post { id } => {
+                    let value = fetch_full(backend, &format!("posts/{}", id), item).await?;
+                    let post: ExportPost = serde::Deserialize::deserialize(&value)?;
+
+                    writer
+                        .add_json(&format!("posts/{}.json", id), &value)
+                        .await?;
+                    writer
+                        .add_file(&format!("posts/{}.md", id), post_markdown(&post).as_bytes())
+                        .await?;
+                }
+                ExportThing::Comment { id } => {
+                    let value = fetch_full(backend, &format!("comments/{}", id), item).await?;
+                    let comment: ExportComment = serde::Deserialize::deserialize(&value)?;
+
+                    writer
+                        .add_json(&format!("comments/{}.json", id), &value)
+                        .await?;
+                    writer
+                        .add_file(
+                            &format!("comments/{}.md", id),
+                            comment_markdown(&comment).as_bytes(),
+                        )
+                        .await?;
+                }
+            }
+        }
+
+        match list.next_page {
+            Some(next_page) => page = Some(next_page.into_owned()),
+            None => break,
+        }
+    }
+
+    let follows_path = format!(
+        "communities?{}",
+        serde_urlencoded::to_string(&CommunityListQuery {
+            your_follow_accepted: Some(true),
+            include_your: Some(true),
+            ..Default::default()
+        })?
+    );
+    match fetch_list_all(backend, &follows_path).await? {
+        Some(items) => {
+            writer.add_json("follows.json", &items.into()).await?;
+            readme.push_str("- follows.json: communities you follow\n");
+        }
+        None => {
+            readme.push_str(
+                "- follows.json is not included, since this server does not list the communities you follow\n",
+            );
+        }
+    }
+
+    // Notes are only returned alongside each user, and the API has no way to list them
+    readme.push_str(
+        "- Your notes on other users are not included, since this server has no way to list them\n",
+    );
+
+    writer.add_file("README.md", readme.as_bytes()).await?;
+
+    writer.finish().await
+}
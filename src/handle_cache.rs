@@ -0,0 +1,63 @@
+//! Remembers what readable handles from `/c/` and `/u/` links resolved to, so that following a
+//! handle link doesn't need a backend lookup every time.
+
+use crate::resp_types::RespLookupKind;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How long a resolved handle is used before looking it up again
+const TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Most entries kept at once. Expired ones are only swept once there are this many, and if none
+/// have expired the oldest is evicted.
+const MAX_ENTRIES: usize = 10000;
+
+struct Entry {
+    id: i64,
+    resolved: Instant,
+}
+
+#[derive(Default)]
+pub struct HandleCache {
+    entries: Mutex<HashMap<(RespLookupKind, String), Entry>>,
+}
+
+impl HandleCache {
+    pub fn get(&self, kind: RespLookupKind, handle: &str) -> Option<i64> {
+        let entries = self.entries.lock().unwrap();
+
+        entries
+            .get(&(kind, handle.to_owned()))
+            .filter(|entry| entry.resolved.elapsed() < TTL)
+            .map(|entry| entry.id)
+    }
+
+    pub fn insert(&self, kind: RespLookupKind, handle: String, id: i64) {
+        let mut entries = self.entries.lock().unwrap();
+
+        let key = (kind, handle);
+
+        if entries.len() >= MAX_ENTRIES && !entries.contains_key(&key) {
+            entries.retain(|_, entry| entry.resolved.elapsed() < TTL);
+
+            if entries.len() >= MAX_ENTRIES {
+                let oldest = entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.resolved)
+                    .map(|(key, _)| key.clone());
+                if let Some(oldest) = oldest {
+                    entries.remove(&oldest);
+                }
+            }
+        }
+
+        entries.insert(
+            key,
+            Entry {
+                id,
+                resolved: Instant::now(),
+            },
+        );
+    }
+}
@@ -0,0 +1,18 @@
+// Included by both build.rs and assets.rs, so that assets built ahead of time and those added at
+// startup are named the same way.
+
+/// Inserts a hash of `content` before the extension of `name`
+fn hashed_name(name: &str, content: &[u8]) -> String {
+    use sha2::Digest;
+
+    let digest = sha2::Sha256::digest(content);
+    let hash: String = digest[..8]
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+
+    match name.rsplit_once('.') {
+        Some((stem, ext)) => format!("{}.{}.{}", stem, hash, ext),
+        None => format!("{}.{}", name, hash),
+    }
+}
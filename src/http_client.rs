@@ -0,0 +1,411 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+type Inner = hyper::Client<hyper_tls::HttpsConnector<hyper::client::HttpConnector>>;
+
+#[derive(Clone, Debug)]
+pub struct HttpClientConfig {
+    pub connect_timeout: Duration,
+    pub read_timeout: Duration,
+    pub retries: u32,
+    pub retry_backoff: Duration,
+    pub circuit_failure_threshold: u32,
+    pub circuit_reset: Duration,
+}
+
+struct CircuitState {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+    probing: bool,
+}
+
+/// Tracks consecutive backend failures so that requests can fail fast while the backend is down
+struct CircuitBreaker {
+    failure_threshold: u32,
+    reset: Duration,
+    state: Mutex<CircuitState>,
+}
+
+impl CircuitBreaker {
+    fn new(failure_threshold: u32, reset: Duration) -> Self {
+        Self {
+            failure_threshold,
+            reset,
+            state: Mutex::new(CircuitState {
+                consecutive_failures: 0,
+                open_until: None,
+                probing: false,
+            }),
+        }
+    }
+
+    fn allows_request(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        match state.open_until {
+            Some(open_until) => {
+                let now = Instant::now();
+                if now < open_until {
+                    return false;
+                }
+
+                // once the reset period has passed, let a single request through to check if it's
+                // back up, keeping the circuit open for the rest until that one reports back, or
+                // for another reset period in case it never does
+                state.probing = true;
+                state.open_until = Some(now + self.reset);
+
+                true
+            }
+            None => true,
+        }
+    }
+
+    fn record_success(&self) {
+        let mut state = self.state.lock().unwrap();
+        if state.open_until.is_some() {
+            log::info!("Backend is reachable again, closing circuit");
+        }
+
+        state.consecutive_failures = 0;
+        state.open_until = None;
+        state.probing = false;
+    }
+
+    fn record_failure(&self) {
+        if self.failure_threshold == 0 {
+            return;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        state.consecutive_failures = state.consecutive_failures.saturating_add(1);
+
+        if state.consecutive_failures >= self.failure_threshold {
+            if state.probing {
+                log::warn!("Backend is still failing, keeping circuit open");
+            } else if state.open_until.is_none() {
+                log::warn!(
+                    "Backend failed {} times in a row, opening circuit",
+                    state.consecutive_failures
+                );
+            }
+
+            state.open_until = Some(Instant::now() + self.reset);
+            state.probing = false;
+        }
+    }
+}
+
+fn is_unavailable_status(status: hyper::StatusCode) -> bool {
+    matches!(
+        status,
+        hyper::StatusCode::BAD_GATEWAY
+            | hyper::StatusCode::SERVICE_UNAVAILABLE
+            | hyper::StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+/// Client used for all requests to the backend
+pub struct HttpClient {
+    inner: Inner,
+    config: HttpClientConfig,
+    circuit: CircuitBreaker,
+}
+
+impl HttpClient {
+    pub fn new(config: HttpClientConfig) -> Self {
+        let mut http = hyper::client::HttpConnector::new();
+        http.enforce_http(false);
+        http.set_connect_timeout(Some(config.connect_timeout));
+
+        Self {
+            inner: hyper::Client::builder()
+                .build(hyper_tls::HttpsConnector::new_with_connector(http)),
+            circuit: CircuitBreaker::new(config.circuit_failure_threshold, config.circuit_reset),
+            config,
+        }
+    }
+
+    async fn attempt(
+        &self,
+        req: hyper::Request<hyper::Body>,
+    ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+        let start = Instant::now();
+        let res = tokio::time::timeout(self.config.read_timeout, self.inner.request(req)).await;
+        crate::access_log::add_backend_time(start.elapsed());
+
+        match res {
+            Ok(Ok(res)) => Ok(res),
+            Ok(Err(err)) => {
+                if err.is_timeout() {
+                    Err(crate::Error::BackendTimeout)
+                } else {
+                    Err(crate::Error::BackendConnection(err))
+                }
+            }
+            Err(_) => Err(crate::Error::BackendTimeout),
+        }
+    }
+
+    /// Sends a request to the backend.
+    ///
+    /// GET requests are retried with backoff if the backend can't be reached or reports that it
+    /// is unavailable.
+    pub async fn request(
+        &self,
+        req: hyper::Request<hyper::Body>,
+    ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+        if !self.circuit.allows_request() {
+            return Err(crate::Error::BackendUnavailable);
+        }
+
+        // only GET requests are safe to resend, and they have no body to preserve
+        let retry_parts = if req.method() == hyper::Method::GET {
+            Some((req.uri().clone(), req.headers().clone()))
+        } else {
+            None
+        };
+
+        let mut req = req;
+        let mut attempts = 0;
+
+        loop {
+            let res = self.attempt(req).await;
+
+            let failed = match &res {
+                Ok(res) => is_unavailable_status(res.status()),
+                Err(_) => true,
+            };
+
+            if failed {
+                self.circuit.record_failure();
+            } else {
+                self.circuit.record_success();
+            }
+
+            match &retry_parts {
+                Some((uri, headers))
+                    if failed
+                        && attempts < self.config.retries
+                        && self.circuit.allows_request() =>
+                {
+                    tokio::time::delay_for(self.config.retry_backoff * 2u32.pow(attempts.min(10)))
+                        .await;
+                    attempts += 1;
+
+                    let mut new_req = hyper::Request::new(hyper::Body::empty());
+                    *new_req.uri_mut() = uri.clone();
+                    *new_req.headers_mut() = headers.clone();
+
+                    req = new_req;
+                }
+                _ => return res,
+            }
+        }
+    }
+
+    /// Reads a whole response body from the backend, with the same timeout as for the response
+    /// itself.
+    pub async fn get_bytes(&self, body: hyper::Body) -> Result<hyper::body::Bytes, crate::Error> {
+        let start = Instant::now();
+        let res = tokio::time::timeout(self.config.read_timeout, hyper::body::to_bytes(body)).await;
+        crate::access_log::add_backend_time(start.elapsed());
+
+        match res {
+            Ok(Ok(bytes)) => Ok(bytes),
+            Ok(Err(err)) => {
+                self.circuit.record_failure();
+                Err(crate::Error::BackendConnection(err))
+            }
+            Err(_) => {
+                self.circuit.record_failure();
+                Err(crate::Error::BackendTimeout)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::{CircuitBreaker, HttpClient, HttpClientConfig};
+    use std::convert::Infallible;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    /// Serves `handler` as a stand-in backend on a local port, returning its base URL
+    pub fn stub_backend<F>(handler: F) -> String
+    where
+        F: Fn(hyper::Request<hyper::Body>) -> hyper::Response<hyper::Body>
+            + Clone
+            + Send
+            + Sync
+            + 'static,
+    {
+        let make_service = hyper::service::make_service_fn(move |_| {
+            let handler = handler.clone();
+
+            async move {
+                Ok::<_, Infallible>(hyper::service::service_fn(move |req| {
+                    let res = handler(req);
+
+                    async move { Ok::<_, Infallible>(res) }
+                }))
+            }
+        });
+
+        let server = hyper::Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service);
+        let host = format!("http://{}", server.local_addr());
+
+        tokio::spawn(server);
+
+        host
+    }
+
+    /// Serves 503 for the first `failures` requests and 200 after that, counting every request
+    fn flaky_backend(failures: u32) -> (String, Arc<AtomicU32>) {
+        let count = Arc::new(AtomicU32::new(0));
+
+        let host = stub_backend({
+            let count = count.clone();
+            move |_| {
+                let status = if count.fetch_add(1, Ordering::SeqCst) < failures {
+                    hyper::StatusCode::SERVICE_UNAVAILABLE
+                } else {
+                    hyper::StatusCode::OK
+                };
+
+                hyper::Response::builder()
+                    .status(status)
+                    .body(hyper::Body::empty())
+                    .unwrap()
+            }
+        });
+
+        (host, count)
+    }
+
+    fn retrying_client(retries: u32, retry_backoff: Duration) -> HttpClient {
+        HttpClient::new(HttpClientConfig {
+            connect_timeout: Duration::from_secs(1),
+            read_timeout: Duration::from_secs(1),
+            retries,
+            retry_backoff,
+            circuit_failure_threshold: 0,
+            circuit_reset: Duration::from_secs(1),
+        })
+    }
+
+    #[test]
+    fn circuit_opens_after_threshold() {
+        let circuit = CircuitBreaker::new(2, Duration::from_secs(60));
+
+        assert!(circuit.allows_request());
+        circuit.record_failure();
+        assert!(circuit.allows_request());
+        circuit.record_failure();
+        assert!(!circuit.allows_request());
+
+        // a success in between starts the count over
+        let circuit = CircuitBreaker::new(2, Duration::from_secs(60));
+        circuit.record_failure();
+        circuit.record_success();
+        circuit.record_failure();
+        assert!(circuit.allows_request());
+    }
+
+    #[test]
+    fn half_open_circuit_allows_single_probe() {
+        let reset = Duration::from_millis(50);
+        let circuit = CircuitBreaker::new(1, reset);
+
+        circuit.record_failure();
+        assert!(!circuit.allows_request());
+
+        std::thread::sleep(reset);
+        assert!(circuit.allows_request());
+        assert!(!circuit.allows_request());
+
+        // a failed probe reopens the circuit for another reset period
+        circuit.record_failure();
+        assert!(!circuit.allows_request());
+
+        std::thread::sleep(reset);
+        assert!(circuit.allows_request());
+        assert!(!circuit.allows_request());
+
+        circuit.record_success();
+        assert!(circuit.allows_request());
+        assert!(circuit.allows_request());
+    }
+
+    #[test]
+    fn half_open_circuit_probes_again_if_probe_never_finishes() {
+        let reset = Duration::from_millis(50);
+        let circuit = CircuitBreaker::new(1, reset);
+
+        circuit.record_failure();
+        std::thread::sleep(reset);
+        assert!(circuit.allows_request());
+
+        std::thread::sleep(reset);
+        assert!(circuit.allows_request());
+        assert!(!circuit.allows_request());
+    }
+
+    #[tokio::test]
+    async fn retries_get_with_backoff() {
+        let (host, count) = flaky_backend(2);
+        let client = retrying_client(2, Duration::from_millis(20));
+
+        let start = Instant::now();
+        let res = client
+            .request(
+                hyper::Request::get(format!("{}/api/unstable/instance", host))
+                    .body(hyper::Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(res.status(), hyper::StatusCode::OK);
+        assert_eq!(count.load(Ordering::SeqCst), 3);
+        // 20ms, then 40ms
+        assert!(start.elapsed() >= Duration::from_millis(60));
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_retries() {
+        let (host, count) = flaky_backend(u32::MAX);
+        let client = retrying_client(2, Duration::from_millis(1));
+
+        let res = client
+            .request(
+                hyper::Request::get(format!("{}/api/unstable/instance", host))
+                    .body(hyper::Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(res.status(), hyper::StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(count.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_other_methods() {
+        let (host, count) = flaky_backend(1);
+        let client = retrying_client(2, Duration::from_millis(1));
+
+        let res = client
+            .request(
+                hyper::Request::post(format!("{}/api/unstable/posts", host))
+                    .body("{}".into())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(res.status(), hyper::StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+    }
+}
@@ -1,24 +1,179 @@
+use serde_derive::{Deserialize, Serialize};
 use std::borrow::Cow;
+use std::collections::HashMap;
 use std::convert::TryFrom;
+use std::sync::{Arc, RwLock};
 
+pub type Bundle = fluent::bundle::FluentBundle<
+    Arc<fluent::FluentResource>,
+    intl_memoizer::concurrent::IntlLangMemoizer,
+>;
+
+/// Resources for each language, with overrides listed before the resources they override
+pub type ResourceMap = HashMap<unic_langid::LanguageIdentifier, Vec<Arc<fluent::FluentResource>>>;
+
+/// Builds a bundle using the given languages, in order of preference
+pub fn new_bundle(
+    resources: &ResourceMap,
+    languages: &[&unic_langid::LanguageIdentifier],
+) -> Bundle {
+    let mut bundle = Bundle::new_concurrent(languages.iter().copied().cloned().collect());
+    for lang in languages {
+        for resource in resources.get(lang).into_iter().flatten() {
+            if let Err(errors) = bundle.add_resource(resource.clone()) {
+                for err in errors {
+                    match err {
+                        fluent::FluentError::Overriding { .. } => {}
+                        _ => {
+                            log::error!("Failed to add {} language resource: {:?}", lang, err);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    bundle
+}
+
+/// A set of loaded translations, along with the bundles that have been built from them
+pub struct Catalog {
+    resources: ResourceMap,
+    languages: Vec<unic_langid::LanguageIdentifier>,
+    bundles: RwLock<HashMap<Vec<unic_langid::LanguageIdentifier>, Arc<Bundle>>>,
+}
+
+impl Catalog {
+    pub fn new(resources: ResourceMap) -> Self {
+        Self {
+            languages: resources.keys().cloned().collect(),
+            resources,
+            bundles: Default::default(),
+        }
+    }
+
+    pub fn languages(&self) -> &[unic_langid::LanguageIdentifier] {
+        &self.languages
+    }
+
+    fn bundle(&self, languages: &[&unic_langid::LanguageIdentifier]) -> Arc<Bundle> {
+        let key: Vec<_> = languages.iter().copied().cloned().collect();
+
+        let existing = self.bundles.read().unwrap().get(&key).cloned();
+        match existing {
+            Some(bundle) => bundle,
+            None => self
+                .bundles
+                .write()
+                .unwrap()
+                .entry(key)
+                .or_insert_with(|| Arc::new(new_bundle(&self.resources, languages)))
+                .clone(),
+        }
+    }
+
+    pub fn translator(&self, languages: &[&unic_langid::LanguageIdentifier]) -> Translator {
+        Translator::new(self.bundle(languages), languages[0].clone())
+    }
+
+    /// Each available language along with its name in that language, sorted by name
+    pub fn native_names(&self) -> Vec<(&unic_langid::LanguageIdentifier, String)> {
+        let mut result: Vec<_> = self
+            .languages
+            .iter()
+            .map(|lang| {
+                let bundle = self.bundle(&[lang]);
+                let name = bundle
+                    .get_message("language_name")
+                    .and_then(|msg| msg.value())
+                    .map(|value| {
+                        bundle
+                            .format_pattern(value, None, &mut Vec::new())
+                            .into_owned()
+                    })
+                    .unwrap_or_else(|| lang.to_string());
+
+                (lang, name)
+            })
+            .collect();
+
+        result.sort_by(|a, b| a.1.cmp(&b.1));
+
+        result
+    }
+}
+
+/// How timestamps should be shown
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeDisplay {
+    Relative,
+    Absolute,
+}
+
+impl TimeDisplay {
+    pub const VALUES: &'static [TimeDisplay] = &[TimeDisplay::Relative, TimeDisplay::Absolute];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TimeDisplay::Relative => "relative",
+            TimeDisplay::Absolute => "absolute",
+        }
+    }
+
+    pub fn lang_key(&self) -> LangKey<'static> {
+        match self {
+            TimeDisplay::Relative => time_display_relative(),
+            TimeDisplay::Absolute => time_display_absolute(),
+        }
+    }
+}
+
+/// Finds the closest locale chrono has date formatting data for
+fn chrono_locale(lang: &unic_langid::LanguageIdentifier) -> chrono::Locale {
+    let language = lang.language.as_str();
+
+    let candidates = [
+        lang.region
+            .map(|region| format!("{}_{}", language, region.as_str())),
+        Some(language.to_owned()),
+        Some(format!("{}_{}", language, language.to_uppercase())),
+    ];
+
+    candidates
+        .iter()
+        .flatten()
+        .find_map(|name| chrono::Locale::try_from(name.as_str()).ok())
+        .unwrap_or(chrono::Locale::POSIX)
+}
+
+#[derive(Clone)]
 pub struct Translator {
-    bundle: fluent::bundle::FluentBundle<
-        &'static fluent::FluentResource,
-        intl_memoizer::concurrent::IntlLangMemoizer,
-    >,
+    bundle: Arc<Bundle>,
     primary_language: unic_langid::LanguageIdentifier,
+    locale: chrono::Locale,
+    time_zone: chrono_tz::Tz,
+    time_display: TimeDisplay,
 }
 impl Translator {
     pub fn new(
-        bundle: fluent::bundle::FluentBundle<
-            &'static fluent::FluentResource,
-            intl_memoizer::concurrent::IntlLangMemoizer,
-        >,
+        bundle: Arc<Bundle>,
         primary_language: unic_langid::LanguageIdentifier,
     ) -> Translator {
         Translator {
             bundle,
+            locale: chrono_locale(&primary_language),
             primary_language,
+            time_zone: chrono_tz::UTC,
+            time_display: TimeDisplay::Relative,
+        }
+    }
+
+    pub fn with_time_settings(self, time_zone: chrono_tz::Tz, time_display: TimeDisplay) -> Self {
+        Translator {
+            time_zone,
+            time_display,
+            ..self
         }
     }
 
@@ -27,12 +182,8 @@ impl Translator {
         let args = args.as_ref();
 
         let mut errors = Vec::with_capacity(0);
-        let out = match self.bundle.get_message(key) {
-            Some(msg) => self.bundle.format_pattern(
-                msg.value().expect("Missing value for translation key"),
-                args,
-                &mut errors,
-            ),
+        let out = match self.bundle.get_message(key).and_then(|msg| msg.value()) {
+            Some(value) => self.bundle.format_pattern(value, args, &mut errors),
             None => {
                 log::error!("Missing translation for {}", key);
                 Cow::Borrowed(*key)
@@ -48,6 +199,29 @@ impl Translator {
     pub fn primary_language(&self) -> &unic_langid::LanguageIdentifier {
         &self.primary_language
     }
+
+    pub fn time_display(&self) -> TimeDisplay {
+        self.time_display
+    }
+
+    /// Formats a timestamp as a date and time in the chosen time zone, using the language's own
+    /// format and month names
+    pub fn format_datetime<Tz: chrono::TimeZone>(&self, time: &chrono::DateTime<Tz>) -> String {
+        use std::fmt::Write;
+
+        let time = time.with_timezone(&self.time_zone);
+        let format = self.tr(&TIME_ABSOLUTE_FORMAT);
+
+        let mut out = String::new();
+        if write!(out, "{}", time.format_localized(&format, self.locale)).is_err() {
+            log::error!("Invalid time_absolute_format: {}", format);
+
+            out.clear();
+            write!(out, "{}", time.format("%Y-%m-%d %H:%M")).unwrap();
+        }
+
+        out
+    }
 }
 impl std::fmt::Debug for Translator {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
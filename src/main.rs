@@ -2,19 +2,38 @@
 
 use crate::resp_types::RespLoginInfo;
 use serde_derive::{Deserialize, Serialize};
-use std::collections::HashMap;
 use std::sync::Arc;
 use trout::http02::RoutingFailureExtHttp;
 
+mod access_log;
+mod accounts;
+mod assets;
+mod backend;
+mod branding;
+mod captcha;
 mod components;
+mod compression;
 mod config;
+mod data_export;
+mod handle_cache;
+mod http_client;
 mod lang;
+mod page_cache;
+mod preferences;
 mod query_types;
+mod rate_limit;
 mod resp_types;
 mod routes;
+mod security_headers;
+mod signing;
+mod translations;
 mod util;
+mod zip_writer;
 
+pub use http_client::HttpClient;
 pub use lang::Translator;
+use page_cache::PageCache;
+use security_headers::SecurityHeaders;
 
 use self::config::Config;
 
@@ -46,12 +65,23 @@ impl SortType {
     }
 }
 
-pub type HttpClient = hyper::Client<hyper_tls::HttpsConnector<hyper::client::HttpConnector>>;
-
 pub struct RouteContext {
     backend_host: String,
     frontend_url: url::Url,
     http_client: HttpClient,
+    cookie_key: signing::SigningKey,
+    branding: Arc<branding::Branding>,
+    assets: Arc<assets::Assets>,
+    rate_limiter: rate_limit::RateLimiter,
+    captcha: captcha::Captcha,
+    handle_cache: handle_cache::HandleCache,
+}
+
+impl RouteContext {
+    /// Whether cookies holding login tokens can be marked `Secure`
+    pub fn secure_cookies(&self) -> bool {
+        self.frontend_url.scheme() == "https"
+    }
 }
 
 pub type RouteNode<P> = trout::Node<
@@ -72,6 +102,9 @@ pub enum Error {
     RoutingError(trout::RoutingFailure),
     RemoteError((hyper::StatusCode, String)),
     InternalUserError(String),
+    BackendConnection(hyper::Error),
+    BackendTimeout,
+    BackendUnavailable,
 }
 
 impl<T: 'static + std::error::Error + Send> From<T> for Error {
@@ -83,6 +116,10 @@ impl<T: 'static + std::error::Error + Send> From<T> for Error {
 #[derive(Debug)]
 pub struct PageBaseData {
     pub login: Option<RespLoginInfo>,
+    pub accounts: Vec<accounts::AccountSummary>,
+    pub preferences: preferences::Preferences,
+    pub branding: Arc<branding::Branding>,
+    pub assets: Arc<assets::Assets>,
 }
 
 impl PageBaseData {
@@ -103,27 +140,23 @@ pub fn simple_response(
     res
 }
 
-lazy_static::lazy_static! {
-    static ref LANG_MAP: HashMap<unic_langid::LanguageIdentifier, fluent::FluentResource> = {
-        let mut result = HashMap::new();
-
-        result.insert(unic_langid::langid!("de"), fluent::FluentResource::try_new(include_str!("../res/lang/de.ftl").to_owned()).expect("Failed to parse translation"));
-        result.insert(unic_langid::langid!("en"), fluent::FluentResource::try_new(include_str!("../res/lang/en.ftl").to_owned()).expect("Failed to parse translation"));
-        result.insert(unic_langid::langid!("eo"), fluent::FluentResource::try_new(include_str!("../res/lang/eo.ftl").to_owned()).expect("Failed to parse translation"));
-        result.insert(unic_langid::langid!("fr"), fluent::FluentResource::try_new(include_str!("../res/lang/fr.ftl").to_owned()).expect("Failed to parse translation"));
-        result.insert(unic_langid::langid!("fa"), fluent::FluentResource::try_new(include_str!("../res/lang/fa.ftl").to_owned()).expect("Failed to parse translation"));
-
-        result
-    };
+/// Sent in place of a response whose body failed to read, so there's nothing left to pass on
+fn body_error_response(request_id: &str) -> hyper::Response<hyper::Body> {
+    simple_response(
+        hyper::StatusCode::INTERNAL_SERVER_ERROR,
+        format!("Internal Server Error\nRequest ID: {}", request_id),
+    )
+}
 
-    static ref LANGS: Vec<unic_langid::LanguageIdentifier> = {
-        LANG_MAP.keys().cloned().collect()
-    };
+lazy_static::lazy_static! {
+    static ref DEFAULT_LANG: unic_langid::LanguageIdentifier = unic_langid::langid!("en");
 }
 
-pub fn get_lang_for_headers(headers: &hyper::header::HeaderMap) -> Translator {
-    let default = unic_langid::langid!("en");
-    let languages = match headers
+fn negotiate_languages<'a>(
+    headers: &hyper::header::HeaderMap,
+    available: &'a [unic_langid::LanguageIdentifier],
+) -> Vec<&'a unic_langid::LanguageIdentifier> {
+    match headers
         .get(hyper::header::ACCEPT_LANGUAGE)
         .and_then(|x| x.to_str().ok())
     {
@@ -131,37 +164,78 @@ pub fn get_lang_for_headers(headers: &hyper::header::HeaderMap) -> Translator {
             let requested = fluent_langneg::accepted_languages::parse(accept_language);
             fluent_langneg::negotiate_languages(
                 &requested,
-                &LANGS,
-                Some(&default),
+                available,
+                Some(&*DEFAULT_LANG),
                 fluent_langneg::NegotiationStrategy::Filtering,
             )
         }
-        None => vec![&default],
-    };
+        None => vec![&*DEFAULT_LANG],
+    }
+}
 
-    let mut bundle = fluent::bundle::FluentBundle::<
-        &'static fluent::FluentResource,
-        intl_memoizer::concurrent::IntlLangMemoizer,
-    >::new_concurrent(languages.iter().copied().cloned().collect());
-    for lang in &languages {
-        if let Err(errors) = bundle.add_resource(&LANG_MAP[lang]) {
-            for err in errors {
-                match err {
-                    fluent::FluentError::Overriding { .. } => {}
-                    _ => {
-                        log::error!("Failed to add language resource: {:?}", err);
-                        break;
-                    }
-                }
-            }
-        }
+/// Picks the languages to use for a request, in order of preference
+pub fn get_langs_for_headers(
+    headers: &hyper::header::HeaderMap,
+) -> Vec<unic_langid::LanguageIdentifier> {
+    negotiate_languages(headers, translations::current().languages())
+        .into_iter()
+        .cloned()
+        .collect()
+}
+
+pub fn get_lang_for_headers(ctx: &RouteContext, headers: &hyper::header::HeaderMap) -> Translator {
+    let catalog = translations::current();
+    let lang = catalog.translator(&negotiate_languages(headers, catalog.languages()));
+
+    let cookies = routes::get_cookie_map_for_headers(headers).unwrap_or_default();
+    let preferences = preferences::Preferences::from_cookies(&ctx.cookie_key, &cookies);
+
+    lang.with_time_settings(preferences.time_zone, preferences.time_display)
+}
+
+/// Makes a language chosen through `?lang=` or the language cookie take priority over the
+/// browser's preferences, both here and in requests to the backend
+fn apply_language_choice(req: &mut hyper::Request<hyper::Body>) {
+    let chosen = req
+        .uri()
+        .query()
+        .and_then(|query| {
+            url::form_urlencoded::parse(query.as_bytes())
+                .find(|(key, _)| key == "lang")
+                .map(|(_, value)| value.into_owned())
+        })
+        .or_else(|| {
+            routes::get_cookie_map_for_headers(req.headers())
+                .ok()?
+                .get(routes::LANG_COOKIE)
+                .map(|cookie| cookie.value.to_owned())
+        });
+
+    let chosen: unic_langid::LanguageIdentifier = match chosen.and_then(|x| x.parse().ok()) {
+        Some(chosen) => chosen,
+        None => return,
+    };
+    if !translations::current().languages().contains(&chosen) {
+        return;
     }
 
-    Translator::new(bundle, languages[0].clone())
+    let value = match req
+        .headers()
+        .get(hyper::header::ACCEPT_LANGUAGE)
+        .and_then(|x| x.to_str().ok())
+    {
+        Some(original) => format!("{}, {}", chosen, original),
+        None => chosen.to_string(),
+    };
+
+    if let Ok(value) = hyper::header::HeaderValue::from_str(&value) {
+        req.headers_mut()
+            .insert(hyper::header::ACCEPT_LANGUAGE, value);
+    }
 }
 
-pub fn get_lang_for_req(req: &hyper::Request<hyper::Body>) -> Translator {
-    get_lang_for_headers(req.headers())
+pub fn get_lang_for_req(ctx: &RouteContext, req: &hyper::Request<hyper::Body>) -> Translator {
+    get_lang_for_headers(ctx, req.headers())
 }
 
 pub fn bool_as_str(src: bool) -> &'static str {
@@ -174,43 +248,185 @@ pub fn bool_as_str(src: bool) -> &'static str {
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    env_logger::init();
+    env_logger::Builder::from_env(
+        env_logger::Env::default().default_filter_or("error,hitide::access=info"),
+    )
+    .init();
 
     let config = Config::load().expect("Failed to load config");
 
+    if let Some(dir) = &config.translations_dir {
+        translations::reload(dir);
+        translations::spawn_watcher(dir.clone());
+    }
+
+    let branding = branding::Branding::load(&config).expect("Failed to load branding");
+
+    let cookie_key = config.cookie_signing_key();
+
     let routes = Arc::new(routes::route_root());
     let context = Arc::new(RouteContext {
+        http_client: HttpClient::new(config.http_client_config()),
+        captcha: captcha::Captcha::new(cookie_key.clone(), config.captcha),
+        cookie_key,
+        assets: Arc::new(assets::Assets::new(&branding)),
+        branding: Arc::new(branding),
+        rate_limiter: rate_limit::RateLimiter::new(
+            config
+                .rate_limit_config()
+                .expect("Invalid rate limit config"),
+        ),
+        handle_cache: Default::default(),
         backend_host: config.backend_host,
         frontend_url: config.frontend_url,
-        http_client: hyper::Client::builder().build(hyper_tls::HttpsConnector::new()),
     });
+    let page_cache = Arc::new(PageCache::new(config.page_cache_config()));
+    let security_headers = Arc::new(
+        SecurityHeaders::new(&config.security_headers_config())
+            .expect("Invalid security header in config"),
+    );
+    let log_format = config.log_format;
+    let compression_min_bytes = config.compression_min_bytes;
 
     let server = hyper::Server::bind(&(std::net::Ipv6Addr::UNSPECIFIED, config.port).into()).serve(
-        hyper::service::make_service_fn(|_| {
+        hyper::service::make_service_fn(|conn: &hyper::server::conn::AddrStream| {
+            let remote_ip = conn.remote_addr().ip();
             let routes = routes.clone();
             let context = context.clone();
-            async {
-                Ok::<_, hyper::Error>(hyper::service::service_fn(move |req| {
+            let page_cache = page_cache.clone();
+            let security_headers = security_headers.clone();
+            async move {
+                Ok::<_, hyper::Error>(hyper::service::service_fn(move |mut req| {
                     let routes = routes.clone();
                     let context = context.clone();
+                    let page_cache = page_cache.clone();
+                    let security_headers = security_headers.clone();
                     async move {
-                        let result = match routes.route(req, context) {
-                            Ok(fut) => fut.await,
-                            Err(err) => Err(Error::RoutingError(err)),
+                        let start = std::time::Instant::now();
+                        let info = Arc::new(access_log::RequestInfo::new());
+                        let request_id = hyper::header::HeaderValue::from_str(&info.id).unwrap();
+
+                        req.headers_mut()
+                            .insert(access_log::REQUEST_ID_HEADER, request_id.clone());
+                        apply_language_choice(&mut req);
+
+                        let client_ip = context.rate_limiter.client_ip(remote_ip, req.headers());
+                        req.extensions_mut().insert(rate_limit::ClientIp(client_ip));
+
+                        let method = req.method().clone();
+                        let path = req.uri().path().to_owned();
+                        let req_headers = req.headers().clone();
+                        let error_context = context.clone();
+
+                        let cache_key = page_cache.key_for(&req);
+                        let cached = cache_key
+                            .as_ref()
+                            .and_then(|(key, _)| page_cache.get(key, &req_headers));
+
+                        let result = match cached {
+                            Some(res) => Ok(res),
+                            None => {
+                                info.clone()
+                                    .scope(async {
+                                        let res = match routes.route(req, context) {
+                                            Ok(fut) => fut.await,
+                                            Err(err) => Err(Error::RoutingError(err)),
+                                        }?;
+
+                                        match cache_key {
+                                            Some((key, ttl)) => Ok(page_cache
+                                                .store(key, ttl, res, &req_headers)
+                                                .await?),
+                                            None => Ok(res),
+                                        }
+                                    })
+                                    .await
+                            }
                         };
-                        Ok::<_, hyper::Error>(match result {
+                        let mut res = match result {
                             Ok(val) => val,
-                            Err(Error::UserError(res)) => res,
-                            Err(Error::RoutingError(err)) => err.to_simple_response(),
                             Err(err) => {
-                                log::error!("Error: {:?}", err);
+                                let res = match err {
+                                    Error::UserError(res) => res,
+                                    Error::RoutingError(err) => err.to_simple_response(),
+                                    err @ Error::BackendConnection(_)
+                                    | err @ Error::BackendTimeout
+                                    | err @ Error::BackendUnavailable => {
+                                        log::warn!(
+                                            "Backend error in request {}: {:?}",
+                                            info.id,
+                                            err
+                                        );
+
+                                        routes::backend_error_response(
+                                            &req_headers,
+                                            &error_context,
+                                            &err,
+                                        )
+                                    }
+                                    err => {
+                                        log::error!("Error in request {}: {:?}", info.id, err);
+
+                                        simple_response(
+                                            hyper::StatusCode::INTERNAL_SERVER_ERROR,
+                                            "Internal Server Error",
+                                        )
+                                    }
+                                };
+
+                                match access_log::add_request_id_to_error(res, &info.id).await {
+                                    Ok(res) => res,
+                                    Err(err) => {
+                                        log::error!(
+                                            "Failed to read error response in request {}: {:?}",
+                                            info.id,
+                                            err
+                                        );
+
+                                        body_error_response(&info.id)
+                                    }
+                                }
+                            }
+                        };
 
-                                simple_response(
-                                    hyper::StatusCode::INTERNAL_SERVER_ERROR,
-                                    "Internal Server Error",
-                                )
+                        res.headers_mut()
+                            .insert(access_log::REQUEST_ID_HEADER, request_id.clone());
+                        security_headers.apply(res.headers_mut());
+
+                        let res = match compression::compress_response(
+                            res,
+                            &req_headers,
+                            compression_min_bytes,
+                        )
+                        .await
+                        {
+                            Ok(res) => res,
+                            Err(err) => {
+                                log::error!(
+                                    "Failed to read response body in request {}: {:?}",
+                                    info.id,
+                                    err
+                                );
+
+                                let mut res = body_error_response(&info.id);
+                                res.headers_mut()
+                                    .insert(access_log::REQUEST_ID_HEADER, request_id);
+                                security_headers.apply(res.headers_mut());
+
+                                res
                             }
-                        })
+                        };
+
+                        access_log::log_request(
+                            log_format,
+                            &info,
+                            &method,
+                            &path,
+                            res.status(),
+                            start.elapsed(),
+                        );
+
+                        Ok::<_, hyper::Error>(res)
                     }
                 }))
             }
@@ -0,0 +1,270 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Debug)]
+pub struct PageCacheConfig {
+    pub max_bytes: usize,
+    pub max_entry_bytes: usize,
+    pub ttl_all: Duration,
+    pub ttl_local: Duration,
+    pub ttl_community: Duration,
+    pub ttl_post: Duration,
+}
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct CacheKey {
+    path_and_query: String,
+    langs: String,
+    preferences: Option<String>,
+}
+
+struct CacheEntry {
+    body: hyper::body::Bytes,
+    content_type: Option<hyper::header::HeaderValue>,
+    etag: hyper::header::HeaderValue,
+    inserted: Instant,
+    expires: Instant,
+}
+
+#[derive(Default)]
+struct CacheState {
+    entries: HashMap<CacheKey, CacheEntry>,
+    // insertion order, for evicting the oldest entries first
+    order: VecDeque<(CacheKey, Instant)>,
+    total_bytes: usize,
+}
+
+impl CacheState {
+    fn remove(&mut self, key: &CacheKey) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.total_bytes -= entry.body.len();
+        }
+    }
+
+    /// Removes the oldest entry, returning false if there was nothing left to remove
+    fn pop_oldest(&mut self) -> bool {
+        match self.order.pop_front() {
+            None => false,
+            Some((key, inserted)) => {
+                // order may still contain keys that have since been replaced or removed
+                if self.entries.get(&key).map(|entry| entry.inserted) == Some(inserted) {
+                    self.remove(&key);
+                }
+                true
+            }
+        }
+    }
+}
+
+/// In-memory cache of rendered pages for logged-out visitors
+pub struct PageCache {
+    config: PageCacheConfig,
+    state: Mutex<CacheState>,
+}
+
+impl PageCache {
+    pub fn new(config: PageCacheConfig) -> Self {
+        Self {
+            config,
+            state: Default::default(),
+        }
+    }
+
+    fn ttl_for_path(&self, path: &str) -> Option<Duration> {
+        let mut segments = path.trim_start_matches('/').split('/');
+
+        let ttl = match (segments.next(), segments.next(), segments.next()) {
+            (Some("all"), None, None) => self.config.ttl_all,
+            (Some("local"), None, None) => self.config.ttl_local,
+            (Some("communities"), Some(id), None) if id.parse::<i64>().is_ok() => {
+                self.config.ttl_community
+            }
+            (Some("posts"), Some(id), None) if id.parse::<i64>().is_ok() => self.config.ttl_post,
+            _ => return None,
+        };
+
+        if ttl == Duration::from_secs(0) {
+            None
+        } else {
+            Some(ttl)
+        }
+    }
+
+    /// Determines whether a request can be served from the cache, returning the key and TTL to use
+    pub fn key_for(&self, req: &hyper::Request<hyper::Body>) -> Option<(CacheKey, Duration)> {
+        if self.config.max_bytes == 0 || req.method() != hyper::Method::GET {
+            return None;
+        }
+
+        let ttl = self.ttl_for_path(req.uri().path())?;
+
+        // logged-in pages are personalized, and unparseable cookies might be hiding a token
+        let cookies = match crate::routes::get_cookie_map_for_headers(req.headers()) {
+            Ok(cookies) if !cookies.contains_key(crate::accounts::TOKEN_COOKIE) => cookies,
+            _ => return None,
+        };
+        let preferences = cookies
+            .get(crate::routes::PREFERENCES_COOKIE)
+            .map(|cookie| cookie.value.to_owned());
+
+        let langs = crate::get_langs_for_headers(req.headers())
+            .iter()
+            .map(|lang| lang.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+
+        Some((
+            CacheKey {
+                path_and_query: req
+                    .uri()
+                    .path_and_query()
+                    .map(|x| x.as_str())
+                    .unwrap_or("/")
+                    .to_owned(),
+                langs,
+                preferences,
+            },
+            ttl,
+        ))
+    }
+
+    pub fn get(
+        &self,
+        key: &CacheKey,
+        req_headers: &hyper::header::HeaderMap,
+    ) -> Option<hyper::Response<hyper::Body>> {
+        let mut state = self.state.lock().unwrap();
+
+        let now = Instant::now();
+        let entry = state.entries.get(key)?;
+        if entry.expires <= now {
+            state.remove(key);
+            return None;
+        }
+
+        Some(build_response(
+            entry.body.clone(),
+            entry.content_type.clone(),
+            entry.etag.clone(),
+            entry.expires - now,
+            req_headers,
+        ))
+    }
+
+    /// Stores a freshly rendered page if it's cacheable, and returns the response to send
+    pub async fn store(
+        &self,
+        key: CacheKey,
+        ttl: Duration,
+        res: hyper::Response<hyper::Body>,
+        req_headers: &hyper::header::HeaderMap,
+    ) -> Result<hyper::Response<hyper::Body>, hyper::Error> {
+        if res.status() != hyper::StatusCode::OK
+            || res.headers().contains_key(hyper::header::SET_COOKIE)
+        {
+            return Ok(res);
+        }
+
+        let (parts, body) = res.into_parts();
+        let body = hyper::body::to_bytes(body).await?;
+
+        let etag = {
+            use sha2::Digest;
+
+            // DefaultHasher's output may change between Rust versions, which would invalidate
+            // every ETag clients hold after an upgrade
+            let digest = sha2::Sha256::digest(&body);
+            let hash: String = digest[..16]
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect();
+            hyper::header::HeaderValue::from_str(&format!("\"{}\"", hash)).unwrap()
+        };
+        let content_type = parts.headers.get(hyper::header::CONTENT_TYPE).cloned();
+
+        if body.len() <= self.config.max_entry_bytes && body.len() <= self.config.max_bytes {
+            let now = Instant::now();
+
+            let mut state = self.state.lock().unwrap();
+            state.remove(&key);
+
+            // drop anything that has already expired before resorting to evicting live entries
+            loop {
+                let expired = match state.order.front() {
+                    None => break,
+                    Some((front_key, inserted)) => match state.entries.get(front_key) {
+                        Some(entry) if entry.inserted == *inserted => entry.expires <= now,
+                        _ => true,
+                    },
+                };
+                if !expired {
+                    break;
+                }
+
+                state.pop_oldest();
+            }
+            while state.total_bytes + body.len() > self.config.max_bytes {
+                if !state.pop_oldest() {
+                    break;
+                }
+            }
+
+            state.total_bytes += body.len();
+            state.order.push_back((key.clone(), now));
+            state.entries.insert(
+                key,
+                CacheEntry {
+                    body: body.clone(),
+                    content_type: content_type.clone(),
+                    etag: etag.clone(),
+                    inserted: now,
+                    expires: now + ttl,
+                },
+            );
+        }
+
+        Ok(build_response(body, content_type, etag, ttl, req_headers))
+    }
+}
+
+fn build_response(
+    body: hyper::body::Bytes,
+    content_type: Option<hyper::header::HeaderValue>,
+    etag: hyper::header::HeaderValue,
+    max_age: Duration,
+    req_headers: &hyper::header::HeaderMap,
+) -> hyper::Response<hyper::Body> {
+    let not_modified = req_headers
+        .get_all(hyper::header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|tag| tag.trim())
+        .any(|tag| tag == "*" || etag == tag.trim_start_matches("W/"));
+
+    let mut res = if not_modified {
+        crate::simple_response(hyper::StatusCode::NOT_MODIFIED, hyper::Body::empty())
+    } else {
+        let mut res = hyper::Response::new(body.into());
+        if let Some(content_type) = content_type {
+            res.headers_mut()
+                .insert(hyper::header::CONTENT_TYPE, content_type);
+        }
+        res
+    };
+
+    let headers = res.headers_mut();
+    headers.insert(hyper::header::ETAG, etag);
+    headers.insert(
+        hyper::header::CACHE_CONTROL,
+        hyper::header::HeaderValue::from_str(&format!("public, max-age={}", max_age.as_secs()))
+            .unwrap(),
+    );
+    headers.insert(
+        hyper::header::VARY,
+        hyper::header::HeaderValue::from_static("Accept-Language, Cookie"),
+    );
+
+    res
+}
@@ -0,0 +1,127 @@
+use crate::lang;
+use crate::signing::SigningKey;
+use crate::SortType;
+use serde_derive::{Deserialize, Serialize};
+
+pub const POSTS_PER_PAGE_VALUES: &[u8] = &[10, 20, 30, 50, 100];
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Copy, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Density {
+    Comfortable,
+    Compact,
+}
+
+impl Density {
+    pub const VALUES: &'static [Density] = &[Density::Comfortable, Density::Compact];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Density::Comfortable => "comfortable",
+            Density::Compact => "compact",
+        }
+    }
+
+    pub fn lang_key(&self) -> lang::LangKey<'static> {
+        match self {
+            Density::Comfortable => lang::density_comfortable(),
+            Density::Compact => lang::density_compact(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Copy, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Theme {
+    System,
+    Light,
+    Dark,
+    HighContrast,
+}
+
+impl Theme {
+    pub const VALUES: &'static [Theme] = &[
+        Theme::System,
+        Theme::Light,
+        Theme::Dark,
+        Theme::HighContrast,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Theme::System => "system",
+            Theme::Light => "light",
+            Theme::Dark => "dark",
+            Theme::HighContrast => "high_contrast",
+        }
+    }
+
+    pub fn lang_key(&self) -> lang::LangKey<'static> {
+        match self {
+            Theme::System => lang::theme_system(),
+            Theme::Light => lang::theme_light(),
+            Theme::Dark => lang::theme_dark(),
+            Theme::HighContrast => lang::theme_high_contrast(),
+        }
+    }
+
+    /// Name of the theme's stylesheet in [`Assets`](crate::assets::Assets), and the media query it applies to
+    pub fn stylesheet(&self) -> (&'static str, &'static str) {
+        match self {
+            Theme::System => ("theme-dark.css", "(prefers-color-scheme: dark)"),
+            Theme::Light => ("theme-light.css", "all"),
+            Theme::Dark => ("theme-dark.css", "all"),
+            Theme::HighContrast => ("theme-high_contrast.css", "all"),
+        }
+    }
+}
+
+/// Display settings for a visitor, kept in a signed cookie
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(default)]
+pub struct Preferences {
+    pub post_sort: SortType,
+    pub comment_sort: SortType,
+    pub posts_per_page: Option<u8>,
+    pub expand_sensitive: bool,
+    pub density: Density,
+    pub links_new_tab: bool,
+    pub theme: Theme,
+    pub time_display: lang::TimeDisplay,
+    pub time_zone: chrono_tz::Tz,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Self {
+            post_sort: SortType::Hot,
+            comment_sort: SortType::Hot,
+            posts_per_page: None,
+            expand_sensitive: false,
+            density: Density::Comfortable,
+            links_new_tab: false,
+            theme: Theme::System,
+            time_display: lang::TimeDisplay::Relative,
+            time_zone: chrono_tz::UTC,
+        }
+    }
+}
+
+impl Preferences {
+    /// Reads preferences from the cookie, falling back to the defaults if it's missing or invalid
+    pub fn from_cookies(key: &SigningKey, cookies: &crate::routes::CookieMap<'_>) -> Self {
+        cookies
+            .get(crate::routes::PREFERENCES_COOKIE)
+            .and_then(|cookie| key.verify_json(cookie.value))
+            .unwrap_or_default()
+    }
+
+    /// `target` for links leading away from the site
+    pub fn external_link_target(&self) -> &'static str {
+        if self.links_new_tab {
+            "_blank"
+        } else {
+            "_self"
+        }
+    }
+}
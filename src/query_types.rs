@@ -16,9 +16,41 @@ pub struct PostListQuery<'a> {
 }
 
 #[derive(Serialize)]
+pub struct ReplyListQuery<'a> {
+    pub include_your: Option<bool>,
+    pub sort: Option<crate::SortType>,
+    pub page: Option<&'a str>,
+}
+
+#[derive(Serialize, Default)]
+pub struct CommunityListQuery<'a> {
+    pub local: Option<bool>,
+    #[serde(rename = "your_follow.accepted")]
+    pub your_follow_accepted: Option<bool>,
+    pub you_are_moderator: Option<bool>,
+    pub include_your: Option<bool>,
+    pub search: Option<&'a str>,
+    pub page: Option<&'a str>,
+}
+
+#[derive(Serialize, Default)]
+pub struct UserListQuery<'a> {
+    pub local: Option<bool>,
+    pub username: Option<&'a str>,
+}
+
+#[derive(Serialize, Default)]
 pub struct FlagListQuery {
     pub to_this_site_admin: Option<bool>,
     pub to_community: Option<i64>,
+    pub dismissed: Option<bool>,
+}
+
+#[derive(Serialize, Default)]
+pub struct InvitationListQuery<'a> {
+    pub key: Option<&'a str>,
+    pub created_by: Option<&'a str>,
+    pub page: Option<&'a str>,
 }
 
 #[derive(Serialize)]
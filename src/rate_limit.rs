@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Buckets are only swept for idle entries once there are this many
+const SWEEP_THRESHOLD: usize = 10000;
+
+/// Group of endpoints sharing a limit
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum RateLimitClass {
+    Login,
+    Signup,
+    ForgotPassword,
+    Submit,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct BucketConfig {
+    /// Number of requests allowed at once
+    pub burst: u32,
+    /// Rate at which requests become allowed again. 0 disables the limit.
+    pub per_minute: u32,
+}
+
+#[derive(Clone, Debug)]
+pub struct RateLimitConfig {
+    pub login: BucketConfig,
+    pub signup: BucketConfig,
+    pub forgot_password: BucketConfig,
+    pub submit: BucketConfig,
+    pub trusted_proxies: Vec<IpNet>,
+}
+
+impl RateLimitConfig {
+    fn bucket(&self, class: RateLimitClass) -> BucketConfig {
+        match class {
+            RateLimitClass::Login => self.login,
+            RateLimitClass::Signup => self.signup,
+            RateLimitClass::ForgotPassword => self.forgot_password,
+            RateLimitClass::Submit => self.submit,
+        }
+    }
+}
+
+/// An IP address or CIDR range
+#[derive(Clone, Copy, Debug)]
+pub struct IpNet {
+    addr: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpNet {
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        fn mask_matches(a: &[u8], b: &[u8], prefix_len: u8) -> bool {
+            let full_bytes = usize::from(prefix_len / 8);
+            let rest_bits = prefix_len % 8;
+
+            if a[..full_bytes] != b[..full_bytes] {
+                return false;
+            }
+            if rest_bits == 0 {
+                return true;
+            }
+
+            let mask = 0xffu8 << (8 - rest_bits);
+            a[full_bytes] & mask == b[full_bytes] & mask
+        }
+
+        match (self.addr, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                mask_matches(&net.octets(), &ip.octets(), self.prefix_len)
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                mask_matches(&net.octets(), &ip.octets(), self.prefix_len)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl std::str::FromStr for IpNet {
+    type Err = String;
+
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        let (addr, prefix_len) = match src.split_once('/') {
+            Some((addr, prefix_len)) => (addr, Some(prefix_len)),
+            None => (src, None),
+        };
+
+        let addr: IpAddr = addr
+            .parse()
+            .map_err(|_| format!("Invalid IP address: {}", src))?;
+        let max_len = match addr {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+
+        let prefix_len = match prefix_len {
+            None => max_len,
+            Some(prefix_len) => match prefix_len.parse() {
+                Ok(prefix_len) if prefix_len <= max_len => prefix_len,
+                _ => return Err(format!("Invalid prefix length: {}", src)),
+            },
+        };
+
+        Ok(Self { addr, prefix_len })
+    }
+}
+
+/// Converts IPv4-mapped IPv6 addresses, as seen when listening on IPv6, back to IPv4
+fn canonical(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(v6) => match v6.segments() {
+            [0, 0, 0, 0, 0, 0xffff, _, _] => {
+                let octets = v6.octets();
+                IpAddr::V4(std::net::Ipv4Addr::new(
+                    octets[12], octets[13], octets[14], octets[15],
+                ))
+            }
+            _ => ip,
+        },
+        IpAddr::V4(_) => ip,
+    }
+}
+
+/// Address to limit a client by. IPv6 clients are usually given a whole /64, so they are limited
+/// by that instead of by address.
+fn bucket_key(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(v6) => {
+            let segments = v6.segments();
+            IpAddr::V6(std::net::Ipv6Addr::new(
+                segments[0],
+                segments[1],
+                segments[2],
+                segments[3],
+                0,
+                0,
+                0,
+                0,
+            ))
+        }
+        IpAddr::V4(_) => ip,
+    }
+}
+
+/// Address of the client that sent a request, stored in its extensions
+#[derive(Clone, Copy, Debug)]
+pub struct ClientIp(pub IpAddr);
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token bucket limiter, with a bucket for each class and client IPv4 address or IPv6 /64
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Mutex<HashMap<(RateLimitClass, IpAddr), Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            buckets: Default::default(),
+        }
+    }
+
+    /// Determines the client's address from the connection, using `X-Forwarded-For` only if the
+    /// connection came from a trusted proxy
+    pub fn client_ip(&self, remote: IpAddr, headers: &hyper::header::HeaderMap) -> IpAddr {
+        let remote = canonical(remote);
+        let is_trusted = |ip: IpAddr| {
+            self.config
+                .trusted_proxies
+                .iter()
+                .any(|net| net.contains(ip))
+        };
+
+        if !is_trusted(remote) {
+            return remote;
+        }
+
+        // later entries were added by closer proxies, so the first untrusted one from the end is
+        // the most distant address that can be believed
+        let forwarded: Vec<IpAddr> = headers
+            .get_all("x-forwarded-for")
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .filter_map(|item| item.trim().parse().ok())
+            .map(canonical)
+            .collect();
+
+        let mut result = remote;
+        for ip in forwarded.into_iter().rev() {
+            result = ip;
+            if !is_trusted(ip) {
+                break;
+            }
+        }
+
+        result
+    }
+
+    /// Takes a token for the request, or returns how long to wait before trying again
+    pub fn check(&self, class: RateLimitClass, ip: IpAddr) -> Result<(), Duration> {
+        let config = self.config.bucket(class);
+        if config.per_minute == 0 {
+            return Ok(());
+        }
+
+        let burst = f64::from(config.burst.max(1));
+        let per_second = f64::from(config.per_minute) / 60.0;
+        let now = Instant::now();
+
+        let mut buckets = self.buckets.lock().unwrap();
+
+        if buckets.len() >= SWEEP_THRESHOLD {
+            let limits = &self.config;
+            buckets.retain(|(class, _), bucket| {
+                let config = limits.bucket(*class);
+                let refill = now.duration_since(bucket.updated).as_secs_f64()
+                    * f64::from(config.per_minute)
+                    / 60.0;
+                bucket.tokens + refill < f64::from(config.burst.max(1))
+            });
+        }
+
+        let bucket = buckets.entry((class, bucket_key(ip))).or_insert(Bucket {
+            tokens: burst,
+            updated: now,
+        });
+
+        bucket.tokens = (bucket.tokens
+            + now.duration_since(bucket.updated).as_secs_f64() * per_second)
+            .min(burst);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / per_second))
+        }
+    }
+}
@@ -5,10 +5,7 @@ use std::borrow::Cow;
 #[serde(tag = "type")]
 #[serde(rename_all = "snake_case")]
 pub enum RespFlagDetails<'a> {
-    Post {
-        #[serde(borrow)]
-        post: RespPostListPost<'a>,
-    },
+    Post { post: RespPostListPost<'a> },
 }
 
 #[derive(Deserialize, Debug)]
@@ -17,7 +14,6 @@ pub struct RespFlagInfo<'a> {
     pub flagger: RespMinimalAuthorInfo<'a>,
     pub created_local: Cow<'a, str>,
     pub content: Option<JustContentText<'a>>,
-    #[serde(borrow)]
     #[serde(flatten)]
     pub details: RespFlagDetails<'a>,
 }
@@ -45,10 +41,8 @@ pub struct RespSomePostInfo<'a> {
     #[serde(flatten)]
     pub base: RespMinimalPostInfo<'a>,
     pub href: Option<Cow<'a, str>>,
-    #[serde(borrow)]
     pub author: Option<RespMinimalAuthorInfo<'a>>,
     pub created: Cow<'a, str>,
-    #[serde(borrow)]
     pub community: RespMinimalCommunityInfo<'a>,
     pub sticky: bool,
 }
@@ -61,7 +55,7 @@ impl<'a> AsRef<RespMinimalPostInfo<'a>> for RespSomePostInfo<'a> {
 
 #[derive(Deserialize, Debug)]
 pub struct RespPostListPost<'a> {
-    #[serde(flatten, borrow)]
+    #[serde(flatten)]
     pub base: RespSomePostInfo<'a>,
     pub replies_count_total: i64,
 }
@@ -78,7 +72,6 @@ pub enum RespThingInfo<'a> {
     #[serde(rename = "post")]
     Post(RespPostListPost<'a>),
     #[serde(rename = "comment")]
-    #[serde(borrow)]
     Comment(RespThingComment<'a>),
 }
 
@@ -97,7 +90,6 @@ pub struct RespThingComment<'a> {
     pub base: RespMinimalCommentInfo<'a>,
 
     pub created: Cow<'a, str>,
-    #[serde(borrow)]
     pub post: RespMinimalPostInfo<'a>,
 }
 
@@ -138,7 +130,6 @@ pub struct RespCommentInfo<'a> {
     pub base: RespPostCommentInfo<'a>,
 
     pub parent: Option<JustID>,
-    #[serde(borrow)]
     pub post: Option<RespMinimalPostInfo<'a>>,
 }
 
@@ -150,7 +141,7 @@ impl<'a> AsRef<RespPostCommentInfo<'a>> for RespCommentInfo<'a> {
 
 #[derive(Deserialize, Debug)]
 pub struct RespPostInfo<'a> {
-    #[serde(flatten, borrow)]
+    #[serde(flatten)]
     pub base: RespSomePostInfo<'a>,
 
     pub content_text: Option<Cow<'a, str>>,
@@ -239,6 +230,15 @@ pub struct RespPermissionInfo {
     pub allowed: bool,
 }
 
+#[derive(Deserialize, Debug)]
+pub struct RespLoginSession<'a> {
+    pub id: i64,
+    pub created: Cow<'a, str>,
+    pub user_agent: Option<Cow<'a, str>>,
+    #[serde(default)]
+    pub current: bool,
+}
+
 #[derive(Deserialize, Debug)]
 pub struct Empty {}
 
@@ -247,6 +247,11 @@ pub struct JustID {
     pub id: i64,
 }
 
+#[derive(Deserialize, Debug)]
+pub struct JustToken<'a> {
+    pub token: Cow<'a, str>,
+}
+
 #[derive(Deserialize, Debug)]
 pub struct JustStringID<'a> {
     pub id: &'a str,
@@ -267,6 +272,11 @@ pub struct RespCommunityFeeds<'a> {
     pub atom: RespCommunityFeedsType<'a>,
 }
 
+#[derive(Deserialize, Debug)]
+pub struct CommunitiesCreateResponse {
+    pub community: JustID,
+}
+
 #[derive(Deserialize, Debug)]
 pub struct RespCommunityInfoMaybeYour<'a> {
     #[serde(flatten)]
@@ -319,6 +329,24 @@ pub struct InvitationsCreateResponse<'a> {
     pub key: Cow<'a, str>,
 }
 
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum RespLookupKind {
+    Community,
+    User,
+    Post,
+    Comment,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RespLookupResult {
+    pub id: i64,
+    #[serde(rename = "type")]
+    pub kind: RespLookupKind,
+}
+
 #[derive(Deserialize, Debug)]
 #[serde(tag = "type")]
 #[serde(rename_all = "snake_case")]
@@ -367,22 +395,18 @@ pub struct RespSiteModlogEvent<'a> {
 pub enum RespNotificationInfo<'a> {
     PostReply {
         reply: RespPostCommentInfo<'a>,
-        #[serde(borrow)]
         post: RespPostListPost<'a>,
     },
     PostMention {
-        #[serde(borrow)]
         post: RespPostListPost<'a>,
     },
     CommentReply {
         reply: RespPostCommentInfo<'a>,
         comment: RespPostCommentInfo<'a>,
-        #[serde(borrow)]
         post: RespPostListPost<'a>,
     },
     CommentMention {
         comment: RespPostCommentInfo<'a>,
-        #[serde(borrow)]
         post: RespPostListPost<'a>,
     },
     #[serde(other)]
@@ -392,7 +416,6 @@ pub enum RespNotificationInfo<'a> {
 #[derive(Deserialize, Debug)]
 pub struct RespNotification<'a> {
     #[serde(flatten)]
-    #[serde(borrow)]
     pub info: RespNotificationInfo<'a>,
 
     pub unseen: bool,
@@ -0,0 +1,835 @@
+use crate::accounts::Accounts;
+use crate::backend::Backend;
+use crate::components::{HTPage, MaybeFillInput, TimeAgo};
+use crate::lang;
+use crate::rate_limit::RateLimitClass;
+use crate::resp_types::RespUserInfo;
+use crate::routes::{
+    accounts_redirect, check_rate_limit, fetch_base_data, fetch_with_base_data,
+    get_cookie_map_for_headers, get_cookie_map_for_req, html_response, CookieMap,
+};
+use serde_derive::Deserialize;
+use std::borrow::Cow;
+use std::sync::Arc;
+
+const MIN_PASSWORD_LENGTH: usize = 8;
+
+/// Passwords at least this long are accepted without mixing character classes
+const PASSPHRASE_LENGTH: usize = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum PasswordProblem {
+    TooShort,
+    TooSimple,
+    ContainsUsername,
+}
+
+impl PasswordProblem {
+    fn lang_key(self) -> lang::LangKey<'static> {
+        match self {
+            PasswordProblem::TooShort => lang::user_security_password_too_short(),
+            PasswordProblem::TooSimple => lang::user_security_password_too_simple(),
+            PasswordProblem::ContainsUsername => lang::user_security_password_contains_username(),
+        }
+    }
+}
+
+/// Rough check for easily guessed passwords, so they can be rejected with a reason
+fn check_password_strength(password: &str, username: &str) -> Result<(), PasswordProblem> {
+    let length = password.chars().count();
+    if length < MIN_PASSWORD_LENGTH {
+        return Err(PasswordProblem::TooShort);
+    }
+
+    if username.chars().count() >= 3 && password.to_lowercase().contains(&username.to_lowercase()) {
+        return Err(PasswordProblem::ContainsUsername);
+    }
+
+    let mut chars = password.chars();
+    let first = chars.next();
+    if chars.all(|c| Some(c) == first) {
+        return Err(PasswordProblem::TooSimple);
+    }
+
+    let classes = [
+        password.chars().any(char::is_lowercase),
+        password.chars().any(char::is_uppercase),
+        password.chars().any(|c| c.is_ascii_digit()),
+        password.chars().any(|c| !c.is_alphanumeric()),
+    ]
+    .iter()
+    .filter(|x| **x)
+    .count();
+
+    if classes < 2 && length < PASSPHRASE_LENGTH {
+        return Err(PasswordProblem::TooSimple);
+    }
+
+    Ok(())
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+enum SecurityUpdate {
+    Password,
+    Email,
+    Session,
+}
+
+#[derive(Deserialize)]
+struct SecurityQuery {
+    updated: Option<SecurityUpdate>,
+}
+
+/// Ensures the current login belongs to `user_id`, returning their username
+async fn require_self(
+    ctx: &crate::RouteContext,
+    headers: &hyper::header::HeaderMap,
+    cookies: &CookieMap<'_>,
+    user_id: i64,
+) -> Result<String, crate::Error> {
+    let backend = Backend::for_request(ctx, headers, cookies);
+
+    let login = backend.get_login_current().await?;
+    if login.map(|login| login.user.id) != Some(user_id) {
+        let lang = crate::get_lang_for_headers(&ctx, headers);
+
+        return Err(crate::Error::UserError(crate::simple_response(
+            hyper::StatusCode::FORBIDDEN,
+            lang.tr(&lang::user_edit_not_you()).into_owned(),
+        )));
+    }
+
+    Ok(backend
+        .get_user(user_id, false)
+        .await?
+        .base
+        .username
+        .into_owned())
+}
+
+fn redirect_updated(
+    user_id: i64,
+    updated: &str,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    Ok(hyper::Response::builder()
+        .status(hyper::StatusCode::SEE_OTHER)
+        .header(
+            hyper::header::LOCATION,
+            format!("/users/{}/security?updated={}", user_id, updated),
+        )
+        .body("Successfully updated.".into())?)
+}
+
+async fn page_user_security(
+    params: (i64,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (user_id,) = params;
+
+    let query: SecurityQuery = serde_urlencoded::from_str(req.uri().query().unwrap_or(""))?;
+    let cookies = get_cookie_map_for_req(&req)?;
+
+    page_user_security_inner(
+        user_id,
+        ctx,
+        req.headers(),
+        &cookies,
+        query.updated,
+        None,
+        None,
+    )
+    .await
+}
+
+async fn page_user_security_inner(
+    user_id: i64,
+    ctx: Arc<crate::RouteContext>,
+    headers: &hyper::header::HeaderMap,
+    cookies: &CookieMap<'_>,
+    updated: Option<SecurityUpdate>,
+    display_error: Option<String>,
+    prev_values: Option<&serde_json::Value>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let lang = crate::get_lang_for_headers(&ctx, headers);
+    let base_data = fetch_base_data(&ctx, headers, cookies).await?;
+
+    let title = lang.tr(&lang::USER_SECURITY_TITLE);
+
+    let is_me = match &base_data.login {
+        None => false,
+        Some(login) => login.user.id == user_id,
+    };
+
+    if !is_me {
+        let mut res = html_response(render::html! {
+            <HTPage base_data={&base_data} lang={&lang} title={&title}>
+                <h1>{title.as_ref()}</h1>
+                <div class={"errorBox"}>{lang.tr(&lang::user_edit_not_you())}</div>
+            </HTPage>
+        });
+
+        *res.status_mut() = hyper::StatusCode::FORBIDDEN;
+
+        return Ok(res);
+    }
+
+    let sessions = Backend::for_request(&ctx, headers, cookies)
+        .list_logins()
+        .await?;
+
+    let updated_message = updated.map(|updated| match updated {
+        SecurityUpdate::Password => lang.tr(&lang::USER_SECURITY_PASSWORD_UPDATED),
+        SecurityUpdate::Email => lang.tr(&lang::USER_SECURITY_EMAIL_UPDATED),
+        SecurityUpdate::Session => lang.tr(&lang::USER_SECURITY_SESSION_REVOKED),
+    });
+
+    Ok(html_response(render::html! {
+        <HTPage base_data={&base_data} lang={&lang} title={&title}>
+            <h1>{title.as_ref()}</h1>
+            {
+                display_error.map(|msg| {
+                    render::rsx! {
+                        <div class={"errorBox"}>{msg}</div>
+                    }
+                })
+            }
+            {
+                updated_message.map(|msg| {
+                    render::rsx! {
+                        <div class={"infoBox"}>{msg}</div>
+                    }
+                })
+            }
+            <h2>{lang.tr(&lang::user_security_password_heading())}</h2>
+            <form method={"POST"} action={format!("/users/{}/security/password", user_id)}>
+                <table>
+                    <tr>
+                        <td><label for={"input_current_password"}>{lang.tr(&lang::user_security_current_password_prompt())}</label></td>
+                        <td>
+                            <input r#type={"password"} name={"current_password"} id={"input_current_password"} required={""} autocomplete={"current-password"} />
+                        </td>
+                    </tr>
+                    <tr>
+                        <td><label for={"input_new_password"}>{lang.tr(&lang::user_security_new_password_prompt())}</label></td>
+                        <td>
+                            <input r#type={"password"} name={"new_password"} id={"input_new_password"} required={""} minlength={MIN_PASSWORD_LENGTH.to_string()} autocomplete={"new-password"} />
+                        </td>
+                    </tr>
+                    <tr>
+                        <td><label for={"input_new_password_confirm"}>{lang.tr(&lang::user_security_new_password_confirm_prompt())}</label></td>
+                        <td>
+                            <input r#type={"password"} name={"new_password_confirm"} id={"input_new_password_confirm"} required={""} minlength={MIN_PASSWORD_LENGTH.to_string()} autocomplete={"new-password"} />
+                        </td>
+                    </tr>
+                </table>
+                <p>{lang.tr(&lang::user_security_password_hint())}</p>
+                <button r#type={"submit"}>{lang.tr(&lang::submit())}</button>
+            </form>
+            <h2>{lang.tr(&lang::user_security_email_heading())}</h2>
+            <p>{lang.tr(&lang::user_security_email_info())}</p>
+            <form method={"POST"} action={format!("/users/{}/security/email", user_id)}>
+                <table>
+                    <tr>
+                        <td><label for={"input_email_address"}>{lang.tr(&lang::user_security_email_prompt())}</label></td>
+                        <td>
+                            <MaybeFillInput values={&prev_values} r#type={"email"} name={"email_address"} required={true} id={"input_email_address"} />
+                        </td>
+                    </tr>
+                    <tr>
+                        <td><label for={"input_email_current_password"}>{lang.tr(&lang::user_security_current_password_prompt())}</label></td>
+                        <td>
+                            <input r#type={"password"} name={"current_password"} id={"input_email_current_password"} required={""} autocomplete={"current-password"} />
+                        </td>
+                    </tr>
+                </table>
+                <button r#type={"submit"}>{lang.tr(&lang::submit())}</button>
+            </form>
+            <h2>{lang.tr(&lang::user_security_sessions_heading())}</h2>
+            {
+                sessions.is_none().then(|| render::rsx! {
+                    <p>{lang.tr(&lang::USER_SECURITY_SESSIONS_UNAVAILABLE)}</p>
+                })
+            }
+            {
+                sessions.as_ref().map(|sessions| {
+                    render::rsx! {
+                        <ul>
+                            {
+                                sessions.items.iter().map(|session| {
+                                    render::rsx! {
+                                        <li>
+                                            {
+                                                match &session.user_agent {
+                                                    Some(user_agent) => Cow::Borrowed(user_agent.as_ref()),
+                                                    None => lang.tr(&lang::USER_SECURITY_SESSION_UNKNOWN_DEVICE),
+                                                }
+                                            }
+                                            {" - "}
+                                            <TimeAgo since={chrono::DateTime::parse_from_rfc3339(&session.created).unwrap()} lang={&lang} />
+                                            {" "}
+                                            {
+                                                if session.current {
+                                                    Some(render::rsx! {
+                                                        <em>{lang.tr(&lang::USER_SECURITY_SESSION_CURRENT)}</em>
+                                                    })
+                                                } else {
+                                                    None
+                                                }
+                                            }
+                                            {
+                                                if session.current {
+                                                    None
+                                                } else {
+                                                    Some(render::rsx! {
+                                                        <form class={"inline"} method={"POST"} action={format!("/users/{}/security/sessions/{}/revoke", user_id, session.id)}>
+                                                            <button r#type={"submit"}>{lang.tr(&lang::USER_SECURITY_SESSION_REVOKE)}</button>
+                                                        </form>
+                                                    })
+                                                }
+                                            }
+                                        </li>
+                                    }
+                                })
+                                .collect::<Vec<_>>()
+                            }
+                        </ul>
+                    }
+                })
+            }
+            <form method={"POST"} action={format!("/users/{}/security/sessions/revoke_all", user_id)}>
+                <p>{lang.tr(&lang::user_security_logout_everywhere_info())}</p>
+                <button r#type={"submit"}>{lang.tr(&lang::user_security_logout_everywhere())}</button>
+            </form>
+            <h2>{lang.tr(&lang::user_export_heading())}</h2>
+            <p>{lang.tr(&lang::user_export_info())}</p>
+            <p>
+                <a href={format!("/users/{}/export", user_id)}>{lang.tr(&lang::user_export_download())}</a>
+            </p>
+            <h2>{lang.tr(&lang::USER_DELETE)}</h2>
+            <p>
+                <a href={format!("/users/{}/delete", user_id)}>{lang.tr(&lang::user_delete_link())}</a>
+            </p>
+        </HTPage>
+    }))
+}
+
+async fn handler_user_security_password_submit(
+    params: (i64,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    #[derive(Deserialize)]
+    struct PasswordSubmitBody<'a> {
+        current_password: Cow<'a, str>,
+        new_password: Cow<'a, str>,
+        new_password_confirm: Cow<'a, str>,
+    }
+
+    let (user_id,) = params;
+
+    let (req_parts, body) = req.into_parts();
+    check_rate_limit(&ctx, RateLimitClass::Login, &req_parts)?;
+
+    let cookies = get_cookie_map_for_headers(&req_parts.headers)?;
+    let lang = crate::get_lang_for_headers(&ctx, &req_parts.headers);
+
+    let body = hyper::body::to_bytes(body).await?;
+    let body: PasswordSubmitBody = serde_urlencoded::from_bytes(&body)?;
+
+    let username = require_self(&ctx, &req_parts.headers, &cookies, user_id).await?;
+
+    let problem = if body.new_password != body.new_password_confirm {
+        Some(lang.tr(&lang::USER_SECURITY_PASSWORD_MISMATCH).into_owned())
+    } else if let Err(problem) = check_password_strength(&body.new_password, &username) {
+        Some(lang.tr(&problem.lang_key()).into_owned())
+    } else {
+        None
+    };
+
+    if let Some(problem) = problem {
+        return page_user_security_inner(
+            user_id,
+            ctx,
+            &req_parts.headers,
+            &cookies,
+            None,
+            Some(problem),
+            None,
+        )
+        .await;
+    }
+
+    let backend = Backend::for_request(&ctx, &req_parts.headers, &cookies);
+
+    if !backend
+        .verify_password(&username, &body.current_password)
+        .await?
+    {
+        return page_user_security_inner(
+            user_id,
+            ctx,
+            &req_parts.headers,
+            &cookies,
+            None,
+            Some(
+                lang.tr(&lang::user_security_password_incorrect())
+                    .into_owned(),
+            ),
+            None,
+        )
+        .await;
+    }
+
+    match backend
+        .update_user_me(&serde_json::json!({ "password": body.new_password }))
+        .await
+    {
+        Ok(()) => redirect_updated(user_id, "password"),
+        Err(crate::Error::RemoteError((status, message))) if status.is_client_error() => {
+            page_user_security_inner(
+                user_id,
+                ctx,
+                &req_parts.headers,
+                &cookies,
+                None,
+                Some(message),
+                None,
+            )
+            .await
+        }
+        Err(other) => Err(other),
+    }
+}
+
+async fn handler_user_security_email_submit(
+    params: (i64,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (user_id,) = params;
+
+    let (req_parts, body) = req.into_parts();
+    check_rate_limit(&ctx, RateLimitClass::Login, &req_parts)?;
+
+    let cookies = get_cookie_map_for_headers(&req_parts.headers)?;
+    let lang = crate::get_lang_for_headers(&ctx, &req_parts.headers);
+
+    let body = hyper::body::to_bytes(body).await?;
+    let mut body: serde_json::Value = serde_urlencoded::from_bytes(&body)?;
+
+    let current_password = body
+        .as_object_mut()
+        .and_then(|body| body.remove("current_password"));
+    let current_password = current_password
+        .as_ref()
+        .and_then(serde_json::Value::as_str)
+        .unwrap_or("");
+
+    let username = require_self(&ctx, &req_parts.headers, &cookies, user_id).await?;
+
+    let backend = Backend::for_request(&ctx, &req_parts.headers, &cookies);
+
+    if !backend.verify_password(&username, current_password).await? {
+        return page_user_security_inner(
+            user_id,
+            ctx,
+            &req_parts.headers,
+            &cookies,
+            None,
+            Some(
+                lang.tr(&lang::user_security_password_incorrect())
+                    .into_owned(),
+            ),
+            Some(&body),
+        )
+        .await;
+    }
+
+    match backend
+        .update_user_me(&serde_json::json!({ "email_address": body.get("email_address") }))
+        .await
+    {
+        Ok(()) => redirect_updated(user_id, "email"),
+        Err(crate::Error::RemoteError((status, message))) if status.is_client_error() => {
+            page_user_security_inner(
+                user_id,
+                ctx,
+                &req_parts.headers,
+                &cookies,
+                None,
+                Some(message),
+                Some(&body),
+            )
+            .await
+        }
+        Err(other) => Err(other),
+    }
+}
+
+async fn handler_user_security_session_revoke(
+    params: (i64, i64),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (user_id, login_id) = params;
+
+    let cookies = get_cookie_map_for_req(&req)?;
+
+    require_self(&ctx, req.headers(), &cookies, user_id).await?;
+
+    match Backend::for_request(&ctx, req.headers(), &cookies)
+        .delete_login(login_id)
+        .await
+    {
+        Ok(()) => redirect_updated(user_id, "session"),
+        Err(crate::Error::RemoteError((status, message))) if status.is_client_error() => {
+            page_user_security_inner(
+                user_id,
+                ctx,
+                req.headers(),
+                &cookies,
+                None,
+                Some(message),
+                None,
+            )
+            .await
+        }
+        Err(other) => Err(other),
+    }
+}
+
+async fn handler_user_security_session_revoke_all(
+    params: (i64,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (user_id,) = params;
+
+    let cookies = get_cookie_map_for_req(&req)?;
+
+    require_self(&ctx, req.headers(), &cookies, user_id).await?;
+
+    match Backend::for_request(&ctx, req.headers(), &cookies)
+        .delete_all_logins()
+        .await
+    {
+        Ok(()) => accounts_redirect(
+            "/",
+            Accounts::logged_out_set_cookies(&ctx.cookie_key, &cookies, ctx.secure_cookies())?,
+            "Successfully logged out.",
+        ),
+        Err(crate::Error::RemoteError((status, message))) if status.is_client_error() => {
+            page_user_security_inner(
+                user_id,
+                ctx,
+                req.headers(),
+                &cookies,
+                None,
+                Some(message),
+                None,
+            )
+            .await
+        }
+        Err(other) => Err(other),
+    }
+}
+
+async fn handler_user_export(
+    params: (i64,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (user_id,) = params;
+
+    let cookies = get_cookie_map_for_req(&req)?;
+
+    let username = require_self(&ctx, req.headers(), &cookies, user_id).await?;
+    let file_name: String = username
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '-')
+        .collect();
+
+    let (mut sender, body) = hyper::Body::channel();
+    let headers = req.headers().clone();
+
+    tokio::spawn(async move {
+        let res = async {
+            let cookies = get_cookie_map_for_headers(&headers)?;
+            let backend = Backend::for_request(&ctx, &headers, &cookies);
+
+            crate::data_export::write_export(&backend, user_id, &mut sender).await
+        }
+        .await;
+
+        if let Err(err) = res {
+            log::error!("Failed to export data for user {}: {:?}", user_id, err);
+            sender.abort();
+        }
+    });
+
+    Ok(hyper::Response::builder()
+        .header(hyper::header::CONTENT_TYPE, "application/zip")
+        .header(
+            hyper::header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}-data.zip\"", file_name),
+        )
+        .header(hyper::header::CACHE_CONTROL, "no-store")
+        .body(body)?)
+}
+
+/// Whether the current login may delete `user`, and if so, whether it is their own account
+fn user_delete_permission(
+    base_data: &crate::PageBaseData,
+    user: &RespUserInfo<'_>,
+) -> Option<bool> {
+    let login = base_data.login.as_ref()?;
+
+    if login.user.id == user.base.id {
+        Some(true)
+    } else if login.user.is_site_admin && user.base.local {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+async fn page_user_delete(
+    params: (i64,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (user_id,) = params;
+
+    let cookies = get_cookie_map_for_req(&req)?;
+
+    page_user_delete_inner(user_id, ctx, req.headers(), &cookies, None).await
+}
+
+async fn page_user_delete_inner(
+    user_id: i64,
+    ctx: Arc<crate::RouteContext>,
+    headers: &hyper::header::HeaderMap,
+    cookies: &CookieMap<'_>,
+    display_error: Option<String>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let lang = crate::get_lang_for_headers(&ctx, headers);
+
+    let backend = Backend::for_request(&ctx, headers, cookies);
+    let (base_data, user) = fetch_with_base_data(&ctx, headers, cookies, |include_your| {
+        backend.get_user(user_id, include_your)
+    })
+    .await?;
+
+    let title = lang.tr(&lang::USER_DELETE);
+
+    let is_me = match user_delete_permission(&base_data, &user) {
+        Some(is_me) => is_me,
+        None => {
+            let mut res = html_response(render::html! {
+                <HTPage base_data={&base_data} lang={&lang} title={&title}>
+                    <h1>{title.as_ref()}</h1>
+                    <div class={"errorBox"}>{lang.tr(&lang::user_delete_not_allowed())}</div>
+                </HTPage>
+            });
+
+            *res.status_mut() = hyper::StatusCode::FORBIDDEN;
+
+            return Ok(res);
+        }
+    };
+
+    Ok(html_response(render::html! {
+        <HTPage base_data={&base_data} lang={&lang} title={&title}>
+            <h1>{title.as_ref()}</h1>
+            <p><em>{format!("@{}@{}", user.base.username, user.base.host)}</em></p>
+            {
+                display_error.map(|msg| {
+                    render::rsx! {
+                        <div class={"errorBox"}>{msg}</div>
+                    }
+                })
+            }
+            <p>
+                {
+                    if is_me {
+                        lang.tr(&lang::USER_DELETE_INFO)
+                    } else {
+                        lang.tr(&lang::USER_DELETE_ADMIN_INFO)
+                    }
+                }
+            </p>
+            <form method={"POST"} action={format!("/users/{}/delete/submit", user_id)}>
+                <div>
+                    <label>
+                        {lang.tr(&lang::user_delete_password_prompt())}
+                        {" "}
+                        <input r#type={"password"} name={"password"} required={""} autocomplete={"current-password"} />
+                    </label>
+                </div>
+                <a href={format!("/users/{}", user_id)}>{lang.tr(&lang::no_cancel())}</a>
+                {" "}
+                <button r#type={"submit"}>{lang.tr(&lang::user_delete_yes())}</button>
+            </form>
+        </HTPage>
+    }))
+}
+
+async fn handler_user_delete_submit(
+    params: (i64,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    #[derive(Deserialize)]
+    struct DeleteSubmitBody<'a> {
+        password: Cow<'a, str>,
+    }
+
+    let (user_id,) = params;
+
+    let (req_parts, body) = req.into_parts();
+    check_rate_limit(&ctx, RateLimitClass::Login, &req_parts)?;
+
+    let cookies = get_cookie_map_for_headers(&req_parts.headers)?;
+    let lang = crate::get_lang_for_headers(&ctx, &req_parts.headers);
+
+    let body = hyper::body::to_bytes(body).await?;
+    let body: DeleteSubmitBody = serde_urlencoded::from_bytes(&body)?;
+
+    let backend = Backend::for_request(&ctx, &req_parts.headers, &cookies);
+
+    let (base_data, user) = fetch_with_base_data(&ctx, &req_parts.headers, &cookies, |_| {
+        backend.get_user(user_id, false)
+    })
+    .await?;
+
+    let is_me = match user_delete_permission(&base_data, &user) {
+        Some(is_me) => is_me,
+        None => {
+            return Err(crate::Error::UserError(crate::simple_response(
+                hyper::StatusCode::FORBIDDEN,
+                lang.tr(&lang::user_delete_not_allowed()).into_owned(),
+            )));
+        }
+    };
+
+    // the password checked is always the one for the account doing the deleting
+    let username = if is_me {
+        Cow::Borrowed(user.base.username.as_ref())
+    } else {
+        let login_user_id = base_data.login.as_ref().unwrap().user.id;
+        backend.get_user(login_user_id, false).await?.base.username
+    };
+
+    if !backend.verify_password(&username, &body.password).await? {
+        return page_user_delete_inner(
+            user_id,
+            ctx,
+            &req_parts.headers,
+            &cookies,
+            Some(
+                lang.tr(&lang::user_security_password_incorrect())
+                    .into_owned(),
+            ),
+        )
+        .await;
+    }
+
+    match backend.delete_user(user_id).await {
+        Ok(()) => {}
+        Err(crate::Error::RemoteError((status, message))) if status.is_client_error() => {
+            return page_user_delete_inner(
+                user_id,
+                ctx,
+                &req_parts.headers,
+                &cookies,
+                Some(message),
+            )
+            .await;
+        }
+        Err(other) => return Err(other),
+    }
+
+    let title = lang.tr(&lang::USER_DELETE);
+
+    if is_me {
+        let base_data = crate::PageBaseData {
+            login: None,
+            ..base_data
+        };
+
+        let mut res = html_response(render::html! {
+            <HTPage base_data={&base_data} lang={&lang} title={&title}>
+                <h1>{title.as_ref()}</h1>
+                <p>{lang.tr(&lang::user_delete_complete())}</p>
+            </HTPage>
+        });
+
+        for value in
+            Accounts::logged_out_set_cookies(&ctx.cookie_key, &cookies, ctx.secure_cookies())?
+        {
+            res.headers_mut().append(
+                hyper::header::SET_COOKIE,
+                hyper::header::HeaderValue::from_str(&value)?,
+            );
+        }
+
+        Ok(res)
+    } else {
+        Ok(html_response(render::html! {
+            <HTPage base_data={&base_data} lang={&lang} title={&title}>
+                <h1>{title.as_ref()}</h1>
+                <p>{lang.tr(&lang::user_delete_complete_admin())}</p>
+                <p><a href={"/administration"}>{lang.tr(&lang::administration())}</a></p>
+            </HTPage>
+        }))
+    }
+}
+
+pub fn route_user_delete() -> crate::RouteNode<(i64,)> {
+    crate::RouteNode::new()
+        .with_handler_async(hyper::Method::GET, page_user_delete)
+        .with_child(
+            "submit",
+            crate::RouteNode::new()
+                .with_handler_async(hyper::Method::POST, handler_user_delete_submit),
+        )
+}
+
+pub fn route_user_export() -> crate::RouteNode<(i64,)> {
+    crate::RouteNode::new().with_handler_async(hyper::Method::GET, handler_user_export)
+}
+
+pub fn route_user_security() -> crate::RouteNode<(i64,)> {
+    crate::RouteNode::new()
+        .with_handler_async(hyper::Method::GET, page_user_security)
+        .with_child(
+            "email",
+            crate::RouteNode::new()
+                .with_handler_async(hyper::Method::POST, handler_user_security_email_submit),
+        )
+        .with_child(
+            "password",
+            crate::RouteNode::new()
+                .with_handler_async(hyper::Method::POST, handler_user_security_password_submit),
+        )
+        .with_child(
+            "sessions",
+            crate::RouteNode::new()
+                .with_child(
+                    "revoke_all",
+                    crate::RouteNode::new().with_handler_async(
+                        hyper::Method::POST,
+                        handler_user_security_session_revoke_all,
+                    ),
+                )
+                .with_child_parse::<i64, _>(crate::RouteNode::new().with_child(
+                    "revoke",
+                    crate::RouteNode::new().with_handler_async(
+                        hyper::Method::POST,
+                        handler_user_security_session_revoke,
+                    ),
+                )),
+        )
+}
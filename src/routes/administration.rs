@@ -1,14 +1,12 @@
 use super::{
-    fetch_base_data, for_client, get_cookie_map_for_headers, get_cookie_map_for_req, html_response,
-    res_to_error, CookieMap,
+    fetch_base_data, get_cookie_map_for_headers, get_cookie_map_for_req, html_response, CookieMap,
 };
+use crate::backend::Backend;
 use crate::components::{HTPage, MaybeFillOption, MaybeFillTextArea};
 use crate::lang;
-use crate::resp_types::RespInstanceInfo;
 use render::Render;
 use std::borrow::Cow;
 use std::collections::HashMap;
-use std::convert::TryInto;
 use std::sync::Arc;
 
 async fn page_administration(
@@ -17,10 +15,9 @@ async fn page_administration(
     req: hyper::Request<hyper::Body>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
     let cookies = get_cookie_map_for_req(&req)?;
-    let lang = crate::get_lang_for_req(&req);
+    let lang = crate::get_lang_for_req(&ctx, &req);
 
-    let base_data =
-        fetch_base_data(&ctx.backend_host, &ctx.http_client, req.headers(), &cookies).await?;
+    let base_data = fetch_base_data(&ctx, req.headers(), &cookies).await?;
 
     let title = lang.tr(&lang::ADMINISTRATION);
 
@@ -35,23 +32,16 @@ async fn page_administration(
         }));
     }
 
-    let api_res = res_to_error(
-        ctx.http_client
-            .get(
-                format!("{}/api/unstable/instance", ctx.backend_host)
-                    .try_into()
-                    .unwrap(),
-            )
-            .await?,
-    )
-    .await?;
-    let api_res = hyper::body::to_bytes(api_res.into_body()).await?;
-    let api_res: RespInstanceInfo = serde_json::from_slice(&api_res)?;
+    let api_res = Backend::for_request(&ctx, req.headers(), &cookies)
+        .get_instance()
+        .await?;
 
     Ok(html_response(render::html! {
         <HTPage base_data={&base_data} lang={&lang} title={&title}>
             <h1>{title.as_ref()}</h1>
             <a href={"/administration/edit"}>{lang.tr(&lang::administration_edit())}</a>
+            <br />
+            <a href={"/administration/invitations"}>{lang.tr(&lang::administration_invitations())}</a>
             <ul>
                 <li>
                     {
@@ -139,9 +129,9 @@ async fn page_administration_edit_inner(
     display_error: Option<String>,
     prev_values: Option<&HashMap<Cow<'_, str>, serde_json::Value>>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
-    let lang = crate::get_lang_for_headers(headers);
+    let lang = crate::get_lang_for_headers(&ctx, headers);
 
-    let base_data = fetch_base_data(&ctx.backend_host, &ctx.http_client, headers, cookies).await?;
+    let base_data = fetch_base_data(&ctx, headers, cookies).await?;
 
     let title = lang.tr(&lang::ADMINISTRATION_EDIT);
 
@@ -156,18 +146,9 @@ async fn page_administration_edit_inner(
         }));
     }
 
-    let api_res = res_to_error(
-        ctx.http_client
-            .get(
-                format!("{}/api/unstable/instance", ctx.backend_host)
-                    .try_into()
-                    .unwrap(),
-            )
-            .await?,
-    )
-    .await?;
-    let api_res = hyper::body::to_bytes(api_res.into_body()).await?;
-    let api_res: RespInstanceInfo = serde_json::from_slice(&api_res)?;
+    let api_res = Backend::for_request(&ctx, headers, cookies)
+        .get_instance()
+        .await?;
 
     let signup_allowed_value = Some(crate::bool_as_str(api_res.signup_allowed));
     let invitations_enabled_value = Some(crate::bool_as_str(api_res.invitations_enabled));
@@ -333,17 +314,9 @@ async fn handler_administration_edit_submit(
         body.insert(format!("description_{}", format).into(), content.into());
     }
 
-    let api_res = res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::patch(format!("{}/api/unstable/instance", ctx.backend_host,))
-                    .body(serde_json::to_vec(&body)?.into())?,
-                &req_parts.headers,
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await;
+    let api_res = Backend::for_request(&ctx, &req_parts.headers, &cookies)
+        .update_instance(&body)
+        .await;
 
     match api_res {
         Err(crate::Error::RemoteError((_, message))) => {
@@ -379,4 +352,8 @@ pub fn route_administration() -> crate::RouteNode<()> {
                     ),
                 ),
         )
+        .with_child(
+            "invitations",
+            super::invitations::route_administration_invitations(),
+        )
 }
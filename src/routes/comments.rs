@@ -1,14 +1,17 @@
 use super::{
-    default_comments_sort, fetch_base_data, for_client, get_cookie_map_for_headers,
-    get_cookie_map_for_req, html_response, res_to_error, CookieMap, JustStringID, ReturnToParams,
+    check_rate_limit, fetch_base_data, fetch_with_base_data, get_cookie_map_for_headers,
+    get_cookie_map_for_req, html_response, CookieMap, ReturnToParams,
 };
+use crate::backend::Backend;
 use crate::components::{
     Comment, ContentView, HTPage, IconExt, MaybeFillCheckbox, MaybeFillTextArea, TimeAgo, UserLink,
 };
 use crate::lang;
-use crate::resp_types::{JustContentHTML, JustID, RespCommentInfo, RespList, RespPostCommentInfo};
+use crate::preferences::Preferences;
+use crate::query_types::ReplyListQuery;
+use crate::rate_limit::RateLimitClass;
 use crate::util::{abbreviate_link, author_is_me};
-use serde_derive::{Deserialize, Serialize};
+use serde_derive::Deserialize;
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -45,75 +48,40 @@ async fn page_comment_inner(
     prev_values: Option<&HashMap<Cow<'_, str>, serde_json::Value>>,
     display_preview: Option<&str>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
-    let lang = crate::get_lang_for_headers(headers);
+    let lang = crate::get_lang_for_headers(&ctx, headers);
 
     #[derive(Deserialize)]
     struct Query<'a> {
-        #[serde(default = "default_comments_sort")]
-        sort: crate::SortType,
+        sort: Option<crate::SortType>,
         page: Option<Cow<'a, str>>,
     }
 
     let query: Query = serde_urlencoded::from_str(query.unwrap_or(""))?;
+    let sort = query
+        .sort
+        .unwrap_or(Preferences::from_cookies(&ctx.cookie_key, cookies).comment_sort);
+
+    let backend = Backend::for_request(&ctx, headers, cookies);
+
+    let (base_data, (comment, replies)) =
+        fetch_with_base_data(&ctx, headers, cookies, |include_your| {
+            let backend = &backend;
+            let query = &query;
+
+            async move {
+                let replies_query = ReplyListQuery {
+                    include_your: if include_your { Some(true) } else { None },
+                    sort: Some(sort),
+                    page: query.page.as_deref(),
+                };
 
-    let base_data = fetch_base_data(&ctx.backend_host, &ctx.http_client, headers, cookies).await?;
-
-    let info_api_res = res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::get(format!(
-                    "{}/api/unstable/comments/{}{}",
-                    ctx.backend_host,
-                    comment_id,
-                    if base_data.login.is_some() {
-                        "?include_your=true"
-                    } else {
-                        ""
-                    },
-                ))
-                .body(Default::default())?,
-                headers,
-                cookies,
-            )?)
-            .await?,
-    )
-    .await?;
-    let info_api_res = hyper::body::to_bytes(info_api_res.into_body()).await?;
-    let comment: RespCommentInfo<'_> = serde_json::from_slice(&info_api_res)?;
-
-    #[derive(Serialize)]
-    struct RepliesListQuery<'a> {
-        include_your: Option<bool>,
-        sort: Option<crate::SortType>,
-        page: Option<&'a str>,
-    }
-    let replies_req_query = RepliesListQuery {
-        include_your: if base_data.login.is_some() {
-            Some(true)
-        } else {
-            None
-        },
-        sort: Some(query.sort),
-        page: query.page.as_deref(),
-    };
-    let replies_req_query = serde_urlencoded::to_string(&replies_req_query)?;
-
-    let replies_api_res = res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::get(format!(
-                    "{}/api/unstable/comments/{}/replies?{}",
-                    ctx.backend_host, comment_id, replies_req_query,
-                ))
-                .body(Default::default())?,
-                headers,
-                cookies,
-            )?)
-            .await?,
-    )
-    .await?;
-    let replies_api_res = hyper::body::to_bytes(replies_api_res.into_body()).await?;
-    let replies: RespList<RespPostCommentInfo<'_>> = serde_json::from_slice(&replies_api_res)?;
+                futures_util::try_join!(
+                    backend.get_comment(comment_id, include_your),
+                    backend.list_comment_replies(comment_id, &replies_query),
+                )
+            }
+        })
+        .await?;
 
     let title = lang.tr(&lang::COMMENT);
 
@@ -256,7 +224,7 @@ async fn page_comment_inner(
                     crate::SortType::VALUES.iter()
                         .map(|value| {
                             let name = lang.tr(&value.lang_key()).into_owned();
-                            if query.sort == *value {
+                            if sort == *value {
                                 render::rsx! { <span>{name}</span> }
                             } else {
                                 render::rsx! { <a href={format!("/comments/{}?sort={}", comment_id, value.as_str())}>{name}</a> }
@@ -269,7 +237,7 @@ async fn page_comment_inner(
                 {
                     replies.items.iter().map(|reply| {
                         render::rsx! {
-                            <Comment comment={reply} sort={query.sort} root_sensitive={comment.as_ref().as_ref().sensitive} base_data={&base_data} lang={&lang} />
+                            <Comment comment={reply} sort={sort} root_sensitive={comment.as_ref().as_ref().sensitive} base_data={&base_data} lang={&lang} />
                         }
                     }).collect::<Vec<_>>()
                 }
@@ -277,7 +245,7 @@ async fn page_comment_inner(
             {
                 replies.next_page.as_ref().map(|next_page| {
                     render::rsx! {
-                        <a href={format!("/comments/{}?sort={}&page={}", comment.base.base.id, query.sort.as_str(), next_page)}>{"-> "}{lang.tr(&lang::VIEW_MORE_COMMENTS)}</a>
+                        <a href={format!("/comments/{}?sort={}&page={}", comment.base.base.id, sort.as_str(), next_page)}>{"-> "}{lang.tr(&lang::VIEW_MORE_COMMENTS)}</a>
                     }
                 })
             }
@@ -304,29 +272,17 @@ async fn page_comment_delete_inner(
     cookies: &CookieMap<'_>,
     display_error: Option<String>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
-    let lang = crate::get_lang_for_headers(headers);
-    let base_data = fetch_base_data(&ctx.backend_host, &ctx.http_client, headers, cookies).await?;
+    let lang = crate::get_lang_for_headers(&ctx, headers);
+    let base_data = fetch_base_data(&ctx, headers, cookies).await?;
 
     let referer = headers
         .get(hyper::header::REFERER)
         .and_then(|x| x.to_str().ok());
 
-    let api_res = res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::get(format!(
-                    "{}/api/unstable/comments/{}",
-                    ctx.backend_host, comment_id
-                ))
-                .body(Default::default())?,
-                headers,
-                cookies,
-            )?)
-            .await?,
-    )
-    .await?;
-    let api_res = hyper::body::to_bytes(api_res.into_body()).await?;
-    let comment: RespPostCommentInfo<'_> = serde_json::from_slice(&api_res)?;
+    let comment = Backend::for_request(&ctx, headers, cookies)
+        .get_comment(comment_id, false)
+        .await?
+        .base;
 
     let title = lang.tr(&lang::COMMENT_DELETE_TITLE);
 
@@ -377,20 +333,9 @@ async fn handler_comment_delete_confirm(
     let body = hyper::body::to_bytes(body).await?;
     let body: ReturnToParams = serde_urlencoded::from_bytes(&body)?;
 
-    let api_res = res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::delete(format!(
-                    "{}/api/unstable/comments/{}",
-                    ctx.backend_host, comment_id,
-                ))
-                .body("".into())?,
-                &req_parts.headers,
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await;
+    let api_res = Backend::for_request(&ctx, &req_parts.headers, &cookies)
+        .delete_comment(comment_id)
+        .await;
 
     match api_res {
         Ok(_) => Ok(hyper::Response::builder()
@@ -426,20 +371,9 @@ async fn handler_comment_like(
         .get(hyper::header::REFERER)
         .and_then(|x| x.to_str().ok());
 
-    res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::put(format!(
-                    "{}/api/unstable/comments/{}/your_vote",
-                    ctx.backend_host, comment_id
-                ))
-                .body(Default::default())?,
-                req.headers(),
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await?;
+    Backend::for_request(&ctx, req.headers(), &cookies)
+        .like_comment(comment_id)
+        .await?;
 
     Ok(hyper::Response::builder()
         .status(hyper::StatusCode::SEE_OTHER)
@@ -469,20 +403,9 @@ async fn handler_comment_unlike(
         .get(hyper::header::REFERER)
         .and_then(|x| x.to_str().ok());
 
-    res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::delete(format!(
-                    "{}/api/unstable/comments/{}/your_vote",
-                    ctx.backend_host, comment_id
-                ))
-                .body(Default::default())?,
-                req.headers(),
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await?;
+    Backend::for_request(&ctx, req.headers(), &cookies)
+        .unlike_comment(comment_id)
+        .await?;
 
     Ok(hyper::Response::builder()
         .status(hyper::StatusCode::SEE_OTHER)
@@ -506,8 +429,9 @@ async fn handler_comment_submit_reply(
     let (comment_id,) = params;
 
     let (req_parts, body) = req.into_parts();
+    check_rate_limit(&ctx, RateLimitClass::Submit, &req_parts)?;
 
-    let lang = crate::get_lang_for_headers(&req_parts.headers);
+    let lang = crate::get_lang_for_headers(&ctx, &req_parts.headers);
     let cookies = get_cookie_map_for_headers(&req_parts.headers)?;
 
     let content_type = req_parts
@@ -564,21 +488,10 @@ async fn handler_comment_submit_reply(
                         );
                     }
                     Some(mime) => {
-                        let res = res_to_error(
-                            ctx.http_client
-                                .request(for_client(
-                                    hyper::Request::post(format!(
-                                        "{}/api/unstable/media",
-                                        ctx.backend_host,
-                                    ))
-                                    .header(hyper::header::CONTENT_TYPE, mime.as_ref())
-                                    .body(hyper::Body::wrap_stream(stream))?,
-                                    &req_parts.headers,
-                                    &cookies,
-                                )?)
-                                .await?,
-                        )
-                        .await;
+                        let mime = mime.to_string();
+                        let res = Backend::for_request(&ctx, &req_parts.headers, &cookies)
+                            .upload_media(&mime, hyper::Body::wrap_stream(stream))
+                            .await;
 
                         match res {
                             Err(crate::Error::RemoteError((_, message))) => {
@@ -587,13 +500,10 @@ async fn handler_comment_submit_reply(
                             Err(other) => {
                                 return Err(other);
                             }
-                            Ok(res) => {
-                                let res = hyper::body::to_bytes(res.into_body()).await?;
-                                let res: JustStringID = serde_json::from_slice(&res)?;
-
+                            Ok(media_id) => {
                                 body_values.insert(
                                     "attachment".into(),
-                                    format!("local-media://{}", res.id).into(),
+                                    format!("local-media://{}", media_id).into(),
                                 );
                             }
                         }
@@ -633,27 +543,11 @@ async fn handler_comment_submit_reply(
             .get("content_markdown")
             .and_then(|x| x.as_str())
             .unwrap_or("");
-        let preview_res = res_to_error(
-            ctx.http_client
-                .request(for_client(
-                    hyper::Request::post(format!(
-                        "{}/api/unstable/misc/render_markdown",
-                        ctx.backend_host
-                    ))
-                    .body(
-                        serde_json::to_vec(&serde_json::json!({ "content_markdown": md }))?.into(),
-                    )?,
-                    &req_parts.headers,
-                    &cookies,
-                )?)
-                .await?,
-        )
-        .await;
+        let preview_res = Backend::for_request(&ctx, &req_parts.headers, &cookies)
+            .render_markdown(md)
+            .await;
         return match preview_res {
             Ok(preview_res) => {
-                let preview_res = hyper::body::to_bytes(preview_res.into_body()).await?;
-                let preview_res: JustContentHTML = serde_json::from_slice(&preview_res)?;
-
                 page_comment_inner(
                     comment_id,
                     &req_parts.headers,
@@ -688,34 +582,18 @@ async fn handler_comment_submit_reply(
         body_values.contains_key("sensitive").into(),
     );
 
-    let api_res = res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::post(format!(
-                    "{}/api/unstable/comments/{}/replies",
-                    ctx.backend_host, comment_id
-                ))
-                .body(serde_json::to_vec(&body_values)?.into())?,
-                &req_parts.headers,
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await;
+    let api_res = Backend::for_request(&ctx, &req_parts.headers, &cookies)
+        .create_comment_reply(comment_id, &body_values)
+        .await;
 
     match api_res {
-        Ok(api_res) => {
-            let api_res = hyper::body::to_bytes(api_res.into_body()).await?;
-            let api_res: JustID = serde_json::from_slice(&api_res)?;
-
-            Ok(hyper::Response::builder()
-                .status(hyper::StatusCode::SEE_OTHER)
-                .header(
-                    hyper::header::LOCATION,
-                    format!("/comments/{}#comment{}", comment_id, api_res.id),
-                )
-                .body("Successfully posted.".into())?)
-        }
+        Ok(api_res) => Ok(hyper::Response::builder()
+            .status(hyper::StatusCode::SEE_OTHER)
+            .header(
+                hyper::header::LOCATION,
+                format!("/comments/{}#comment{}", comment_id, api_res.id),
+            )
+            .body("Successfully posted.".into())?),
         Err(crate::Error::RemoteError((status, message))) if status.is_client_error() => {
             page_comment_inner(
                 comment_id,
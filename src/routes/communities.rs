@@ -1,17 +1,16 @@
+use crate::backend::Backend;
 use crate::components::{
     maybe_fill_value, CommunityLink, ContentView, HTPage, HTPageAdvanced, MaybeFillCheckbox,
     MaybeFillInput, MaybeFillOption, MaybeFillTextArea, PostItem, TimeAgo,
 };
 use crate::lang;
-use crate::query_types::PostListQuery;
-use crate::resp_types::{
-    JustContentHTML, JustStringID, RespCommunityInfoMaybeYour, RespCommunityModlogEvent,
-    RespCommunityModlogEventDetails, RespList, RespMinimalAuthorInfo, RespMinimalCommunityInfo,
-    RespPostListPost, RespYourFollow,
-};
+use crate::preferences::Preferences;
+use crate::query_types::{CommunityListQuery, PostListQuery, UserListQuery};
+use crate::rate_limit::RateLimitClass;
+use crate::resp_types::{RespCommunityModlogEventDetails, RespYourFollow};
 use crate::routes::{
-    fetch_base_data, for_client, get_cookie_map_for_headers, get_cookie_map_for_req, html_response,
-    res_to_error, CookieMap, RespUserInfo,
+    check_rate_limit, fetch_base_data, fetch_with_base_data, get_cookie_map_for_headers,
+    get_cookie_map_for_req, html_response, CookieMap,
 };
 use serde_derive::{Deserialize, Serialize};
 use std::borrow::Cow;
@@ -25,10 +24,9 @@ async fn page_communities(
     ctx: Arc<crate::RouteContext>,
     req: hyper::Request<hyper::Body>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
-    let lang = crate::get_lang_for_req(&req);
+    let lang = crate::get_lang_for_req(&ctx, &req);
     let cookies = get_cookie_map_for_req(&req)?;
-    let base_data =
-        fetch_base_data(&ctx.backend_host, &ctx.http_client, req.headers(), &cookies).await?;
+    let base_data = fetch_base_data(&ctx, req.headers(), &cookies).await?;
 
     #[derive(Deserialize, Serialize)]
     struct Query<'a> {
@@ -40,28 +38,14 @@ async fn page_communities(
 
     let query: Query = serde_urlencoded::from_str(req.uri().query().unwrap_or(""))?;
 
-    let api_res = hyper::body::to_bytes(
-        res_to_error(
-            ctx.http_client
-                .request(for_client(
-                    hyper::Request::get(format!(
-                        "{}/api/unstable/communities?{}",
-                        ctx.backend_host,
-                        // for now this works but will need to change if we add other page parameters
-                        serde_urlencoded::to_string(&query)?,
-                    ))
-                    .body(Default::default())?,
-                    req.headers(),
-                    &cookies,
-                )?)
-                .await?,
-        )
-        .await?
-        .into_body(),
-    )
-    .await?;
-
-    let communities: RespList<RespMinimalCommunityInfo> = serde_json::from_slice(&api_res)?;
+    let communities = Backend::for_request(&ctx, req.headers(), &cookies)
+        .list_communities(&CommunityListQuery {
+            local: query.local,
+            your_follow_accepted: query.your_follow_accepted,
+            page: query.page.as_deref(),
+            ..Default::default()
+        })
+        .await?;
 
     let title = lang.tr(&lang::COMMUNITIES);
 
@@ -160,21 +144,16 @@ async fn page_communities(
     }))
 }
 
-async fn page_community(
+pub async fn page_community(
     params: (i64,),
     ctx: Arc<crate::RouteContext>,
     req: hyper::Request<hyper::Body>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
     let (community_id,) = params;
 
-    fn default_sort() -> crate::SortType {
-        crate::SortType::Hot
-    }
-
     #[derive(Deserialize)]
     struct Query<'a> {
-        #[serde(default = "default_sort")]
-        sort: crate::SortType,
+        sort: Option<crate::SortType>,
 
         created_within: Option<Cow<'a, str>>,
 
@@ -183,64 +162,37 @@ async fn page_community(
 
     let query: Query = serde_urlencoded::from_str(req.uri().query().unwrap_or(""))?;
 
-    let lang = crate::get_lang_for_req(&req);
+    let lang = crate::get_lang_for_req(&ctx, &req);
     let cookies = get_cookie_map_for_req(&req)?;
 
-    // TODO parallelize requests
-
-    let base_data =
-        fetch_base_data(&ctx.backend_host, &ctx.http_client, req.headers(), &cookies).await?;
-
-    let community_info_api_res = res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::get(format!(
-                    "{}/api/unstable/communities/{}{}",
-                    ctx.backend_host,
-                    community_id,
-                    if base_data.login.is_some() {
-                        "?include_your=true"
-                    } else {
-                        ""
-                    },
-                ))
-                .body(Default::default())?,
-                req.headers(),
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await?;
-    let community_info_api_res = hyper::body::to_bytes(community_info_api_res.into_body()).await?;
-
-    let community_info: RespCommunityInfoMaybeYour =
-        { serde_json::from_slice(&community_info_api_res)? };
-
-    let posts_api_res = res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::get(format!(
-                    "{}/api/unstable/posts?{}",
-                    ctx.backend_host,
-                    serde_urlencoded::to_string(&PostListQuery {
-                        community: Some(community_id),
-                        created_within: query.created_within.as_deref(),
-                        sort_sticky: Some(query.sort == crate::SortType::Hot),
-                        sort: Some(query.sort.as_str()),
-                        page: query.page.as_deref(),
-                        ..Default::default()
-                    })?,
-                ))
-                .body(Default::default())?,
-                req.headers(),
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await?;
-    let posts_api_res = hyper::body::to_bytes(posts_api_res.into_body()).await?;
+    let preferences = Preferences::from_cookies(&ctx.cookie_key, &cookies);
+    let sort = query.sort.unwrap_or(preferences.post_sort);
+
+    let backend = Backend::for_request(&ctx, req.headers(), &cookies);
+
+    let (base_data, (community_info, posts)) =
+        fetch_with_base_data(&ctx, req.headers(), &cookies, |include_your| {
+            let backend = &backend;
+            let query = &query;
+
+            async move {
+                let posts_query = PostListQuery {
+                    community: Some(community_id),
+                    created_within: query.created_within.as_deref(),
+                    sort_sticky: Some(sort == crate::SortType::Hot),
+                    sort: Some(sort.as_str()),
+                    limit: preferences.posts_per_page,
+                    page: query.page.as_deref(),
+                    ..Default::default()
+                };
 
-    let posts: RespList<RespPostListPost<'_>> = serde_json::from_slice(&posts_api_res)?;
+                futures_util::try_join!(
+                    backend.get_community(community_id, include_your),
+                    backend.list_posts(&posts_query),
+                )
+            }
+        })
+        .await?;
 
     let new_post_url = format!("/communities/{}/new_post", community_id);
 
@@ -383,7 +335,7 @@ async fn page_community(
                     crate::SortType::VALUES.iter()
                         .map(|value| {
                             let name = lang.tr(&value.lang_key()).into_owned();
-                            if query.sort == *value {
+                            if sort == *value {
                                 render::rsx! { <span>{name}</span> }
                             } else {
                                 render::rsx! { <a href={format!("/communities/{}?sort={}", community_id, value.as_str())}>{name}</a> }
@@ -392,7 +344,7 @@ async fn page_community(
                         .collect::<Vec<_>>()
                 }
                 {
-                    (query.sort == crate::SortType::Top)
+                    (sort == crate::SortType::Top)
                         .then(|| {
                             render::rsx! {
                                 <div class={"timeframeOptions"}>
@@ -435,13 +387,13 @@ async fn page_community(
             }
             <ul>
                 {posts.items.iter().map(|post| {
-                    PostItem { post, in_community: true, no_user: false, lang: &lang }
+                    PostItem { post, in_community: true, no_user: false, base_data: &base_data, lang: &lang }
                 }).collect::<Vec<_>>()}
             </ul>
             {
                 if let Some(next_page) = &posts.next_page {
                     Some(render::rsx! {
-                        <a href={format!("/communities/{}?sort={}&page={}", community_id, query.sort.as_str(), next_page)}>
+                        <a href={format!("/communities/{}?sort={}&page={}", community_id, sort.as_str(), next_page)}>
                             {lang.tr(&lang::POSTS_PAGE_NEXT)}
                         </a>
                     })
@@ -473,27 +425,12 @@ async fn page_community_edit_inner(
     display_error: Option<String>,
     prev_values: Option<&HashMap<&str, serde_json::Value>>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
-    let base_data = fetch_base_data(&ctx.backend_host, &ctx.http_client, headers, cookies).await?;
-    let lang = crate::get_lang_for_headers(headers);
-
-    let community_info_api_res = res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::get(format!(
-                    "{}/api/unstable/communities/{}",
-                    ctx.backend_host, community_id,
-                ))
-                .body(Default::default())?,
-                headers,
-                cookies,
-            )?)
-            .await?,
-    )
-    .await?;
-    let community_info_api_res = hyper::body::to_bytes(community_info_api_res.into_body()).await?;
-
-    let community_info: RespCommunityInfoMaybeYour =
-        { serde_json::from_slice(&community_info_api_res)? };
+    let base_data = fetch_base_data(&ctx, headers, cookies).await?;
+    let lang = crate::get_lang_for_headers(&ctx, headers);
+
+    let community_info = Backend::for_request(&ctx, headers, cookies)
+        .get_community(community_id, false)
+        .await?;
 
     let title = lang.tr(&lang::COMMUNITY_EDIT);
 
@@ -535,20 +472,9 @@ async fn handler_communities_edit_submit(
     let body = hyper::body::to_bytes(body).await?;
     let body: HashMap<&str, serde_json::Value> = serde_urlencoded::from_bytes(&body)?;
 
-    let api_res = res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::patch(format!(
-                    "{}/api/unstable/communities/{}",
-                    ctx.backend_host, community_id
-                ))
-                .body(serde_json::to_vec(&body)?.into())?,
-                &req_parts.headers,
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await;
+    let api_res = Backend::for_request(&ctx, &req_parts.headers, &cookies)
+        .update_community(community_id, &body)
+        .await;
 
     match api_res {
         Err(crate::Error::RemoteError((_, message))) => {
@@ -580,29 +506,14 @@ async fn page_community_delete(
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
     let (community_id,) = params;
 
-    let lang = crate::get_lang_for_req(&req);
+    let lang = crate::get_lang_for_req(&ctx, &req);
     let cookies = get_cookie_map_for_req(&req)?;
 
-    let base_data =
-        fetch_base_data(&ctx.backend_host, &ctx.http_client, req.headers(), &cookies).await?;
-
-    let api_res = res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::get(format!(
-                    "{}/api/unstable/communities/{}",
-                    ctx.backend_host, community_id
-                ))
-                .body(Default::default())?,
-                req.headers(),
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await?;
-    let api_res = hyper::body::to_bytes(api_res.into_body()).await?;
+    let base_data = fetch_base_data(&ctx, req.headers(), &cookies).await?;
 
-    let community: RespCommunityInfoMaybeYour = serde_json::from_slice(&api_res)?;
+    let community = Backend::for_request(&ctx, req.headers(), &cookies)
+        .get_community(community_id, false)
+        .await?;
 
     Ok(html_response(render::html! {
         <HTPage base_data={&base_data} lang={&lang} title={&lang.tr(&lang::community_delete_title())}>
@@ -626,20 +537,9 @@ async fn handler_community_delete_confirm(
 
     let cookies = get_cookie_map_for_req(&req)?;
 
-    res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::delete(format!(
-                    "{}/api/unstable/communities/{}",
-                    ctx.backend_host, community_id,
-                ))
-                .body("".into())?,
-                req.headers(),
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await?;
+    Backend::for_request(&ctx, req.headers(), &cookies)
+        .delete_community(community_id)
+        .await?;
 
     Ok(hyper::Response::builder()
         .status(hyper::StatusCode::SEE_OTHER)
@@ -656,21 +556,9 @@ async fn handler_community_follow(
 
     let cookies = get_cookie_map_for_req(&req)?;
 
-    res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::post(format!(
-                    "{}/api/unstable/communities/{}/follow",
-                    ctx.backend_host, community_id
-                ))
-                .header(hyper::header::CONTENT_TYPE, "application/json")
-                .body("{\"try_wait_for_accept\":true}".into())?,
-                req.headers(),
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await?;
+    Backend::for_request(&ctx, req.headers(), &cookies)
+        .follow_community(community_id)
+        .await?;
 
     Ok(hyper::Response::builder()
         .status(hyper::StatusCode::SEE_OTHER)
@@ -702,50 +590,17 @@ async fn page_community_moderators_inner(
     display_error_main: Option<String>,
     display_error_add: Option<String>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
-    let lang = crate::get_lang_for_headers(headers);
+    let lang = crate::get_lang_for_headers(&ctx, headers);
 
-    let base_data = fetch_base_data(&ctx.backend_host, &ctx.http_client, headers, cookies).await?;
+    let base_data = fetch_base_data(&ctx, headers, cookies).await?;
 
-    let community_info_api_res = res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::get(format!(
-                    "{}/api/unstable/communities/{}{}",
-                    ctx.backend_host,
-                    community_id,
-                    if base_data.login.is_some() {
-                        "?include_your=true"
-                    } else {
-                        ""
-                    },
-                ))
-                .body(Default::default())?,
-                headers,
-                cookies,
-            )?)
-            .await?,
-    )
-    .await?;
-    let community_info_api_res = hyper::body::to_bytes(community_info_api_res.into_body()).await?;
-    let community_info: RespCommunityInfoMaybeYour =
-        { serde_json::from_slice(&community_info_api_res)? };
-
-    let api_res = res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::get(format!(
-                    "{}/api/unstable/communities/{}/moderators",
-                    ctx.backend_host, community_id,
-                ))
-                .body(Default::default())?,
-                headers,
-                cookies,
-            )?)
-            .await?,
-    )
-    .await?;
-    let api_res = hyper::body::to_bytes(api_res.into_body()).await?;
-    let api_res: Vec<RespMinimalAuthorInfo> = serde_json::from_slice(&api_res)?;
+    let backend = Backend::for_request(&ctx, headers, cookies);
+
+    let community_info = backend
+        .get_community(community_id, base_data.login.is_some())
+        .await?;
+
+    let api_res = backend.list_community_moderators(community_id).await?;
 
     let title = lang.tr(&lang::MODERATORS);
 
@@ -825,7 +680,7 @@ async fn handler_community_moderators_add(
 
     let (req_parts, body) = req.into_parts();
 
-    let lang = crate::get_lang_for_headers(&req_parts.headers);
+    let lang = crate::get_lang_for_headers(&ctx, &req_parts.headers);
     let cookies = get_cookie_map_for_headers(&req_parts.headers)?;
 
     #[derive(Deserialize)]
@@ -836,59 +691,27 @@ async fn handler_community_moderators_add(
     let body = hyper::body::to_bytes(body).await?;
     let body: ModeratorsAddParams = serde_urlencoded::from_bytes(&body)?;
 
-    #[derive(Serialize)]
-    struct UsersListQuery<'a> {
-        local: bool,
-        username: &'a str,
-    }
+    let backend = Backend::for_request(&ctx, &req_parts.headers, &cookies);
 
-    let user_lookup_api_res = res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::get(format!(
-                    "{}/api/unstable/users?{}",
-                    ctx.backend_host,
-                    serde_urlencoded::to_string(&UsersListQuery {
-                        local: true,
-                        username: &body.username,
-                    })?,
-                ))
-                .body(Default::default())?,
-                &req_parts.headers,
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await;
+    let user_lookup_api_res = backend
+        .list_users(&UserListQuery {
+            local: Some(true),
+            username: Some(body.username.as_ref()),
+        })
+        .await;
 
     let add_result = match user_lookup_api_res {
         Err(err) => Err(err),
-        Ok(api_res) => {
-            let value = hyper::body::to_bytes(api_res.into_body()).await?;
-            let user_list: RespList<RespUserInfo> = serde_json::from_slice(&value)?;
-
-            match user_list.items.first() {
-                None => Err(crate::Error::InternalUserError(
-                    lang.tr(&lang::no_such_local_user()).into_owned(),
-                )),
-                Some(target_user) => {
-                    res_to_error(
-                        ctx.http_client
-                            .request(for_client(
-                                hyper::Request::put(format!(
-                                    "{}/api/unstable/communities/{}/moderators/{}",
-                                    ctx.backend_host, community_id, target_user.base.id,
-                                ))
-                                .body(Default::default())?,
-                                &req_parts.headers,
-                                &cookies,
-                            )?)
-                            .await?,
-                    )
+        Ok(user_list) => match user_list.items.first() {
+            None => Err(crate::Error::InternalUserError(
+                lang.tr(&lang::no_such_local_user()).into_owned(),
+            )),
+            Some(target_user) => {
+                backend
+                    .add_community_moderator(community_id, target_user.base.id)
                     .await
-                }
             }
-        }
+        },
     };
 
     match add_result {
@@ -934,20 +757,9 @@ async fn handler_community_moderators_remove(
     let body = hyper::body::to_bytes(body).await?;
     let body: ModeratorsRemoveParams = serde_urlencoded::from_bytes(&body)?;
 
-    let api_res = res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::delete(format!(
-                    "{}/api/unstable/communities/{}/moderators/{}",
-                    ctx.backend_host, community_id, body.user,
-                ))
-                .body(Default::default())?,
-                &req_parts.headers,
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await;
+    let api_res = Backend::for_request(&ctx, &req_parts.headers, &cookies)
+        .remove_community_moderator(community_id, body.user)
+        .await;
 
     match api_res {
         Err(crate::Error::RemoteError((_, message))) => {
@@ -979,28 +791,14 @@ async fn page_community_modlog(
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
     let (community_id,) = params;
 
-    let lang = crate::get_lang_for_req(&req);
+    let lang = crate::get_lang_for_req(&ctx, &req);
     let cookies = get_cookie_map_for_req(&req)?;
 
-    let base_data =
-        fetch_base_data(&ctx.backend_host, &ctx.http_client, req.headers(), &cookies).await?;
-
-    let api_res = res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::get(format!(
-                    "{}/api/unstable/communities/{}/modlog/events",
-                    ctx.backend_host, community_id,
-                ))
-                .body(Default::default())?,
-                req.headers(),
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await?;
-    let api_res = hyper::body::to_bytes(api_res.into_body()).await?;
-    let api_res: RespList<RespCommunityModlogEvent> = serde_json::from_slice(&api_res)?;
+    let base_data = fetch_base_data(&ctx, req.headers(), &cookies).await?;
+
+    let api_res = Backend::for_request(&ctx, req.headers(), &cookies)
+        .list_community_modlog_events(community_id)
+        .await?;
 
     let title = lang.tr(&lang::MODLOG);
 
@@ -1055,20 +853,9 @@ async fn handler_community_post_approve(
 
     let cookies = get_cookie_map_for_req(&req)?;
 
-    res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::patch(format!(
-                    "{}/api/unstable/communities/{}/posts/{}",
-                    ctx.backend_host, community_id, post_id
-                ))
-                .body("{\"approved\": true}".into())?,
-                req.headers(),
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await?;
+    Backend::for_request(&ctx, req.headers(), &cookies)
+        .set_community_post_approved(community_id, post_id, true)
+        .await?;
 
     Ok(hyper::Response::builder()
         .status(hyper::StatusCode::SEE_OTHER)
@@ -1085,20 +872,9 @@ async fn handler_community_post_unapprove(
 
     let cookies = get_cookie_map_for_req(&req)?;
 
-    res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::patch(format!(
-                    "{}/api/unstable/communities/{}/posts/{}",
-                    ctx.backend_host, community_id, post_id
-                ))
-                .body("{\"approved\": false}".into())?,
-                req.headers(),
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await?;
+    Backend::for_request(&ctx, req.headers(), &cookies)
+        .set_community_post_approved(community_id, post_id, false)
+        .await?;
 
     Ok(hyper::Response::builder()
         .status(hyper::StatusCode::SEE_OTHER)
@@ -1115,20 +891,9 @@ async fn handler_community_post_make_sticky(
 
     let cookies = get_cookie_map_for_req(&req)?;
 
-    res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::patch(format!(
-                    "{}/api/unstable/communities/{}/posts/{}",
-                    ctx.backend_host, community_id, post_id
-                ))
-                .body("{\"sticky\": true}".into())?,
-                req.headers(),
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await?;
+    Backend::for_request(&ctx, req.headers(), &cookies)
+        .set_community_post_sticky(community_id, post_id, true)
+        .await?;
 
     Ok(hyper::Response::builder()
         .status(hyper::StatusCode::SEE_OTHER)
@@ -1145,20 +910,9 @@ async fn handler_community_post_make_unsticky(
 
     let cookies = get_cookie_map_for_req(&req)?;
 
-    res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::patch(format!(
-                    "{}/api/unstable/communities/{}/posts/{}",
-                    ctx.backend_host, community_id, post_id
-                ))
-                .body("{\"sticky\": false}".into())?,
-                req.headers(),
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await?;
+    Backend::for_request(&ctx, req.headers(), &cookies)
+        .set_community_post_sticky(community_id, post_id, false)
+        .await?;
 
     Ok(hyper::Response::builder()
         .status(hyper::StatusCode::SEE_OTHER)
@@ -1175,20 +929,9 @@ async fn handler_community_unfollow(
 
     let cookies = get_cookie_map_for_req(&req)?;
 
-    res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::post(format!(
-                    "{}/api/unstable/communities/{}/unfollow",
-                    ctx.backend_host, community_id
-                ))
-                .body(Default::default())?,
-                req.headers(),
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await?;
+    Backend::for_request(&ctx, req.headers(), &cookies)
+        .unfollow_community(community_id)
+        .await?;
 
     Ok(hyper::Response::builder()
         .status(hyper::StatusCode::SEE_OTHER)
@@ -1221,8 +964,8 @@ async fn page_community_new_post_inner(
     prev_values: Option<&HashMap<Cow<'_, str>, serde_json::Value>>,
     display_preview: Option<&str>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
-    let base_data = fetch_base_data(&ctx.backend_host, &ctx.http_client, headers, cookies).await?;
-    let lang = crate::get_lang_for_headers(headers);
+    let base_data = fetch_base_data(&ctx, headers, cookies).await?;
+    let lang = crate::get_lang_for_headers(&ctx, headers);
 
     let submit_url = format!("/communities/{}/new_post/submit", community_id);
 
@@ -1339,7 +1082,9 @@ async fn handler_communities_new_post_submit(
     let (community_id,) = params;
 
     let (req_parts, body) = req.into_parts();
-    let lang = crate::get_lang_for_headers(&req_parts.headers);
+    check_rate_limit(&ctx, RateLimitClass::Submit, &req_parts)?;
+
+    let lang = crate::get_lang_for_headers(&ctx, &req_parts.headers);
     let cookies = get_cookie_map_for_headers(&req_parts.headers)?;
 
     let content_type = req_parts
@@ -1398,21 +1143,10 @@ async fn handler_communities_new_post_submit(
                         }
                         Some(mime) => {
                             log::debug!("will upload media");
-                            let res = res_to_error(
-                                ctx.http_client
-                                    .request(for_client(
-                                        hyper::Request::post(format!(
-                                            "{}/api/unstable/media",
-                                            ctx.backend_host,
-                                        ))
-                                        .header(hyper::header::CONTENT_TYPE, mime.as_ref())
-                                        .body(hyper::Body::wrap_stream(stream))?,
-                                        &req_parts.headers,
-                                        &cookies,
-                                    )?)
-                                    .await?,
-                            )
-                            .await;
+                            let mime = mime.to_string();
+                            let res = Backend::for_request(&ctx, &req_parts.headers, &cookies)
+                                .upload_media(&mime, hyper::Body::wrap_stream(stream))
+                                .await;
 
                             match res {
                                 Err(crate::Error::RemoteError((_, message))) => {
@@ -1421,13 +1155,10 @@ async fn handler_communities_new_post_submit(
                                 Err(other) => {
                                     return Err(other);
                                 }
-                                Ok(res) => {
-                                    let res = hyper::body::to_bytes(res.into_body()).await?;
-                                    let res: JustStringID = serde_json::from_slice(&res)?;
-
+                                Ok(media_id) => {
                                     body_values_src.insert(
                                         "href".into(),
-                                        format!("local-media://{}", res.id).into(),
+                                        format!("local-media://{}", media_id).into(),
                                     );
                                 }
                             }
@@ -1476,27 +1207,11 @@ async fn handler_communities_new_post_submit(
             .get("content_markdown")
             .and_then(|x| x.as_str())
             .unwrap_or("");
-        let preview_res = res_to_error(
-            ctx.http_client
-                .request(for_client(
-                    hyper::Request::post(format!(
-                        "{}/api/unstable/misc/render_markdown",
-                        ctx.backend_host
-                    ))
-                    .body(
-                        serde_json::to_vec(&serde_json::json!({ "content_markdown": md }))?.into(),
-                    )?,
-                    &req_parts.headers,
-                    &cookies,
-                )?)
-                .await?,
-        )
-        .await;
+        let preview_res = Backend::for_request(&ctx, &req_parts.headers, &cookies)
+            .render_markdown(md)
+            .await;
         return match preview_res {
             Ok(preview_res) => {
-                let preview_res = hyper::body::to_bytes(preview_res.into_body()).await?;
-                let preview_res: JustContentHTML = serde_json::from_slice(&preview_res)?;
-
                 page_community_new_post_inner(
                     community_id,
                     &req_parts.headers,
@@ -1574,33 +1289,15 @@ async fn handler_communities_new_post_submit(
         );
     }
 
-    let api_res = res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::post(format!("{}/api/unstable/posts", ctx.backend_host))
-                    .body(serde_json::to_vec(&body_values)?.into())?,
-                &req_parts.headers,
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await;
+    let api_res = Backend::for_request(&ctx, &req_parts.headers, &cookies)
+        .create_post(&body_values)
+        .await;
 
     match api_res {
-        Ok(api_res) => {
-            #[derive(Deserialize)]
-            struct PostsCreateResponse {
-                id: i64,
-            }
-
-            let api_res = hyper::body::to_bytes(api_res.into_body()).await?;
-            let api_res: PostsCreateResponse = serde_json::from_slice(&api_res)?;
-
-            Ok(hyper::Response::builder()
-                .status(hyper::StatusCode::SEE_OTHER)
-                .header(hyper::header::LOCATION, format!("/posts/{}", api_res.id))
-                .body("Successfully posted.".into())?)
-        }
+        Ok(api_res) => Ok(hyper::Response::builder()
+            .status(hyper::StatusCode::SEE_OTHER)
+            .header(hyper::header::LOCATION, format!("/posts/{}", api_res.id))
+            .body("Successfully posted.".into())?),
         Err(crate::Error::RemoteError((_, message))) => {
             page_community_new_post_inner(
                 community_id,
@@ -1,30 +1,52 @@
+use crate::backend::Backend;
+use crate::captcha::CaptchaKind;
+use crate::components::CaptchaField;
 use crate::lang;
+use crate::rate_limit::RateLimitClass;
 use crate::routes::{
-    fetch_base_data, for_client, get_cookie_map_for_headers, get_cookie_map_for_req, html_response,
-    res_to_error, CookieMap, HTPage,
+    check_rate_limit, fetch_base_data, get_cookie_map_for_headers, get_cookie_map_for_req,
+    html_response, CookieMap, HTPage,
 };
 use serde_derive::Deserialize;
 use std::borrow::Cow;
+use std::collections::HashMap;
 use std::sync::Arc;
 
+#[derive(Deserialize)]
+struct ForgotPasswordQuery {
+    captcha: Option<CaptchaKind>,
+}
+
 async fn page_forgot_password(
     _: (),
     ctx: Arc<crate::RouteContext>,
     req: hyper::Request<hyper::Body>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let query: ForgotPasswordQuery = serde_urlencoded::from_str(req.uri().query().unwrap_or(""))?;
     let cookies = get_cookie_map_for_req(&req)?;
 
-    page_forgot_password_inner(ctx, req.headers(), &cookies, None).await
+    page_forgot_password_inner(
+        ctx,
+        req.headers(),
+        &cookies,
+        query.captcha.unwrap_or_default(),
+        None,
+    )
+    .await
 }
 
 async fn page_forgot_password_inner(
     ctx: Arc<crate::RouteContext>,
     headers: &hyper::header::HeaderMap,
     cookies: &CookieMap<'_>,
+    captcha_kind: CaptchaKind,
     display_error: Option<String>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
-    let lang = crate::get_lang_for_headers(headers);
-    let base_data = fetch_base_data(&ctx.backend_host, &ctx.http_client, headers, cookies).await?;
+    let lang = crate::get_lang_for_headers(&ctx, headers);
+    let base_data = fetch_base_data(&ctx, headers, cookies).await?;
+
+    let captcha = ctx.captcha.generate(captcha_kind);
+    let captcha_switch_href = format!("/forgot_password?captcha={}", captcha_kind.other().as_str());
 
     let title = lang.tr(&lang::FORGOT_PASSWORD);
 
@@ -47,6 +69,7 @@ async fn page_forgot_password_inner(
                         <input type={"email"} name={"email_address"} required={"required"} />
                     </label>
                 </div>
+                <CaptchaField challenge={captcha.as_ref()} switch_href={captcha_switch_href} lang={&lang} />
                 <button type={"submit"}>{lang.tr(&lang::submit())}</button>
             </form>
         </HTPage>
@@ -69,8 +92,8 @@ async fn page_forgot_password_code_inner(
     cookies: &CookieMap<'_>,
     display_error: Option<String>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
-    let lang = crate::get_lang_for_headers(headers);
-    let base_data = fetch_base_data(&ctx.backend_host, &ctx.http_client, headers, cookies).await?;
+    let lang = crate::get_lang_for_headers(&ctx, headers);
+    let base_data = fetch_base_data(&ctx, headers, cookies).await?;
 
     let title = lang.tr(&lang::FORGOT_PASSWORD);
 
@@ -106,8 +129,8 @@ async fn page_forgot_password_code_reset_inner(
     cookies: &CookieMap<'_>,
     display_error: Option<String>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
-    let lang = crate::get_lang_for_headers(headers);
-    let base_data = fetch_base_data(&ctx.backend_host, &ctx.http_client, headers, cookies).await?;
+    let lang = crate::get_lang_for_headers(&ctx, headers);
+    let base_data = fetch_base_data(&ctx, headers, cookies).await?;
 
     let title = lang.tr(&lang::FORGOT_PASSWORD);
 
@@ -148,6 +171,7 @@ async fn handler_forgot_password_code_submit(
     }
 
     let (req_parts, body) = req.into_parts();
+    check_rate_limit(&ctx, RateLimitClass::ForgotPassword, &req_parts)?;
 
     let cookies = get_cookie_map_for_headers(&req_parts.headers)?;
 
@@ -155,36 +179,15 @@ async fn handler_forgot_password_code_submit(
     let body: CodeSubmitBody = serde_urlencoded::from_bytes(&body)?;
 
     if let Some(new_password) = body.new_password {
-        let api_res = res_to_error(
-            ctx.http_client
-                .request(for_client(
-                    hyper::Request::post(format!(
-                        "{}/api/unstable/forgot_password/keys/{}/reset",
-                        ctx.backend_host,
-                        urlencoding::encode(&body.key),
-                    ))
-                    .body(
-                        serde_json::to_vec(&serde_json::json!({ "new_password": new_password }))?
-                            .into(),
-                    )?,
-                    &req_parts.headers,
-                    &cookies,
-                )?)
-                .await?,
-        )
-        .await;
+        let api_res = Backend::for_request(&ctx, &req_parts.headers, &cookies)
+            .reset_forgot_password(&body.key, &new_password)
+            .await;
 
         match api_res {
             Ok(_) => {
-                let base_data = fetch_base_data(
-                    &ctx.backend_host,
-                    &ctx.http_client,
-                    &req_parts.headers,
-                    &cookies,
-                )
-                .await?;
+                let base_data = fetch_base_data(&ctx, &req_parts.headers, &cookies).await?;
 
-                let lang = crate::get_lang_for_headers(&req_parts.headers);
+                let lang = crate::get_lang_for_headers(&ctx, &req_parts.headers);
 
                 let title = lang.tr(&lang::forgot_password()).into_owned();
 
@@ -211,21 +214,9 @@ async fn handler_forgot_password_code_submit(
             Err(other) => Err(other),
         }
     } else {
-        let api_res = res_to_error(
-            ctx.http_client
-                .request(for_client(
-                    hyper::Request::get(format!(
-                        "{}/api/unstable/forgot_password/keys/{}",
-                        ctx.backend_host,
-                        urlencoding::encode(&body.key),
-                    ))
-                    .body(Default::default())?,
-                    &req_parts.headers,
-                    &cookies,
-                )?)
-                .await?,
-        )
-        .await;
+        let api_res = Backend::for_request(&ctx, &req_parts.headers, &cookies)
+            .check_forgot_password_key(&body.key)
+            .await;
 
         match api_res {
             Ok(_) => {
@@ -253,26 +244,31 @@ async fn handler_forgot_password_submit(
     req: hyper::Request<hyper::Body>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
     let (req_parts, body) = req.into_parts();
+    check_rate_limit(&ctx, RateLimitClass::ForgotPassword, &req_parts)?;
 
     let cookies = get_cookie_map_for_headers(&req_parts.headers)?;
 
     let body = hyper::body::to_bytes(body).await?;
-    let body: serde_json::Value = serde_urlencoded::from_bytes(&body)?;
-
-    let api_res = res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::post(format!(
-                    "{}/api/unstable/forgot_password/keys",
-                    ctx.backend_host,
-                ))
-                .body(serde_json::to_vec(&body)?.into())?,
-                &req_parts.headers,
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await;
+    let mut body: HashMap<Cow<'_, str>, serde_json::Value> = serde_urlencoded::from_bytes(&body)?;
+
+    let captcha_kind = ctx.captcha.submitted_kind(&body);
+    if let Err(err) = ctx.captcha.verify(&mut body) {
+        let lang = crate::get_lang_for_headers(&ctx, &req_parts.headers);
+        let message = lang.tr(&err.lang_key()).into_owned();
+
+        return page_forgot_password_inner(
+            ctx,
+            &req_parts.headers,
+            &cookies,
+            captcha_kind,
+            Some(message),
+        )
+        .await;
+    }
+
+    let api_res = Backend::for_request(&ctx, &req_parts.headers, &cookies)
+        .create_forgot_password_key(&body)
+        .await;
 
     match api_res {
         Ok(_) => Ok(hyper::Response::builder()
@@ -280,7 +276,14 @@ async fn handler_forgot_password_submit(
             .header(hyper::header::LOCATION, "/forgot_password/code")
             .body("Request submitted.".into())?),
         Err(crate::Error::RemoteError((_, message))) => {
-            page_forgot_password_inner(ctx, &req_parts.headers, &cookies, Some(message)).await
+            page_forgot_password_inner(
+                ctx,
+                &req_parts.headers,
+                &cookies,
+                captcha_kind,
+                Some(message),
+            )
+            .await
         }
         Err(other) => Err(other),
     }
@@ -0,0 +1,480 @@
+use super::{fetch_base_data, get_cookie_map_for_req, html_response, CookieMap};
+use crate::backend::Backend;
+use crate::components::{HTPage, TimeAgo, UserLink};
+use crate::lang;
+use crate::query_types::InvitationListQuery;
+use crate::resp_types::{InvitationsCreateResponse, RespInvitationInfo};
+use render::Render;
+use serde_derive::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::sync::Arc;
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum InvitationFilter {
+    All,
+    Unused,
+    Used,
+}
+
+impl Default for InvitationFilter {
+    fn default() -> Self {
+        Self::All
+    }
+}
+
+impl InvitationFilter {
+    const VALUES: [Self; 3] = [Self::All, Self::Unused, Self::Used];
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::All => "all",
+            Self::Unused => "unused",
+            Self::Used => "used",
+        }
+    }
+
+    fn lang_key(&self) -> lang::LangKey<'static> {
+        match self {
+            Self::All => lang::invitation_filter_all(),
+            Self::Unused => lang::invitation_filter_unused(),
+            Self::Used => lang::invitation_filter_used(),
+        }
+    }
+
+    fn matches(&self, invitation: &RespInvitationInfo<'_>) -> bool {
+        match self {
+            Self::All => true,
+            Self::Unused => !invitation.used,
+            Self::Used => invitation.used,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct InvitationsQuery<'a> {
+    #[serde(default)]
+    filter: InvitationFilter,
+    page: Option<Cow<'a, str>>,
+}
+
+/// Whether a list shows the current user's invitations, or everyone's for admins
+#[derive(Clone, Copy, PartialEq)]
+enum InvitationScope {
+    Mine,
+    All,
+}
+
+impl InvitationScope {
+    fn path(&self) -> &'static str {
+        match self {
+            Self::Mine => "/my_invitations",
+            Self::All => "/administration/invitations",
+        }
+    }
+}
+
+fn signup_url(ctx: &crate::RouteContext, key: &str) -> String {
+    let mut url = ctx.frontend_url.clone();
+    url.path_segments_mut().unwrap().push("signup");
+    url.query_pairs_mut().append_pair("invitation_key", key);
+
+    url.into()
+}
+
+/// Renders `content` as an inline SVG QR code
+fn qr_code_svg(content: &str) -> Option<String> {
+    let code = qrcode::QrCode::new(content.as_bytes()).ok()?;
+    let svg = code
+        .render::<qrcode::render::svg::Color>()
+        .min_dimensions(160, 160)
+        .build();
+
+    // the XML declaration isn't needed inside HTML
+    let start = svg.find("<svg")?;
+
+    Some(svg[start..].to_owned())
+}
+
+/// Shareable signup link, with a QR code for scanning it from another device
+struct InvitationLink<'a> {
+    url: String,
+    lang: &'a crate::Translator,
+}
+
+impl<'a> render::Render for InvitationLink<'a> {
+    fn render_into<W: std::fmt::Write + ?Sized>(self, writer: &mut W) -> std::fmt::Result {
+        let lang = self.lang;
+        let qr_code = qr_code_svg(&self.url);
+
+        (render::rsx! {
+            <div class={"invitationLink"}>
+                {
+                    qr_code.as_deref().map(|svg| {
+                        render::rsx! {
+                            <div class={"invitationQRCode"} role={"img"} aria-label={lang.tr(&lang::INVITATION_QR_CODE_LABEL)}>
+                                {render::raw!(svg)}
+                            </div>
+                        }
+                    })
+                }
+                <input type={"text"} readonly={""} value={self.url.as_str()} />
+            </div>
+        })
+        .render_into(writer)
+    }
+}
+
+async fn page_invitations(
+    _: (),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+    scope: InvitationScope,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let cookies = get_cookie_map_for_req(&req)?;
+    let query: InvitationsQuery = serde_urlencoded::from_str(req.uri().query().unwrap_or(""))?;
+
+    page_invitations_inner(ctx, req.headers(), &cookies, scope, query, None).await
+}
+
+async fn page_invitations_inner(
+    ctx: Arc<crate::RouteContext>,
+    headers: &hyper::header::HeaderMap,
+    cookies: &CookieMap<'_>,
+    scope: InvitationScope,
+    query: InvitationsQuery<'_>,
+    res: Option<Result<InvitationsCreateResponse<'_>, &str>>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let lang = crate::get_lang_for_headers(&ctx, headers);
+
+    let base_data = fetch_base_data(&ctx, headers, cookies).await?;
+
+    let title = match scope {
+        InvitationScope::Mine => lang.tr(&lang::MY_INVITATIONS),
+        InvitationScope::All => lang.tr(&lang::INVITATIONS_ADMIN_TITLE),
+    };
+
+    let allowed = match (&base_data.login, scope) {
+        (None, _) => Err(lang.tr(&lang::MUST_LOGIN)),
+        (Some(_), InvitationScope::All) => {
+            if base_data.is_site_admin() {
+                Ok(())
+            } else {
+                Err(lang.tr(&lang::NOT_SITE_ADMIN))
+            }
+        }
+        (Some(login), InvitationScope::Mine) => {
+            if login.permissions.create_invitation.allowed {
+                Ok(())
+            } else {
+                Err(lang.tr(&lang::MISSING_PERMISSION_CREATE_INVITATION))
+            }
+        }
+    };
+
+    if let Err(err) = allowed {
+        return Ok(html_response(render::html! {
+            <HTPage base_data={&base_data} lang={&lang} title={&title}>
+                <h1>{title.as_ref()}</h1>
+                <div class={"errorBox"}>{err}</div>
+            </HTPage>
+        }));
+    }
+
+    let invitations = Backend::for_request(&ctx, headers, cookies)
+        .list_invitations(&InvitationListQuery {
+            key: None,
+            created_by: match scope {
+                InvitationScope::Mine => Some("~me"),
+                InvitationScope::All => None,
+            },
+            page: query.page.as_deref(),
+        })
+        .await?;
+
+    let filter = query.filter;
+    let items: Vec<_> = invitations
+        .items
+        .iter()
+        .filter(|invitation| filter.matches(invitation))
+        .collect();
+
+    let next_page_href = invitations
+        .next_page
+        .as_ref()
+        .map(|next_page| {
+            serde_urlencoded::to_string(&InvitationsQuery {
+                filter,
+                page: Some(Cow::Borrowed(next_page)),
+            })
+        })
+        .transpose()?
+        .map(|query| format!("{}?{}", scope.path(), query));
+
+    Ok(html_response(render::html! {
+        <HTPage base_data={&base_data} lang={&lang} title={&title}>
+            <h1>{title.as_ref()}</h1>
+            {
+                (scope == InvitationScope::Mine).then(|| {
+                    render::rsx! {
+                        <form method={"POST"} action={"/my_invitations/create"}>
+                            <button type={"submit"}>{lang.tr(&lang::CREATE_INVITATION)}</button>
+                        </form>
+                    }
+                })
+            }
+            {
+                if let Some(Ok(res)) = &res {
+                    let url = signup_url(&ctx, &res.key);
+
+                    Some(render::rsx! {
+                        <div class={"infoBox"}>
+                            <p>{lang.tr(&lang::CREATE_INVITATION_RESULT)}</p>
+                            <InvitationLink url={url} lang={&lang} />
+                        </div>
+                    })
+                } else {
+                    None
+                }
+            }
+            {
+                if let Some(Err(message)) = res {
+                    Some(render::rsx! {
+                        <div class={"errorBox"}>
+                            {message}
+                        </div>
+                    })
+                } else {
+                    None
+                }
+            }
+            <div class={"sortOptions"}>
+                {
+                    InvitationFilter::VALUES.iter().map(|value| {
+                        let name = lang.tr(&value.lang_key()).into_owned();
+
+                        if *value == filter {
+                            render::rsx! { <span>{name}</span> }
+                        } else {
+                            render::rsx! { <a href={format!("{}?filter={}", scope.path(), value.as_str())}>{name}</a> }
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                }
+            </div>
+            {
+                items.is_empty().then(|| {
+                    render::rsx! {
+                        <p>{lang.tr(&lang::INVITATIONS_NONE)}</p>
+                    }
+                })
+            }
+            <ul class={"invitationList"}>
+                {
+                    items.iter().map(|invitation| {
+                        let url = signup_url(&ctx, &invitation.key);
+
+                        render::rsx! {
+                            <li>
+                                {
+                                    if invitation.used {
+                                        None
+                                    } else {
+                                        Some(render::rsx! {
+                                            <InvitationLink url={url} lang={&lang} />
+                                        })
+                                    }
+                                }
+                                <div class={"actionList small"}>
+                                    <TimeAgo since={chrono::DateTime::parse_from_rfc3339(&invitation.created_at).unwrap()} lang={&lang} />
+                                    {
+                                        (scope == InvitationScope::All).then(|| {
+                                            render::rsx! {
+                                                <span>
+                                                    {lang.tr(&lang::INVITATION_CREATED_BY)}{" "}
+                                                    <UserLink lang={&lang} user={Some(&invitation.created_by)} />
+                                                </span>
+                                            }
+                                        })
+                                    }
+                                    <span>
+                                        {
+                                            if invitation.used {
+                                                lang.tr(&lang::INVITATION_FILTER_USED)
+                                            } else {
+                                                lang.tr(&lang::INVITATION_FILTER_UNUSED)
+                                            }
+                                        }
+                                    </span>
+                                    {
+                                        if invitation.used {
+                                            None
+                                        } else {
+                                            Some(render::rsx! {
+                                                <form method={"POST"} action={format!("{}/{}/revoke", scope.path(), invitation.id)} class={"inline"}>
+                                                    <button type={"submit"}>{lang.tr(&lang::INVITATION_REVOKE)}</button>
+                                                </form>
+                                            })
+                                        }
+                                    }
+                                </div>
+                            </li>
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                }
+            </ul>
+            {
+                next_page_href.map(|href| {
+                    render::rsx! {
+                        <a href={href}>{lang.tr(&lang::INVITATIONS_PAGE_NEXT)}</a>
+                    }
+                })
+            }
+        </HTPage>
+    }))
+}
+
+async fn page_my_invitations(
+    params: (),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    page_invitations(params, ctx, req, InvitationScope::Mine).await
+}
+
+async fn page_administration_invitations(
+    params: (),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    page_invitations(params, ctx, req, InvitationScope::All).await
+}
+
+async fn handler_my_invitations_create(
+    _: (),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let cookies = get_cookie_map_for_req(&req)?;
+
+    let api_res = Backend::for_request(&ctx, req.headers(), &cookies)
+        .create_invitation()
+        .await;
+
+    let query = InvitationsQuery {
+        filter: InvitationFilter::Unused,
+        page: None,
+    };
+
+    match api_res {
+        Ok(api_res) => {
+            page_invitations_inner(
+                ctx,
+                req.headers(),
+                &cookies,
+                InvitationScope::Mine,
+                query,
+                Some(Ok(api_res)),
+            )
+            .await
+        }
+        Err(crate::Error::RemoteError((status, message))) if status.is_client_error() => {
+            page_invitations_inner(
+                ctx,
+                req.headers(),
+                &cookies,
+                InvitationScope::Mine,
+                query,
+                Some(Err(&message)),
+            )
+            .await
+        }
+        Err(other) => Err(other),
+    }
+}
+
+async fn handler_invitation_revoke(
+    params: (i32,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+    scope: InvitationScope,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (invitation_id,) = params;
+
+    let cookies = get_cookie_map_for_req(&req)?;
+
+    let api_res = Backend::for_request(&ctx, req.headers(), &cookies)
+        .delete_invitation(invitation_id)
+        .await;
+
+    match api_res {
+        Ok(()) => Ok(hyper::Response::builder()
+            .status(hyper::StatusCode::SEE_OTHER)
+            .header(
+                hyper::header::LOCATION,
+                format!("{}?filter=unused", scope.path()),
+            )
+            .body("Successfully revoked.".into())?),
+        Err(crate::Error::RemoteError((status, message))) if status.is_client_error() => {
+            page_invitations_inner(
+                ctx,
+                req.headers(),
+                &cookies,
+                scope,
+                InvitationsQuery {
+                    filter: InvitationFilter::Unused,
+                    page: None,
+                },
+                Some(Err(&message)),
+            )
+            .await
+        }
+        Err(other) => Err(other),
+    }
+}
+
+async fn handler_my_invitations_revoke(
+    params: (i32,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    handler_invitation_revoke(params, ctx, req, InvitationScope::Mine).await
+}
+
+async fn handler_administration_invitations_revoke(
+    params: (i32,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    handler_invitation_revoke(params, ctx, req, InvitationScope::All).await
+}
+
+pub fn route_my_invitations() -> crate::RouteNode<()> {
+    crate::RouteNode::new()
+        .with_handler_async(hyper::Method::GET, page_my_invitations)
+        .with_child(
+            "create",
+            crate::RouteNode::new()
+                .with_handler_async(hyper::Method::POST, handler_my_invitations_create),
+        )
+        .with_child_parse::<i32, _>(
+            crate::RouteNode::new().with_child(
+                "revoke",
+                crate::RouteNode::new()
+                    .with_handler_async(hyper::Method::POST, handler_my_invitations_revoke),
+            ),
+        )
+}
+
+pub fn route_administration_invitations() -> crate::RouteNode<()> {
+    crate::RouteNode::new()
+        .with_handler_async(hyper::Method::GET, page_administration_invitations)
+        .with_child_parse::<i32, _>(crate::RouteNode::new().with_child(
+            "revoke",
+            crate::RouteNode::new().with_handler_async(
+                hyper::Method::POST,
+                handler_administration_invitations_revoke,
+            ),
+        ))
+}
@@ -0,0 +1,364 @@
+use super::{fetch_base_data, get_cookie_map_for_headers, get_cookie_map_for_req, html_response};
+use crate::backend::Backend;
+use crate::components::{CommunityLink, HTPage, UserLink};
+use crate::lang;
+use crate::query_types::{CommunityListQuery, UserListQuery};
+use crate::resp_types::{
+    RespCommentInfo, RespCommunityInfoMaybeYour, RespLookupKind, RespLookupResult, RespPostInfo,
+    RespUserInfo,
+};
+use render::Render;
+use serde_derive::Deserialize;
+use std::borrow::Cow;
+use std::sync::Arc;
+
+impl RespLookupKind {
+    fn lang_key(&self) -> lang::LangKey<'static> {
+        match self {
+            RespLookupKind::Community => lang::lookup_kind_community(),
+            RespLookupKind::User => lang::lookup_kind_user(),
+            RespLookupKind::Post => lang::lookup_kind_post(),
+            RespLookupKind::Comment => lang::lookup_kind_comment(),
+            RespLookupKind::Unknown => lang::lookup_kind_unknown(),
+        }
+    }
+}
+
+/// Local page for a lookup result, if it is something that can be shown
+fn result_href(result: &RespLookupResult) -> Option<String> {
+    match result.kind {
+        RespLookupKind::Community => Some(format!("/communities/{}", result.id)),
+        RespLookupKind::User => Some(format!("/users/{}", result.id)),
+        RespLookupKind::Post => Some(format!("/posts/{}", result.id)),
+        RespLookupKind::Comment => Some(format!("/comments/{}", result.id)),
+        RespLookupKind::Unknown => None,
+    }
+}
+
+/// Looks up a handle or URL, trying URLs as any kind of object before falling back to actors
+async fn lookup(backend: &Backend<'_>, query: &str) -> Result<Vec<RespLookupResult>, crate::Error> {
+    if query.starts_with("https://") || query.starts_with("http://") {
+        match backend.lookup_objects(query).await {
+            // older backends only support looking up actors
+            Err(crate::Error::RemoteError((hyper::StatusCode::NOT_FOUND, _))) => {}
+            other => return other,
+        }
+    }
+
+    backend.lookup_actors(query).await
+}
+
+/// Details used to tell apart several matching results
+enum LookupResultDetails {
+    Community(RespCommunityInfoMaybeYour<'static>),
+    User(RespUserInfo<'static>),
+    Post(RespPostInfo<'static>),
+    Comment(RespCommentInfo<'static>),
+    Missing,
+}
+
+async fn fetch_result_details(
+    backend: &Backend<'_>,
+    result: &RespLookupResult,
+) -> Result<LookupResultDetails, crate::Error> {
+    let res = match result.kind {
+        RespLookupKind::Community => backend
+            .get_community(result.id, false)
+            .await
+            .map(LookupResultDetails::Community),
+        RespLookupKind::User => backend
+            .get_user(result.id, false)
+            .await
+            .map(LookupResultDetails::User),
+        RespLookupKind::Post => backend
+            .get_post(result.id, false)
+            .await
+            .map(LookupResultDetails::Post),
+        RespLookupKind::Comment => backend
+            .get_comment(result.id, false)
+            .await
+            .map(LookupResultDetails::Comment),
+        RespLookupKind::Unknown => Ok(LookupResultDetails::Missing),
+    };
+
+    match res {
+        Err(crate::Error::RemoteError((status, _))) if status.is_client_error() => {
+            Ok(LookupResultDetails::Missing)
+        }
+        other => other,
+    }
+}
+
+/// Link to a result, labelled with whatever identifies it best
+struct LookupResultLink<'a> {
+    result: &'a RespLookupResult,
+    details: &'a LookupResultDetails,
+    lang: &'a crate::Translator,
+}
+
+impl<'a> render::Render for LookupResultLink<'a> {
+    fn render_into<W: std::fmt::Write + ?Sized>(self, writer: &mut W) -> std::fmt::Result {
+        let href = result_href(self.result).unwrap_or_default();
+
+        match self.details {
+            LookupResultDetails::Community(community) => CommunityLink {
+                community: &community.base,
+            }
+            .render_into(writer),
+            LookupResultDetails::User(user) => UserLink {
+                lang: self.lang,
+                user: Some(&user.base),
+            }
+            .render_into(writer),
+            LookupResultDetails::Post(post) => {
+                (render::rsx! { <a href={&href}>{post.base.base.title.as_ref()}</a> })
+                    .render_into(writer)
+            }
+            LookupResultDetails::Comment(RespCommentInfo {
+                post: Some(post), ..
+            }) => (render::rsx! { <a href={&href}>{post.title.as_ref()}</a> }).render_into(writer),
+            LookupResultDetails::Comment(_) | LookupResultDetails::Missing => {
+                (render::rsx! { <a href={&href}>{href.as_str()}</a> }).render_into(writer)
+            }
+        }
+    }
+}
+
+pub async fn page_lookup(
+    _: (),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let lang = crate::get_lang_for_req(&ctx, &req);
+    let cookies = get_cookie_map_for_req(&req)?;
+    let base_data = fetch_base_data(&ctx, req.headers(), &cookies).await?;
+
+    #[derive(Deserialize)]
+    struct LookupQuery<'a> {
+        query: Option<Cow<'a, str>>,
+    }
+
+    let query: LookupQuery<'_> = serde_urlencoded::from_str(req.uri().query().unwrap_or(""))?;
+    let query = query.query;
+
+    let backend = Backend::for_request(&ctx, req.headers(), &cookies);
+
+    let api_res: Option<Result<Vec<RespLookupResult>, String>> = if let Some(query) = &query {
+        Some(match lookup(&backend, query).await {
+            Ok(items) => Ok(items),
+            Err(crate::Error::RemoteError((status, message))) if status.is_client_error() => {
+                Err(message)
+            }
+            Err(other) => return Err(other),
+        })
+    } else {
+        None
+    };
+
+    let (found, unsupported) = match &api_res {
+        Some(Ok(items)) => items
+            .iter()
+            .partition::<Vec<_>, _>(|item| result_href(item).is_some()),
+        _ => (Vec::new(), Vec::new()),
+    };
+
+    if let [item] = found.as_slice() {
+        return Ok(hyper::Response::builder()
+            .status(hyper::StatusCode::FOUND)
+            .header(hyper::header::LOCATION, result_href(item).unwrap())
+            .body("Redirecting…".into())?);
+    }
+
+    let details = futures_util::future::try_join_all(
+        found
+            .iter()
+            .map(|item| fetch_result_details(&backend, item)),
+    )
+    .await?;
+
+    let title = lang.tr(&lang::LOOKUP_TITLE);
+    Ok(html_response(render::html! {
+        <HTPage base_data={&base_data} lang={&lang} title={&title}>
+            <h1>{title.as_ref()}</h1>
+            <form method={"GET"} action={"/lookup"}>
+                <input r#type={"text"} name={"query"} value={query.as_deref().unwrap_or("")} />
+            </form>
+            {
+                match &api_res {
+                    Some(Err(display_error)) => Some(render::rsx! {
+                        <div class={"errorBox"}>{display_error.as_str()}</div>
+                    }),
+                    _ => None,
+                }
+            }
+            {
+                (!found.is_empty()).then(|| {
+                    render::rsx! {
+                        <div>
+                            <p>{lang.tr(&lang::LOOKUP_MULTIPLE)}</p>
+                            <ul>
+                                {
+                                    found.iter().zip(details.iter()).map(|(result, details)| {
+                                        render::rsx! {
+                                            <li>
+                                                {lang.tr(&result.kind.lang_key()).into_owned()}{": "}
+                                                <LookupResultLink result={*result} details={details} lang={&lang} />
+                                            </li>
+                                        }
+                                    })
+                                    .collect::<Vec<_>>()
+                                }
+                            </ul>
+                        </div>
+                    }
+                })
+            }
+            {
+                (matches!(api_res, Some(Ok(_))) && found.is_empty()).then(|| {
+                    render::rsx! {
+                        <p>
+                            {
+                                lang.tr(if unsupported.is_empty() {
+                                    &lang::LOOKUP_NOTHING
+                                } else {
+                                    &lang::LOOKUP_UNSUPPORTED
+                                })
+                            }
+                        </p>
+                    }
+                })
+            }
+        </HTPage>
+    }))
+}
+
+/// Finds a local community or user by its exact name
+///
+/// Bare names are looked up in the backend's own listings rather than guessing which host it
+/// federates as, since that need not match the frontend's.
+async fn resolve_local_name(
+    backend: &Backend<'_>,
+    name: &str,
+    kind: RespLookupKind,
+) -> Result<Option<i64>, crate::Error> {
+    Ok(match kind {
+        RespLookupKind::Community => backend
+            .list_communities(&CommunityListQuery {
+                local: Some(true),
+                search: Some(name),
+                ..Default::default()
+            })
+            .await?
+            .items
+            .iter()
+            .find(|community| community.name == name)
+            .map(|community| community.id),
+        RespLookupKind::User => backend
+            .list_users(&UserListQuery {
+                local: Some(true),
+                username: Some(name),
+            })
+            .await?
+            .items
+            .iter()
+            .find(|user| user.base.username == name)
+            .map(|user| user.base.id),
+        _ => None,
+    })
+}
+
+/// Resolves a handle from a readable URL to the ID of the community or user it names
+///
+/// Handles without a host are taken to be local.
+async fn resolve_handle(
+    ctx: &crate::RouteContext,
+    headers: &hyper::header::HeaderMap,
+    handle: &str,
+    kind: RespLookupKind,
+) -> Result<Option<i64>, crate::Error> {
+    let handle = percent_encoding::percent_decode_str(handle).decode_utf8()?;
+
+    if let Some(id) = ctx.handle_cache.get(kind, &handle) {
+        return Ok(Some(id));
+    }
+
+    let cookies = get_cookie_map_for_headers(headers)?;
+    let backend = Backend::for_request(ctx, headers, &cookies);
+
+    let res = if handle.contains('@') {
+        backend.lookup_actors(&handle).await.map(|results| {
+            results
+                .iter()
+                .find(|result| result.kind == kind)
+                .map(|result| result.id)
+        })
+    } else {
+        resolve_local_name(&backend, &handle, kind).await
+    };
+
+    let id = match res {
+        Ok(id) => id,
+        Err(crate::Error::RemoteError((status, _))) if status.is_client_error() => {
+            return Ok(None);
+        }
+        Err(other) => return Err(other),
+    };
+
+    if let Some(id) = id {
+        ctx.handle_cache.insert(kind, handle.into_owned(), id);
+    }
+
+    Ok(id)
+}
+
+async fn page_community_handle(
+    params: (String,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (handle,) = params;
+
+    match resolve_handle(&ctx, req.headers(), &handle, RespLookupKind::Community).await? {
+        Some(community_id) => super::communities::page_community((community_id,), ctx, req).await,
+        None => {
+            let lang = crate::get_lang_for_req(&ctx, &req);
+
+            Err(crate::Error::UserError(crate::simple_response(
+                hyper::StatusCode::NOT_FOUND,
+                lang.tr(&lang::handle_community_not_found()).into_owned(),
+            )))
+        }
+    }
+}
+
+async fn page_user_handle(
+    params: (String,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (handle,) = params;
+
+    match resolve_handle(&ctx, req.headers(), &handle, RespLookupKind::User).await? {
+        Some(user_id) => super::page_user((user_id,), ctx, req).await,
+        None => {
+            let lang = crate::get_lang_for_req(&ctx, &req);
+
+            Err(crate::Error::UserError(crate::simple_response(
+                hyper::StatusCode::NOT_FOUND,
+                lang.tr(&lang::handle_user_not_found()).into_owned(),
+            )))
+        }
+    }
+}
+
+pub fn route_community_handle() -> crate::RouteNode<()> {
+    crate::RouteNode::new().with_child_str(
+        crate::RouteNode::new().with_handler_async(hyper::Method::GET, page_community_handle),
+    )
+}
+
+pub fn route_user_handle() -> crate::RouteNode<()> {
+    crate::RouteNode::new().with_child_str(
+        crate::RouteNode::new().with_handler_async(hyper::Method::GET, page_user_handle),
+    )
+}
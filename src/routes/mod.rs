@@ -2,30 +2,37 @@ use render::Render;
 use serde_derive::{Deserialize, Serialize};
 use std::borrow::Cow;
 use std::collections::HashMap;
-use std::convert::TryInto;
 use std::sync::Arc;
 
+use crate::accounts::{self, Accounts, SavedAccount};
+use crate::backend::Backend;
+use crate::captcha::CaptchaKind;
 use crate::components::{
-    BoolCheckbox, ContentView, FlagItem, HTPage, MaybeFillInput, NotificationItem, PostItem,
-    SiteModlogEventItem, ThingItem,
+    BoolCheckbox, CaptchaField, ContentView, FlagItem, HTPage, MaybeFillInput, NotificationItem,
+    PostItem, RequestIdNote, SiteModlogEventItem, ThingItem,
 };
 use crate::lang;
-use crate::query_types::{FlagListQuery, PostListQuery};
-use crate::resp_types::{
-    InvitationsCreateResponse, JustStringID, RespFlagInfo, RespInstanceInfo, RespInvitationInfo,
-    RespList, RespNotification, RespPostListPost, RespSiteModlogEvent, RespThingInfo, RespUserInfo,
-};
+use crate::preferences::Preferences;
+use crate::query_types::{FlagListQuery, InvitationListQuery, PostListQuery};
+use crate::rate_limit::{ClientIp, RateLimitClass};
 use crate::PageBaseData;
 
+mod account;
 mod administration;
 mod comments;
 mod communities;
 mod forgot_password;
+mod invitations;
+mod lookup;
 mod moderation;
 mod posts;
+mod preferences;
 mod r#static;
 
-const COOKIE_AGE: u32 = 60 * 60 * 24 * 365;
+pub const COOKIE_AGE: u32 = 60 * 60 * 24 * 365;
+
+pub const LANG_COOKIE: &str = "hitideLang";
+pub const PREFERENCES_COOKIE: &str = "hitidePreferences";
 
 #[derive(Deserialize)]
 struct ReturnToParams<'a> {
@@ -35,9 +42,10 @@ struct ReturnToParams<'a> {
 #[derive(Deserialize, Serialize)]
 struct SignupQuery<'a> {
     invitation_key: Option<Cow<'a, str>>,
+    captcha: Option<CaptchaKind>,
 }
 
-type CookieMap<'a> = std::collections::HashMap<&'a str, ginger::Cookie<'a>>;
+pub type CookieMap<'a> = std::collections::HashMap<&'a str, ginger::Cookie<'a>>;
 
 fn get_cookie_map(src: Option<&str>) -> Result<CookieMap, ginger::ParseError> {
     use fallible_iterator::FallibleIterator;
@@ -54,7 +62,7 @@ fn get_cookie_map_for_req(req: &hyper::Request<hyper::Body>) -> Result<CookieMap
     get_cookie_map_for_headers(req.headers())
 }
 
-fn get_cookie_map_for_headers(headers: &hyper::HeaderMap) -> Result<CookieMap, crate::Error> {
+pub fn get_cookie_map_for_headers(headers: &hyper::HeaderMap) -> Result<CookieMap, crate::Error> {
     get_cookie_map(get_cookies_string(headers)?).map_err(Into::into)
 }
 
@@ -65,13 +73,12 @@ fn get_cookies_string(headers: &hyper::HeaderMap) -> Result<Option<&str>, crate:
         .transpose()?)
 }
 
-fn for_client(
+pub fn for_client(
     mut new_req: hyper::Request<hyper::Body>,
     src_headers: &hyper::header::HeaderMap,
     cookies: &CookieMap<'_>,
 ) -> Result<hyper::Request<hyper::Body>, hyper::header::InvalidHeaderValue> {
-    let token = cookies.get("hitideToken").map(|c| c.value);
-    if let Some(token) = token {
+    if let Some(token) = accounts::active_token(cookies) {
         new_req.headers_mut().insert(
             hyper::header::AUTHORIZATION,
             hyper::header::HeaderValue::from_str(&format!("Bearer {}", token))?,
@@ -82,49 +89,225 @@ fn for_client(
             .headers_mut()
             .insert(hyper::header::ACCEPT_LANGUAGE, value.clone());
     }
+    if let Some(value) = src_headers.get(crate::access_log::REQUEST_ID_HEADER) {
+        new_req
+            .headers_mut()
+            .insert(crate::access_log::REQUEST_ID_HEADER, value.clone());
+    }
 
     Ok(new_req)
 }
 
 async fn fetch_base_data(
-    backend_host: &str,
-    http_client: &crate::HttpClient,
+    ctx: &crate::RouteContext,
     headers: &hyper::header::HeaderMap,
     cookies: &CookieMap<'_>,
 ) -> Result<PageBaseData, crate::Error> {
-    let login = {
-        let api_res = http_client
-            .request(for_client(
-                hyper::Request::get(format!("{}/api/unstable/logins/~current", backend_host))
-                    .body(Default::default())?,
-                headers,
-                cookies,
-            )?)
-            .await?;
-
-        if api_res.status() == hyper::StatusCode::UNAUTHORIZED {
-            Ok(None)
-        } else {
-            let api_res = res_to_error(api_res).await?;
-            let api_res = hyper::body::to_bytes(api_res.into_body()).await?;
-            serde_json::from_slice(&api_res)
-        }
-    }?;
+    let login = Backend::for_request(ctx, headers, cookies)
+        .get_login_current()
+        .await?;
+
+    if let Some(login) = &login {
+        crate::access_log::set_user_id(login.user.id);
+    }
+
+    Ok(PageBaseData {
+        login,
+        accounts: Accounts::from_cookies(&ctx.cookie_key, cookies).summaries(),
+        preferences: Preferences::from_cookies(&ctx.cookie_key, cookies),
+        branding: ctx.branding.clone(),
+        assets: ctx.assets.clone(),
+    })
+}
 
-    Ok(PageBaseData { login })
+/// Runs `fetch` concurrently with [`fetch_base_data`].
+///
+/// `fetch` is given whether the request appears to be logged in, for deciding whether to pass
+/// `include_your`. If the login turns out to be invalid, it is run again without.
+async fn fetch_with_base_data<T, F, Fut>(
+    ctx: &crate::RouteContext,
+    headers: &hyper::header::HeaderMap,
+    cookies: &CookieMap<'_>,
+    fetch: F,
+) -> Result<(PageBaseData, T), crate::Error>
+where
+    F: Fn(bool) -> Fut,
+    Fut: std::future::Future<Output = Result<T, crate::Error>>,
+{
+    let include_your = cookies.contains_key(accounts::TOKEN_COOKIE);
+
+    let (base_data, res) =
+        futures_util::future::join(fetch_base_data(ctx, headers, cookies), fetch(include_your))
+            .await;
+    let base_data = base_data?;
+
+    let res = if include_your && base_data.login.is_none() {
+        fetch(false).await?
+    } else {
+        res?
+    };
+
+    Ok((base_data, res))
 }
 
 fn html_response(html: String) -> hyper::Response<hyper::Body> {
     let mut res = hyper::Response::new(html.into());
     res.headers_mut().insert(
         hyper::header::CONTENT_TYPE,
-        hyper::header::HeaderValue::from_static("text/html"),
+        hyper::header::HeaderValue::from_static("text/html; charset=utf-8"),
     );
     res
 }
 
-pub fn default_comments_sort() -> crate::SortType {
-    crate::SortType::Hot
+/// Redirect that also updates the cookies holding signed-in accounts
+fn accounts_redirect(
+    location: &str,
+    set_cookies: Vec<String>,
+    body: &'static str,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let mut res = hyper::Response::builder()
+        .status(hyper::StatusCode::SEE_OTHER)
+        .header(hyper::header::LOCATION, location);
+    for value in set_cookies {
+        res = res.header(hyper::header::SET_COOKIE, value);
+    }
+
+    Ok(res.body(body.into())?)
+}
+
+/// Saves a newly created login alongside any other signed-in accounts, returning the
+/// `Set-Cookie` values that make it the active one
+async fn add_account_set_cookies(
+    ctx: &crate::RouteContext,
+    headers: &hyper::header::HeaderMap,
+    cookies: &CookieMap<'_>,
+    token: &str,
+) -> Result<Vec<String>, crate::Error> {
+    let mut accounts = Accounts::from_cookies(&ctx.cookie_key, cookies);
+
+    // logins from before multiple accounts were supported aren't listed yet
+    if let Some(previous) = accounts::active_token(cookies) {
+        if previous != token && !accounts.contains_token(previous) {
+            let backend = Backend::for_request(ctx, headers, cookies);
+            if let Some(login) = backend.get_login_current().await? {
+                let user = backend.get_user(login.user.id, false).await?;
+                accounts.add(SavedAccount {
+                    token: previous.to_owned(),
+                    user_id: login.user.id,
+                    username: user.base.username.into_owned(),
+                });
+            }
+        }
+    }
+
+    let mut new_headers = headers.clone();
+    new_headers.insert(
+        hyper::header::COOKIE,
+        hyper::header::HeaderValue::from_str(&format!("{}={}", accounts::TOKEN_COOKIE, token))?,
+    );
+    let new_cookies = get_cookie_map_for_headers(&new_headers)?;
+    let backend = Backend::for_request(ctx, &new_headers, &new_cookies);
+
+    let login = backend
+        .get_login_current()
+        .await?
+        .ok_or(crate::Error::InternalStrStatic("New login was rejected"))?;
+    let user = backend.get_user(login.user.id, false).await?;
+
+    accounts.add(SavedAccount {
+        token: token.to_owned(),
+        user_id: login.user.id,
+        username: user.base.username.into_owned(),
+    });
+
+    Ok(accounts.to_set_cookies(&ctx.cookie_key, Some(token), ctx.secure_cookies())?)
+}
+
+/// Page shown when the backend can't be reached, in place of a generic internal error
+pub fn backend_error_response(
+    headers: &hyper::header::HeaderMap,
+    ctx: &crate::RouteContext,
+    err: &crate::Error,
+) -> hyper::Response<hyper::Body> {
+    let lang = crate::get_lang_for_headers(&ctx, headers);
+    let base_data = PageBaseData {
+        login: None,
+        accounts: Vec::new(),
+        preferences: Default::default(),
+        branding: ctx.branding.clone(),
+        assets: ctx.assets.clone(),
+    };
+
+    let (status, message) = match err {
+        crate::Error::BackendTimeout => (
+            hyper::StatusCode::GATEWAY_TIMEOUT,
+            lang.tr(&lang::BACKEND_TIMEOUT),
+        ),
+        _ => (
+            hyper::StatusCode::SERVICE_UNAVAILABLE,
+            lang.tr(&lang::BACKEND_UNAVAILABLE),
+        ),
+    };
+
+    let title = lang.tr(&lang::BACKEND_UNAVAILABLE_TITLE);
+
+    let mut res = html_response(render::html! {
+        <HTPage base_data={&base_data} lang={&lang} title={&title}>
+            <h1>{title.as_ref()}</h1>
+            <div class={"errorBox"}>{message}</div>
+            <RequestIdNote headers={headers} lang={&lang} />
+        </HTPage>
+    });
+
+    *res.status_mut() = status;
+
+    res
+}
+
+/// Takes a token from the client's rate limit bucket, failing with a 429 page if it's empty
+fn check_rate_limit(
+    ctx: &crate::RouteContext,
+    class: RateLimitClass,
+    req_parts: &http::request::Parts,
+) -> Result<(), crate::Error> {
+    let ip = match req_parts.extensions.get::<ClientIp>() {
+        Some(ClientIp(ip)) => *ip,
+        None => return Ok(()),
+    };
+
+    let retry_after = match ctx.rate_limiter.check(class, ip) {
+        Ok(()) => return Ok(()),
+        Err(retry_after) => retry_after,
+    };
+    let seconds = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+
+    let lang = crate::get_lang_for_headers(&ctx, &req_parts.headers);
+    let base_data = PageBaseData {
+        login: None,
+        accounts: Vec::new(),
+        preferences: Preferences::from_cookies(
+            &ctx.cookie_key,
+            &get_cookie_map_for_headers(&req_parts.headers)?,
+        ),
+        branding: ctx.branding.clone(),
+        assets: ctx.assets.clone(),
+    };
+
+    let title = lang.tr(&lang::RATE_LIMITED_TITLE);
+
+    let mut res = html_response(render::html! {
+        <HTPage base_data={&base_data} lang={&lang} title={&title}>
+            <h1>{title.as_ref()}</h1>
+            <div class={"errorBox"}>{lang.tr(&lang::rate_limited(seconds))}</div>
+            <RequestIdNote headers={&req_parts.headers} lang={&lang} />
+        </HTPage>
+    });
+
+    *res.status_mut() = hyper::StatusCode::TOO_MANY_REQUESTS;
+    res.headers_mut()
+        .insert(hyper::header::RETRY_AFTER, seconds.into());
+
+    Err(crate::Error::UserError(res))
 }
 
 async fn page_about(
@@ -132,24 +315,14 @@ async fn page_about(
     ctx: Arc<crate::RouteContext>,
     req: hyper::Request<hyper::Body>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
-    let lang = crate::get_lang_for_req(&req);
+    let lang = crate::get_lang_for_req(&ctx, &req);
     let cookies = get_cookie_map_for_req(&req)?;
 
-    let base_data =
-        fetch_base_data(&ctx.backend_host, &ctx.http_client, req.headers(), &cookies).await?;
+    let base_data = fetch_base_data(&ctx, req.headers(), &cookies).await?;
 
-    let api_res = res_to_error(
-        ctx.http_client
-            .get(
-                format!("{}/api/unstable/instance", ctx.backend_host)
-                    .try_into()
-                    .unwrap(),
-            )
-            .await?,
-    )
-    .await?;
-    let api_res = hyper::body::to_bytes(api_res.into_body()).await?;
-    let api_res: RespInstanceInfo = serde_json::from_slice(&api_res)?;
+    let api_res = Backend::for_request(&ctx, req.headers(), &cookies)
+        .get_instance()
+        .await?;
 
     let title = lang.tr(&lang::ABOUT_TITLE);
 
@@ -206,6 +379,74 @@ async fn page_about(
     }))
 }
 
+async fn handler_language_submit(
+    _: (),
+    _ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    #[derive(Deserialize)]
+    struct LanguageForm<'a> {
+        lang: Cow<'a, str>,
+    }
+
+    let (req_parts, body) = req.into_parts();
+
+    let body = hyper::body::to_bytes(body).await?;
+    let body: LanguageForm = serde_urlencoded::from_bytes(&body)?;
+
+    let cookie = if body.lang.is_empty() {
+        format!(
+            "{}=\"\"; Path=/; Expires=Thu, 01 Jan 1970 00:00:00 GMT",
+            LANG_COOKIE
+        )
+    } else {
+        let lang: Option<unic_langid::LanguageIdentifier> = body.lang.parse().ok();
+        match lang {
+            Some(lang) if crate::translations::current().languages().contains(&lang) => {
+                format!("{}={}; Path=/; Max-Age={}", LANG_COOKIE, lang, COOKIE_AGE)
+            }
+            _ => {
+                return Err(crate::Error::UserError(crate::simple_response(
+                    hyper::StatusCode::BAD_REQUEST,
+                    "Unknown language",
+                )));
+            }
+        }
+    };
+
+    // go back to the page the form was on, without any `lang` parameter overriding the new choice
+    let return_to = req_parts
+        .headers
+        .get(hyper::header::REFERER)
+        .and_then(|x| x.to_str().ok())
+        .and_then(|x| url::Url::parse(x).ok())
+        .map(|mut referer| {
+            let query: Vec<(String, String)> = referer
+                .query_pairs()
+                .filter(|(key, _)| key != "lang")
+                .map(|(key, value)| (key.into_owned(), value.into_owned()))
+                .collect();
+
+            if query.is_empty() {
+                referer.set_query(None);
+            } else {
+                referer.query_pairs_mut().clear().extend_pairs(query);
+            }
+
+            match referer.query() {
+                Some(query) => format!("{}?{}", referer.path(), query),
+                None => referer.path().to_owned(),
+            }
+        })
+        .unwrap_or_else(|| "/".to_owned());
+
+    Ok(hyper::Response::builder()
+        .status(hyper::StatusCode::SEE_OTHER)
+        .header(hyper::header::SET_COOKIE, cookie)
+        .header(hyper::header::LOCATION, return_to)
+        .body("Successfully changed language.".into())?)
+}
+
 async fn page_login(
     _: (),
     ctx: Arc<crate::RouteContext>,
@@ -220,21 +461,22 @@ async fn page_login_inner(
     display_error: Option<String>,
     prev_values: Option<&serde_json::Value>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
-    let lang = crate::get_lang_for_headers(&req_parts.headers);
+    let lang = crate::get_lang_for_headers(&ctx, &req_parts.headers);
     let cookies = get_cookie_map_for_headers(&req_parts.headers)?;
 
-    let base_data = fetch_base_data(
-        &ctx.backend_host,
-        &ctx.http_client,
-        &req_parts.headers,
-        &cookies,
-    )
-    .await?;
+    let base_data = fetch_base_data(&ctx, &req_parts.headers, &cookies).await?;
 
     let title = lang.tr(&lang::LOGIN);
 
     Ok(html_response(render::html! {
         <HTPage base_data={&base_data} lang={&lang} title={&title}>
+            {
+                base_data.login.is_some().then(|| {
+                    render::rsx! {
+                        <div class={"infoBox"}>{lang.tr(&lang::LOGIN_ADD_ACCOUNT_NOTE)}</div>
+                    }
+                })
+            }
             {
                 display_error.map(|msg| {
                     render::rsx! {
@@ -282,61 +524,29 @@ async fn page_login_inner(
     }))
 }
 
-pub async fn res_to_error(
-    res: hyper::Response<hyper::Body>,
-) -> Result<hyper::Response<hyper::Body>, crate::Error> {
-    let status = res.status();
-    if status.is_success() {
-        Ok(res)
-    } else {
-        let bytes = hyper::body::to_bytes(res.into_body()).await?;
-        Err(crate::Error::RemoteError((
-            status,
-            String::from_utf8_lossy(&bytes).into_owned(),
-        )))
-    }
-}
-
 async fn handler_login_submit(
     _: (),
     ctx: Arc<crate::RouteContext>,
     req: hyper::Request<hyper::Body>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
-    #[derive(Deserialize)]
-    struct LoginsCreateResponse<'a> {
-        token: &'a str,
-    }
-
     let (req_parts, body) = req.into_parts();
+    check_rate_limit(&ctx, RateLimitClass::Login, &req_parts)?;
 
     let body = hyper::body::to_bytes(body).await?;
     let body: serde_json::Value = serde_urlencoded::from_bytes(&body)?;
 
-    let api_res = res_to_error(
-        ctx.http_client
-            .request(
-                hyper::Request::post(format!("{}/api/unstable/logins", ctx.backend_host))
-                    .body(serde_json::to_vec(&body)?.into())?,
-            )
-            .await?,
-    )
-    .await;
+    let cookies = get_cookie_map_for_headers(&req_parts.headers)?;
+
+    let api_res = Backend::for_request(&ctx, &req_parts.headers, &cookies)
+        .create_login(&body)
+        .await;
 
     match api_res {
         Ok(api_res) => {
-            let api_res = hyper::body::to_bytes(api_res.into_body()).await?;
-            let api_res: LoginsCreateResponse = serde_json::from_slice(&api_res)?;
+            let set_cookies =
+                add_account_set_cookies(&ctx, &req_parts.headers, &cookies, &api_res.token).await?;
 
-            let token = api_res.token;
-
-            Ok(hyper::Response::builder()
-                .status(hyper::StatusCode::SEE_OTHER)
-                .header(
-                    hyper::header::SET_COOKIE,
-                    format!("hitideToken={}; Path=/; Max-Age={}", token, COOKIE_AGE),
-                )
-                .header(hyper::header::LOCATION, "/")
-                .body("Successfully logged in.".into())?)
+            accounts_redirect("/", set_cookies, "Successfully logged in.")
         }
         Err(crate::Error::RemoteError((status, message))) if status.is_client_error() => {
             page_login_inner(ctx, req_parts, Some(message), Some(&body)).await
@@ -352,135 +562,55 @@ async fn handler_logout(
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
     let cookies = get_cookie_map_for_req(&req)?;
 
-    res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::delete(format!(
-                    "{}/api/unstable/logins/~current",
-                    ctx.backend_host,
-                ))
-                .body(Default::default())?,
-                req.headers(),
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await?;
+    let api_res = Backend::for_request(&ctx, req.headers(), &cookies)
+        .delete_login_current()
+        .await;
 
-    Ok(hyper::Response::builder()
-        .status(hyper::StatusCode::SEE_OTHER)
-        .header(hyper::header::LOCATION, "/")
-        .header(
-            hyper::header::SET_COOKIE,
-            "hitideToken=\"\"; Path=/; Expires=Thu, 01 Jan 1970 00:00:00 GMT".to_owned(),
-        )
-        .body("Successfully logged out.".into())?)
+    match api_res {
+        // an expired login is as good as logged out
+        Ok(_) | Err(crate::Error::RemoteError((hyper::StatusCode::UNAUTHORIZED, _))) => {}
+        Err(other) => return Err(other),
+    }
+
+    accounts_redirect(
+        "/",
+        Accounts::logged_out_set_cookies(&ctx.cookie_key, &cookies, ctx.secure_cookies())?,
+        "Successfully logged out.",
+    )
 }
 
-async fn page_lookup(
+async fn handler_accounts_switch(
     _: (),
     ctx: Arc<crate::RouteContext>,
     req: hyper::Request<hyper::Body>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
-    let lang = crate::get_lang_for_req(&req);
-    let cookies = get_cookie_map_for_req(&req)?;
-    let base_data =
-        fetch_base_data(&ctx.backend_host, &ctx.http_client, req.headers(), &cookies).await?;
-
-    #[derive(Deserialize)]
-    struct LookupQuery<'a> {
-        query: Option<Cow<'a, str>>,
-    }
-
-    let query: LookupQuery<'_> = serde_urlencoded::from_str(req.uri().query().unwrap_or(""))?;
-    let query = query.query;
-
-    #[derive(Deserialize)]
-    #[serde(rename_all = "snake_case")]
-    enum ActorType {
-        Community,
-        User,
-        #[serde(other)]
-        Unknown,
-    }
-
     #[derive(Deserialize)]
-    struct LookupResult {
-        id: i64,
-        #[serde(rename = "type")]
-        kind: ActorType,
+    struct SwitchBody {
+        user_id: i64,
     }
 
-    let api_res: Option<Result<Vec<LookupResult>, String>> = if let Some(query) = &query {
-        let api_res = res_to_error(
-            ctx.http_client
-                .request(
-                    hyper::Request::get(format!(
-                        "{}/api/unstable/actors:lookup/{}",
-                        ctx.backend_host,
-                        urlencoding::encode(query)
-                    ))
-                    .body(Default::default())?,
-                )
-                .await?,
-        )
-        .await;
+    let (req_parts, body) = req.into_parts();
 
-        Some(match api_res {
-            Ok(api_res) => {
-                let api_res = hyper::body::to_bytes(api_res.into_body()).await?;
-                Ok(serde_json::from_slice(&api_res)?)
-            }
-            Err(crate::Error::RemoteError((status, message))) if status.is_client_error() => {
-                Err(message)
-            }
-            Err(other) => return Err(other),
-        })
-    } else {
-        None
-    };
+    let cookies = get_cookie_map_for_headers(&req_parts.headers)?;
 
-    match api_res {
-        Some(Ok(items)) if !items.is_empty() => {
-            let item = &items[0];
-            let dest = match item.kind {
-                ActorType::Community => format!("/communities/{}", item.id),
-                ActorType::User => format!("/users/{}", item.id),
-                ActorType::Unknown => {
-                    return Err(crate::Error::InternalStr(
-                        "Unknown actor type received from lookup".to_owned(),
-                    ));
-                }
-            };
-            Ok(hyper::Response::builder()
-                .status(hyper::StatusCode::FOUND)
-                .header(hyper::header::LOCATION, dest)
-                .body("Redirecting…".into())?)
-        }
-        api_res => {
-            let title = lang.tr(&lang::LOOKUP_TITLE);
-            Ok(html_response(render::html! {
-                <HTPage base_data={&base_data} lang={&lang} title={&title}>
-                    <h1>{title.as_ref()}</h1>
-                    <form method={"GET"} action={"/lookup"}>
-                        <input r#type={"text"} name={"query"} value={query.as_deref().unwrap_or("")} />
-                    </form>
-                    {
-                        match api_res {
-                            None => None,
-                            Some(Ok(_)) => {
-                                // non-empty case is handled above
-                                Some(render::rsx! { <p>{lang.tr(&lang::LOOKUP_NOTHING)}</p> })
-                            },
-                            Some(Err(display_error)) => {
-                                Some(render::rsx! {
-                                    <div class={"errorBox"}>{display_error.into()}</div>
-                                })
-                            }
-                        }
-                    }
-                </HTPage>
-            }))
+    let body = hyper::body::to_bytes(body).await?;
+    let body: SwitchBody = serde_urlencoded::from_bytes(&body)?;
+
+    let accounts = Accounts::from_cookies(&ctx.cookie_key, &cookies);
+
+    match accounts.find(body.user_id) {
+        Some(account) => accounts_redirect(
+            "/",
+            accounts.to_set_cookies(&ctx.cookie_key, Some(&account.token), ctx.secure_cookies())?,
+            "Successfully switched account.",
+        ),
+        None => {
+            let lang = crate::get_lang_for_headers(&ctx, &req_parts.headers);
+
+            Err(crate::Error::UserError(crate::simple_response(
+                hyper::StatusCode::BAD_REQUEST,
+                lang.tr(&lang::accounts_switch_missing()).into_owned(),
+            )))
         }
     }
 }
@@ -490,28 +620,14 @@ async fn page_modlog(
     ctx: Arc<crate::RouteContext>,
     req: hyper::Request<hyper::Body>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
-    let lang = crate::get_lang_for_req(&req);
+    let lang = crate::get_lang_for_req(&ctx, &req);
     let cookies = get_cookie_map_for_req(&req)?;
 
-    let base_data =
-        fetch_base_data(&ctx.backend_host, &ctx.http_client, req.headers(), &cookies).await?;
-
-    let api_res = res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::get(format!(
-                    "{}/api/unstable/instance/modlog/events",
-                    ctx.backend_host,
-                ))
-                .body(Default::default())?,
-                req.headers(),
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await?;
-    let api_res = hyper::body::to_bytes(api_res.into_body()).await?;
-    let api_res: RespList<RespSiteModlogEvent> = serde_json::from_slice(&api_res)?;
+    let base_data = fetch_base_data(&ctx, req.headers(), &cookies).await?;
+
+    let api_res = Backend::for_request(&ctx, req.headers(), &cookies)
+        .list_site_modlog_events()
+        .await?;
 
     let title = lang.tr(&lang::MODLOG_SITE);
 
@@ -532,121 +648,6 @@ async fn page_modlog(
     }))
 }
 
-async fn page_my_invitations(
-    _: (),
-    ctx: Arc<crate::RouteContext>,
-    req: hyper::Request<hyper::Body>,
-) -> Result<hyper::Response<hyper::Body>, crate::Error> {
-    let cookies = get_cookie_map_for_req(&req)?;
-
-    page_my_invitations_inner(ctx, req.headers(), &cookies, None).await
-}
-
-async fn page_my_invitations_inner(
-    ctx: Arc<crate::RouteContext>,
-    headers: &hyper::header::HeaderMap,
-    cookies: &CookieMap<'_>,
-    res: Option<Result<InvitationsCreateResponse<'_>, &str>>,
-) -> Result<hyper::Response<hyper::Body>, crate::Error> {
-    let lang = crate::get_lang_for_headers(headers);
-
-    let base_data = fetch_base_data(&ctx.backend_host, &ctx.http_client, headers, &cookies).await?;
-
-    let can_create_result = match &base_data.login {
-        None => Err(lang.tr(&lang::MUST_LOGIN)),
-        Some(login) => {
-            if login.permissions.create_invitation.allowed {
-                Ok(())
-            } else {
-                Err(lang.tr(&lang::MISSING_PERMISSION_CREATE_INVITATION))
-            }
-        }
-    };
-
-    let title = lang.tr(&lang::MY_INVITATIONS);
-
-    match can_create_result {
-        Ok(()) => Ok(html_response(render::html! {
-            <HTPage base_data={&base_data} lang={&lang} title={&title}>
-                <h1>{title.as_ref()}</h1>
-                <form method={"POST"} action={"/my_invitations/create"}>
-                    <button type={"submit"}>{lang.tr(&lang::CREATE_INVITATION)}</button>
-                </form>
-                <br />
-                {
-                    if let Some(Ok(res)) = &res {
-                        let url = {
-                            let mut url = ctx.frontend_url.clone();
-                            url.path_segments_mut().unwrap().push("signup");
-                            url.set_query(Some(&format!("invitation_key={}", res.key)));
-                            url
-                        };
-
-                        Some(render::rsx! {
-                            <div>
-                                <p>{lang.tr(&lang::CREATE_INVITATION_RESULT)}</p>
-                                <input type={"text"} readonly={""} value={String::from(url)} />
-                            </div>
-                        })
-                    } else {
-                        None
-                    }
-                }
-                {
-                    if let Some(Err(message)) = res {
-                        Some(render::rsx! {
-                            <div class={"errorBox"}>
-                                {message}
-                            </div>
-                        })
-                    } else {
-                        None
-                    }
-                }
-            </HTPage>
-        })),
-        Err(err) => Ok(html_response(render::html! {
-            <HTPage base_data={&base_data} lang={&lang} title={&title}>
-                <h1>{title.as_ref()}</h1>
-                <div class={"errorBox"}>{err}</div>
-            </HTPage>
-        })),
-    }
-}
-
-async fn handler_my_invitations_create(
-    _: (),
-    ctx: Arc<crate::RouteContext>,
-    req: hyper::Request<hyper::Body>,
-) -> Result<hyper::Response<hyper::Body>, crate::Error> {
-    let cookies = get_cookie_map_for_req(&req)?;
-
-    let api_res = res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::post(format!("{}/api/unstable/invitations", ctx.backend_host))
-                    .body(Default::default())?,
-                &req.headers(),
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await;
-
-    match api_res {
-        Ok(api_res) => {
-            let api_res = hyper::body::to_bytes(api_res.into_body()).await?;
-            let api_res: InvitationsCreateResponse = serde_json::from_slice(&api_res)?;
-
-            page_my_invitations_inner(ctx, req.headers(), &cookies, Some(Ok(api_res))).await
-        }
-        Err(crate::Error::RemoteError((status, message))) if status.is_client_error() => {
-            page_my_invitations_inner(ctx, req.headers(), &cookies, Some(Err(&message))).await
-        }
-        Err(other) => Err(other),
-    }
-}
-
 async fn page_new_community(
     _: (),
     ctx: Arc<crate::RouteContext>,
@@ -664,8 +665,8 @@ async fn page_new_community_inner(
     display_error: Option<String>,
     prev_values: Option<&serde_json::Value>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
-    let lang = crate::get_lang_for_headers(headers);
-    let base_data = fetch_base_data(&ctx.backend_host, &ctx.http_client, headers, cookies).await?;
+    let lang = crate::get_lang_for_headers(&ctx, headers);
+    let base_data = fetch_base_data(&ctx, headers, cookies).await?;
 
     let title = lang.tr(&lang::COMMUNITY_CREATE);
 
@@ -727,33 +728,12 @@ async fn handler_new_community_submit(
     let body = hyper::body::to_bytes(body).await?;
     let body: serde_json::Value = serde_urlencoded::from_bytes(&body)?;
 
-    #[derive(Deserialize)]
-    struct CommunitiesCreateResponseCommunity {
-        id: i64,
-    }
-
-    #[derive(Deserialize)]
-    struct CommunitiesCreateResponse {
-        community: CommunitiesCreateResponseCommunity,
-    }
-
-    let api_res = res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::post(format!("{}/api/unstable/communities", ctx.backend_host))
-                    .body(serde_json::to_vec(&body)?.into())?,
-                &req_parts.headers,
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await;
+    let api_res = Backend::for_request(&ctx, &req_parts.headers, &cookies)
+        .create_community(&body)
+        .await;
 
     match api_res {
         Ok(api_res) => {
-            let api_res = hyper::body::to_bytes(api_res.into_body()).await?;
-            let api_res: CommunitiesCreateResponse = serde_json::from_slice(&api_res)?;
-
             let community_id = api_res.community.id;
 
             Ok(hyper::Response::builder()
@@ -783,38 +763,15 @@ async fn page_notifications(
     ctx: Arc<crate::RouteContext>,
     req: hyper::Request<hyper::Body>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
-    use futures_util::future::TryFutureExt;
-
-    let lang = crate::get_lang_for_req(&req);
+    let lang = crate::get_lang_for_req(&ctx, &req);
     let cookies = get_cookie_map_for_req(&req)?;
 
-    let api_res: Result<_, _> = res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::get(format!(
-                    "{}/api/unstable/users/~me/notifications",
-                    ctx.backend_host
-                ))
-                .body(Default::default())?,
-                req.headers(),
-                &cookies,
-            )?)
-            .await?,
+    let (base_data, api_res) = futures_util::future::join(
+        fetch_base_data(&ctx, req.headers(), &cookies),
+        Backend::for_request(&ctx, req.headers(), &cookies).list_notifications(),
     )
-    .map_err(crate::Error::from)
-    .and_then(|body| hyper::body::to_bytes(body).map_err(crate::Error::from))
     .await;
-
-    // I really hope there's a better way to do this
-    // I need to return the error in the Err case, but only borrow the value from Ok
-    let api_res: Result<Result<RespList<RespNotification>, _>, _> = if api_res.is_ok() {
-        Ok(serde_json::from_slice(&api_res.as_ref().unwrap()))
-    } else {
-        Err(api_res.unwrap_err())
-    };
-
-    let base_data =
-        fetch_base_data(&ctx.backend_host, &ctx.http_client, req.headers(), &cookies).await?;
+    let base_data = base_data?;
 
     let title = lang.tr(&lang::NOTIFICATIONS);
 
@@ -833,7 +790,7 @@ async fn page_notifications(
         }
         Err(other) => Err(other),
         Ok(api_res) => {
-            let notifications = api_res?.items;
+            let notifications = api_res.items;
 
             Ok(html_response(render::html! {
                 <HTPage base_data={&base_data} lang={&lang} title={&title}>
@@ -875,45 +832,23 @@ async fn page_signup_inner(
     display_error: Option<String>,
     prev_values: Option<&HashMap<Cow<'_, str>, serde_json::Value>>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
-    let lang = crate::get_lang_for_headers(headers);
+    let lang = crate::get_lang_for_headers(&ctx, headers);
     let cookies = get_cookie_map_for_headers(headers)?;
 
-    let base_data = fetch_base_data(&ctx.backend_host, &ctx.http_client, headers, &cookies).await?;
+    let base_data = fetch_base_data(&ctx, headers, &cookies).await?;
 
-    let api_res = res_to_error(
-        ctx.http_client
-            .get(
-                format!("{}/api/unstable/instance", ctx.backend_host)
-                    .try_into()
-                    .unwrap(),
-            )
-            .await?,
-    )
-    .await?;
-    let api_res = hyper::body::to_bytes(api_res.into_body()).await?;
-    let instance_info: RespInstanceInfo = serde_json::from_slice(&api_res)?;
+    let backend = Backend::for_request(&ctx, headers, &cookies);
+
+    let instance_info = backend.get_instance().await?;
 
     let can_signup_res = {
         if let Some(invitation_key) = &query.invitation_key {
-            let api_res = res_to_error(
-                ctx.http_client
-                    .get(
-                        format!(
-                            "{}/api/unstable/invitations?{}",
-                            ctx.backend_host,
-                            serde_urlencoded::to_string(&serde_json::json!({
-                                "key": invitation_key
-                            }))
-                            .unwrap()
-                        )
-                        .try_into()
-                        .unwrap(),
-                    )
-                    .await?,
-            )
-            .await?;
-            let api_res = hyper::body::to_bytes(api_res.into_body()).await?;
-            let api_res: RespList<RespInvitationInfo> = serde_json::from_slice(&api_res)?;
+            let api_res = backend
+                .list_invitations(&InvitationListQuery {
+                    key: Some(invitation_key.as_ref()),
+                    ..Default::default()
+                })
+                .await?;
 
             if let Some(info) = api_res.items.first() {
                 if info.used {
@@ -937,6 +872,16 @@ async fn page_signup_inner(
         }
     };
 
+    let captcha_kind = query.captcha.unwrap_or_default();
+    let captcha = ctx.captcha.generate(captcha_kind);
+    let captcha_switch_href = format!(
+        "/signup?{}",
+        serde_urlencoded::to_string(&SignupQuery {
+            invitation_key: query.invitation_key.clone(),
+            captcha: Some(captcha_kind.other()),
+        })?
+    );
+
     let title = lang.tr(&lang::REGISTER);
 
     Ok(html_response(render::html! {
@@ -983,6 +928,7 @@ async fn page_signup_inner(
                                 </td>
                             </tr>
                         </table>
+                        <CaptchaField challenge={captcha.as_ref()} switch_href={captcha_switch_href} lang={&lang} />
                         <button r#type={"submit"}>{lang.tr(&lang::REGISTER)}</button>
                     </form>
                 })
@@ -996,15 +942,15 @@ async fn handler_signup_submit(
     ctx: Arc<crate::RouteContext>,
     req: hyper::Request<hyper::Body>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
-    #[derive(Deserialize)]
-    struct UsersCreateResponse<'a> {
-        token: &'a str,
-    }
-
     let (req_parts, body) = req.into_parts();
+    check_rate_limit(&ctx, RateLimitClass::Signup, &req_parts)?;
 
     let body = hyper::body::to_bytes(body).await?;
     let mut body: HashMap<Cow<'_, str>, serde_json::Value> = serde_urlencoded::from_bytes(&body)?;
+
+    let captcha_kind = ctx.captcha.submitted_kind(&body);
+    let captcha_res = ctx.captcha.verify(&mut body);
+
     body.insert("login".into(), true.into());
     if body.get("email_address").and_then(|x| x.as_str()) == Some("") {
         body.remove("email_address");
@@ -1033,33 +979,28 @@ async fn handler_signup_submit(
 
     let query = SignupQuery {
         invitation_key: invitation_key.map(Cow::Borrowed),
+        captcha: Some(captcha_kind),
     };
 
-    let api_res = res_to_error(
-        ctx.http_client
-            .request(
-                hyper::Request::post(format!("{}/api/unstable/users", ctx.backend_host))
-                    .body(serde_json::to_vec(&body)?.into())?,
-            )
-            .await?,
-    )
-    .await;
+    if let Err(err) = captcha_res {
+        let lang = crate::get_lang_for_headers(&ctx, &req_parts.headers);
+        let message = lang.tr(&err.lang_key()).into_owned();
+
+        return page_signup_inner(ctx, &req_parts.headers, query, Some(message), Some(&body)).await;
+    }
+
+    let cookies = get_cookie_map_for_headers(&req_parts.headers)?;
+
+    let api_res = Backend::for_request(&ctx, &req_parts.headers, &cookies)
+        .create_user(&body)
+        .await;
 
     match api_res {
         Ok(api_res) => {
-            let api_res = hyper::body::to_bytes(api_res.into_body()).await?;
-            let api_res: UsersCreateResponse = serde_json::from_slice(&api_res)?;
-
-            let token = api_res.token;
+            let set_cookies =
+                add_account_set_cookies(&ctx, &req_parts.headers, &cookies, &api_res.token).await?;
 
-            Ok(hyper::Response::builder()
-                .status(hyper::StatusCode::SEE_OTHER)
-                .header(
-                    hyper::header::SET_COOKIE,
-                    format!("hitideToken={}; Path=/; Max-Age={}", token, COOKIE_AGE),
-                )
-                .header(hyper::header::LOCATION, "/")
-                .body("Successfully registered new account.".into())?)
+            accounts_redirect("/", set_cookies, "Successfully registered new account.")
         }
         Err(crate::Error::RemoteError((status, message))) if status.is_client_error() => {
             page_signup_inner(ctx, &req_parts.headers, query, Some(message), Some(&body)).await
@@ -1075,49 +1016,23 @@ async fn page_user(
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
     let (user_id,) = params;
 
-    let lang = crate::get_lang_for_req(&req);
+    let lang = crate::get_lang_for_req(&ctx, &req);
     let cookies = get_cookie_map_for_req(&req)?;
 
-    let base_data =
-        fetch_base_data(&ctx.backend_host, &ctx.http_client, req.headers(), &cookies).await?;
-
-    let user = res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::get(format!(
-                    "{}/api/unstable/users/{}{}",
-                    ctx.backend_host,
-                    user_id,
-                    if base_data.login.is_some() {
-                        "?include_your=true"
-                    } else {
-                        ""
-                    },
-                ))
-                .body(Default::default())?,
-                req.headers(),
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await?;
-    let user = hyper::body::to_bytes(user.into_body()).await?;
-    let user: RespUserInfo<'_> = serde_json::from_slice(&user)?;
-
-    let things = res_to_error(
-        ctx.http_client
-            .request(
-                hyper::Request::get(format!(
-                    "{}/api/unstable/users/{}/things",
-                    ctx.backend_host, user_id,
-                ))
-                .body(Default::default())?,
-            )
-            .await?,
-    )
-    .await?;
-    let things = hyper::body::to_bytes(things.into_body()).await?;
-    let things: RespList<RespThingInfo> = serde_json::from_slice(&things)?;
+    let backend = Backend::for_request(&ctx, req.headers(), &cookies);
+
+    let (base_data, (user, things)) =
+        fetch_with_base_data(&ctx, req.headers(), &cookies, |include_your| {
+            let backend = &backend;
+
+            async move {
+                futures_util::try_join!(
+                    backend.get_user(user_id, include_your),
+                    backend.list_user_things(user_id),
+                )
+            }
+        })
+        .await?;
 
     let title = user.as_ref().username.as_ref();
 
@@ -1180,6 +1095,19 @@ async fn page_user(
                                     None
                                 }
                             }
+                            {
+                                let is_me = base_data.login.as_ref().map(|login| login.user.id) == Some(user_id);
+
+                                if base_data.is_site_admin() && !is_me {
+                                    Some(render::rsx! {
+                                        <div>
+                                            <a href={format!("/users/{}/delete", user_id)}>{lang.tr(&lang::USER_DELETE)}</a>
+                                        </div>
+                                    })
+                                } else {
+                                    None
+                                }
+                            }
                         </>
                     })
                 } else {
@@ -1215,6 +1143,9 @@ async fn page_user(
                                 <div>
                                     <a href={format!("/users/{}/edit", user_id)}>{lang.tr(&lang::EDIT)}</a>
                                 </div>
+                                <div>
+                                    <a href={format!("/users/{}/security", user_id)}>{lang.tr(&lang::USER_SECURITY_TITLE)}</a>
+                                </div>
                                 {
                                     login.permissions.create_invitation.allowed.then(|| {
                                         render::rsx! {
@@ -1244,7 +1175,7 @@ async fn page_user(
             <ul>
                 {
                     things.items.iter().map(|thing| {
-                        ThingItem { thing, lang: &lang }
+                        ThingItem { thing, base_data: &base_data, lang: &lang }
                     })
                     .collect::<Vec<_>>()
                 }
@@ -1260,11 +1191,10 @@ async fn page_user_edit(
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
     let (user_id,) = params;
 
-    let lang = crate::get_lang_for_req(&req);
+    let lang = crate::get_lang_for_req(&ctx, &req);
     let cookies = get_cookie_map_for_req(&req)?;
 
-    let base_data =
-        fetch_base_data(&ctx.backend_host, &ctx.http_client, req.headers(), &cookies).await?;
+    let base_data = fetch_base_data(&ctx, req.headers(), &cookies).await?;
 
     let title = lang.tr(&lang::USER_EDIT_TITLE);
 
@@ -1286,24 +1216,16 @@ async fn page_user_edit(
         return Ok(res);
     }
 
-    let user = res_to_error(
-        ctx.http_client
-            .request(
-                hyper::Request::get(format!(
-                    "{}/api/unstable/users/{}",
-                    ctx.backend_host, user_id
-                ))
-                .body(Default::default())?,
-            )
-            .await?,
-    )
-    .await?;
-    let user = hyper::body::to_bytes(user.into_body()).await?;
-    let user: RespUserInfo<'_> = serde_json::from_slice(&user)?;
+    let user = Backend::for_request(&ctx, req.headers(), &cookies)
+        .get_user(user_id, false)
+        .await?;
 
     Ok(html_response(render::html! {
         <HTPage base_data={&base_data} lang={&lang} title={&title}>
             <h1>{title.as_ref()}</h1>
+            <p>
+                <a href={format!("/users/{}/security", user_id)}>{lang.tr(&lang::user_edit_security_link())}</a>
+            </p>
             <form method={"POST"} action={format!("/users/{}/edit/submit", user_id)}>
                 <div>
                     <label>
@@ -1311,12 +1233,6 @@ async fn page_user_edit(
                         <textarea name={"description_markdown"}>{user.description.content_markdown.as_deref().or(user.description.content_html.as_deref()).or(user.description.content_text.as_deref()).unwrap()}</textarea>
                     </label>
                 </div>
-                <div>
-                    <label>
-                        {lang.tr(&lang::user_edit_password_prompt())}<br />
-                        <input name={"password"} type={"password"} value={""} autocomplete={"new-password"} />
-                    </label>
-                </div>
                 <div>
                     <label>
                         <BoolCheckbox name={"is_bot"} value={user.base.is_bot} />
@@ -1344,26 +1260,14 @@ async fn handler_user_edit_submit(
     let mut body: serde_json::map::Map<String, serde_json::Value> =
         serde_urlencoded::from_bytes(&body)?;
 
-    // ignore password field if blank
-    if let Some(password) = body.get("password") {
-        if password == "" {
-            body.remove("password");
-        }
-    }
+    // password changes go through the security page, which checks the current password
+    body.remove("password");
 
     body.insert("is_bot".to_owned(), body.contains_key("is_bot").into());
 
-    res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::patch(format!("{}/api/unstable/users/~me", ctx.backend_host))
-                    .body(serde_json::to_vec(&body)?.into())?,
-                &req_parts.headers,
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await?;
+    Backend::for_request(&ctx, &req_parts.headers, &cookies)
+        .update_user_me(&body)
+        .await?;
 
     Ok(hyper::Response::builder()
         .status(hyper::StatusCode::SEE_OTHER)
@@ -1378,11 +1282,10 @@ async fn page_user_suspend(
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
     let (user_id,) = params;
 
-    let lang = crate::get_lang_for_req(&req);
+    let lang = crate::get_lang_for_req(&ctx, &req);
     let cookies = get_cookie_map_for_req(&req)?;
 
-    let base_data =
-        fetch_base_data(&ctx.backend_host, &ctx.http_client, req.headers(), &cookies).await?;
+    let base_data = fetch_base_data(&ctx, req.headers(), &cookies).await?;
 
     let title = lang.tr(&lang::USER_SUSPEND_TITLE);
 
@@ -1410,20 +1313,9 @@ async fn handler_user_suspend_submit(
 
     let cookies = get_cookie_map_for_req(&req)?;
 
-    res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::patch(format!(
-                    "{}/api/unstable/users/{}",
-                    ctx.backend_host, user_id
-                ))
-                .body(r#"{"suspended":true}"#.into())?,
-                req.headers(),
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await?;
+    Backend::for_request(&ctx, req.headers(), &cookies)
+        .set_user_suspended(user_id, true)
+        .await?;
 
     Ok(hyper::Response::builder()
         .status(hyper::StatusCode::SEE_OTHER)
@@ -1440,20 +1332,9 @@ async fn handler_user_suspend_undo(
 
     let cookies = get_cookie_map_for_req(&req)?;
 
-    res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::patch(format!(
-                    "{}/api/unstable/users/{}",
-                    ctx.backend_host, user_id
-                ))
-                .body(r#"{"suspended":false}"#.into())?,
-                req.headers(),
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await?;
+    Backend::for_request(&ctx, req.headers(), &cookies)
+        .set_user_suspended(user_id, false)
+        .await?;
 
     Ok(hyper::Response::builder()
         .status(hyper::StatusCode::SEE_OTHER)
@@ -1468,28 +1349,14 @@ async fn page_user_your_note_edit(
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
     let (user_id,) = params;
 
-    let lang = crate::get_lang_for_req(&req);
+    let lang = crate::get_lang_for_req(&ctx, &req);
     let cookies = get_cookie_map_for_req(&req)?;
 
-    let base_data =
-        fetch_base_data(&ctx.backend_host, &ctx.http_client, req.headers(), &cookies).await?;
-
-    let user = res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::get(format!(
-                    "{}/api/unstable/users/{}?include_your=true",
-                    ctx.backend_host, user_id,
-                ))
-                .body(Default::default())?,
-                req.headers(),
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await?;
-    let user = hyper::body::to_bytes(user.into_body()).await?;
-    let user: RespUserInfo<'_> = serde_json::from_slice(&user)?;
+    let base_data = fetch_base_data(&ctx, req.headers(), &cookies).await?;
+
+    let user = Backend::for_request(&ctx, req.headers(), &cookies)
+        .get_user(user_id, true)
+        .await?;
 
     let title = lang.tr(&lang::YOUR_NOTE_EDIT);
 
@@ -1522,20 +1389,9 @@ async fn handler_user_your_note_edit_submit(
     let body = hyper::body::to_bytes(body).await?;
     let body: serde_json::Value = serde_urlencoded::from_bytes(&body)?;
 
-    res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::put(format!(
-                    "{}/api/unstable/users/{}/your_note",
-                    ctx.backend_host, user_id
-                ))
-                .body(serde_json::to_vec(&body)?.into())?,
-                &req_parts.headers,
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await?;
+    Backend::for_request(&ctx, &req_parts.headers, &cookies)
+        .update_user_your_note(user_id, &body)
+        .await?;
 
     Ok(hyper::Response::builder()
         .status(hyper::StatusCode::SEE_OTHER)
@@ -1548,11 +1404,10 @@ async fn page_home(
     ctx: Arc<crate::RouteContext>,
     req: hyper::Request<hyper::Body>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
-    let lang = crate::get_lang_for_req(&req);
+    let lang = crate::get_lang_for_req(&ctx, &req);
     let cookies = get_cookie_map_for_req(&req)?;
 
-    let base_data =
-        fetch_base_data(&ctx.backend_host, &ctx.http_client, req.headers(), &cookies).await?;
+    let base_data = fetch_base_data(&ctx, req.headers(), &cookies).await?;
 
     if base_data.login.is_none() {
         return page_all_inner(req.headers(), &cookies, &base_data, req.uri().query(), ctx).await;
@@ -1565,35 +1420,22 @@ async fn page_home(
 
     let query: Query = serde_urlencoded::from_str(req.uri().query().unwrap_or(""))?;
 
-    let api_res = res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::get(format!(
-                    "{}/api/unstable/posts?{}",
-                    ctx.backend_host,
-                    serde_urlencoded::to_string(&PostListQuery {
-                        in_your_follows: Some(true),
-                        include_your: Some(true),
-                        page: query.page.as_deref(),
-                        ..Default::default()
-                    })?,
-                ))
-                .body(Default::default())?,
-                req.headers(),
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await?;
-
-    let api_res = hyper::body::to_bytes(api_res.into_body()).await?;
-    let api_res: RespList<RespPostListPost<'_>> = serde_json::from_slice(&api_res)?;
+    let api_res = Backend::for_request(&ctx, req.headers(), &cookies)
+        .list_posts(&PostListQuery {
+            in_your_follows: Some(true),
+            include_your: Some(true),
+            sort: Some(base_data.preferences.post_sort.as_str()),
+            limit: base_data.preferences.posts_per_page,
+            page: query.page.as_deref(),
+            ..Default::default()
+        })
+        .await?;
 
     let home_follow_prompt_src = lang::home_follow_prompt(lang::LangPlaceholder(0));
     let home_follow_prompt_src = lang.tr(&home_follow_prompt_src);
 
     Ok(html_response(render::html! {
-        <HTPage base_data={&base_data} lang={&lang} title={"lotide"}>
+        <HTPage base_data={&base_data} lang={&lang} title={&base_data.branding.site_name}>
             {
                 if api_res.items.is_empty() {
                     Some(render::rsx! {
@@ -1621,7 +1463,7 @@ async fn page_home(
             }
             <ul>
                 {api_res.items.iter().map(|post| {
-                    PostItem { post, in_community: false, no_user: false, lang: &lang }
+                    PostItem { post, in_community: false, no_user: false, base_data: &base_data, lang: &lang }
                 }).collect::<Vec<_>>()}
             </ul>
             {
@@ -1644,8 +1486,7 @@ async fn page_all(
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
     let cookies = get_cookie_map_for_req(&req)?;
 
-    let base_data =
-        fetch_base_data(&ctx.backend_host, &ctx.http_client, req.headers(), &cookies).await?;
+    let base_data = fetch_base_data(&ctx, req.headers(), &cookies).await?;
 
     page_all_inner(req.headers(), &cookies, &base_data, req.uri().query(), ctx).await
 }
@@ -1657,7 +1498,7 @@ async fn page_all_inner(
     query: Option<&str>,
     ctx: Arc<crate::RouteContext>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
-    let lang = crate::get_lang_for_headers(headers);
+    let lang = crate::get_lang_for_headers(&ctx, headers);
 
     #[derive(Deserialize)]
     struct Query<'a> {
@@ -1666,31 +1507,18 @@ async fn page_all_inner(
 
     let query: Query = serde_urlencoded::from_str(query.unwrap_or(""))?;
 
-    let api_res = res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::get(format!(
-                    "{}/api/unstable/posts?{}",
-                    ctx.backend_host,
-                    serde_urlencoded::to_string(&PostListQuery {
-                        use_aggregate_filters: Some(true),
-                        page: query.page.as_deref(),
-                        ..Default::default()
-                    })?,
-                ))
-                .body(Default::default())?,
-                headers,
-                cookies,
-            )?)
-            .await?,
-    )
-    .await?;
-
-    let api_res = hyper::body::to_bytes(api_res.into_body()).await?;
-    let api_res: RespList<RespPostListPost<'_>> = serde_json::from_slice(&api_res)?;
+    let api_res = Backend::for_request(&ctx, headers, cookies)
+        .list_posts(&PostListQuery {
+            use_aggregate_filters: Some(true),
+            sort: Some(base_data.preferences.post_sort.as_str()),
+            limit: base_data.preferences.posts_per_page,
+            page: query.page.as_deref(),
+            ..Default::default()
+        })
+        .await?;
 
     Ok(html_response(render::html! {
-        <HTPage base_data={base_data} lang={&lang} title={"lotide"}>
+        <HTPage base_data={base_data} lang={&lang} title={&base_data.branding.site_name}>
             <h1>{lang.tr(&lang::all_title())}</h1>
             {
                 if api_res.items.is_empty() {
@@ -1705,7 +1533,7 @@ async fn page_all_inner(
             }
             <ul>
                 {api_res.items.iter().map(|post| {
-                    PostItem { post, in_community: false, no_user: false, lang: &lang }
+                    PostItem { post, in_community: false, no_user: false, base_data: &base_data, lang: &lang }
                 }).collect::<Vec<_>>()}
             </ul>
             {
@@ -1726,14 +1554,11 @@ async fn page_flags(
     ctx: Arc<crate::RouteContext>,
     req: hyper::Request<hyper::Body>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
-    use futures_util::TryFutureExt;
-
     let cookies = get_cookie_map_for_req(&req)?;
 
-    let base_data =
-        fetch_base_data(&ctx.backend_host, &ctx.http_client, req.headers(), &cookies).await?;
+    let base_data = fetch_base_data(&ctx, req.headers(), &cookies).await?;
 
-    let lang = crate::get_lang_for_headers(req.headers());
+    let lang = crate::get_lang_for_headers(&ctx, req.headers());
 
     #[derive(Deserialize)]
     struct Query {
@@ -1743,26 +1568,13 @@ async fn page_flags(
 
     let query: Query = serde_urlencoded::from_str(req.uri().query().unwrap_or(""))?;
 
-    let api_res = res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::get(format!(
-                    "{}/api/unstable/flags?{}",
-                    ctx.backend_host,
-                    serde_urlencoded::to_string(&FlagListQuery {
-                        to_this_site_admin: query.to_this_site_admin,
-                        to_community: query.to_community,
-                    })?,
-                ))
-                .body(Default::default())?,
-                req.headers(),
-                &cookies,
-            )?)
-            .await?,
-    )
-    .map_err(crate::Error::from)
-    .and_then(|api_res| hyper::body::to_bytes(api_res.into_body()).map_err(crate::Error::from))
-    .await;
+    let api_res = Backend::for_request(&ctx, req.headers(), &cookies)
+        .list_flags(&FlagListQuery {
+            to_this_site_admin: query.to_this_site_admin,
+            to_community: query.to_community,
+            ..Default::default()
+        })
+        .await;
 
     let title = match query {
         Query {
@@ -1790,31 +1602,27 @@ async fn page_flags(
             Ok(res)
         }
         Err(other) => Err(other),
-        Ok(api_res) => {
-            let api_res: RespList<RespFlagInfo<'_>> = serde_json::from_slice(&api_res)?;
-
-            Ok(html_response(render::html! {
-                <HTPage base_data={&base_data} lang={&lang} title={&title}>
-                    <h1>{title.as_ref()}</h1>
-                    {
-                        if api_res.items.is_empty() {
-                            Some(render::rsx! {
-                                <p>
-                                    {lang.tr(&lang::NOTHING)}
-                                </p>
-                            })
-                        } else {
-                            None
-                        }
+        Ok(api_res) => Ok(html_response(render::html! {
+            <HTPage base_data={&base_data} lang={&lang} title={&title}>
+                <h1>{title.as_ref()}</h1>
+                {
+                    if api_res.items.is_empty() {
+                        Some(render::rsx! {
+                            <p>
+                                {lang.tr(&lang::NOTHING)}
+                            </p>
+                        })
+                    } else {
+                        None
                     }
-                    <ul>
-                    {api_res.items.iter().map(|flag| {
-                        FlagItem { flag, in_community: query.to_community.is_some(), lang: &lang }
-                    }).collect::<Vec<_>>()}
-                    </ul>
-                </HTPage>
-            }))
-        }
+                }
+                <ul>
+                {api_res.items.iter().map(|flag| {
+                    FlagItem { flag, in_community: query.to_community.is_some(), lang: &lang }
+                }).collect::<Vec<_>>()}
+                </ul>
+            </HTPage>
+        })),
     }
 }
 
@@ -1825,10 +1633,9 @@ async fn page_local(
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
     let cookies = get_cookie_map_for_req(&req)?;
 
-    let base_data =
-        fetch_base_data(&ctx.backend_host, &ctx.http_client, req.headers(), &cookies).await?;
+    let base_data = fetch_base_data(&ctx, req.headers(), &cookies).await?;
 
-    let lang = crate::get_lang_for_headers(req.headers());
+    let lang = crate::get_lang_for_headers(&ctx, req.headers());
 
     #[derive(Deserialize)]
     struct Query<'a> {
@@ -1837,32 +1644,19 @@ async fn page_local(
 
     let query: Query = serde_urlencoded::from_str(req.uri().query().unwrap_or(""))?;
 
-    let api_res = res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::get(format!(
-                    "{}/api/unstable/posts?{}",
-                    ctx.backend_host,
-                    serde_urlencoded::to_string(&PostListQuery {
-                        use_aggregate_filters: Some(true),
-                        in_any_local_community: Some(true),
-                        page: query.page.as_deref(),
-                        ..Default::default()
-                    })?,
-                ))
-                .body(Default::default())?,
-                req.headers(),
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await?;
-
-    let api_res = hyper::body::to_bytes(api_res.into_body()).await?;
-    let api_res: RespList<RespPostListPost<'_>> = serde_json::from_slice(&api_res)?;
+    let api_res = Backend::for_request(&ctx, req.headers(), &cookies)
+        .list_posts(&PostListQuery {
+            use_aggregate_filters: Some(true),
+            in_any_local_community: Some(true),
+            sort: Some(base_data.preferences.post_sort.as_str()),
+            limit: base_data.preferences.posts_per_page,
+            page: query.page.as_deref(),
+            ..Default::default()
+        })
+        .await?;
 
     Ok(html_response(render::html! {
-        <HTPage base_data={&base_data} lang={&lang} title={"lotide"}>
+        <HTPage base_data={&base_data} lang={&lang} title={&base_data.branding.site_name}>
             <h1>{lang.tr(&lang::local_title())}</h1>
             {
                 if api_res.items.is_empty() {
@@ -1877,7 +1671,7 @@ async fn page_local(
             }
             <ul>
                 {api_res.items.iter().map(|post| {
-                    PostItem { post, in_community: false, no_user: false, lang: &lang }
+                    PostItem { post, in_community: false, no_user: false, base_data: &base_data, lang: &lang }
                 }).collect::<Vec<_>>()}
             </ul>
             {
@@ -1900,11 +1694,20 @@ pub fn route_root() -> crate::RouteNode<()> {
             "about",
             crate::RouteNode::new().with_handler_async(hyper::Method::GET, page_about),
         )
+        .with_child(
+            "accounts",
+            crate::RouteNode::new().with_child(
+                "switch",
+                crate::RouteNode::new()
+                    .with_handler_async(hyper::Method::POST, handler_accounts_switch),
+            ),
+        )
         .with_child("administration", administration::route_administration())
         .with_child(
             "all",
             crate::RouteNode::new().with_handler_async(hyper::Method::GET, page_all),
         )
+        .with_child("c", lookup::route_community_handle())
         .with_child("comments", comments::route_comments())
         .with_child("communities", communities::route_communities())
         .with_child(
@@ -1912,6 +1715,11 @@ pub fn route_root() -> crate::RouteNode<()> {
             crate::RouteNode::new().with_handler_async(hyper::Method::GET, page_flags),
         )
         .with_child("forgot_password", forgot_password::route_forgot_password())
+        .with_child(
+            "language",
+            crate::RouteNode::new()
+                .with_handler_async(hyper::Method::POST, handler_language_submit),
+        )
         .with_child(
             "local",
             crate::RouteNode::new().with_handler_async(hyper::Method::GET, page_local),
@@ -1932,23 +1740,14 @@ pub fn route_root() -> crate::RouteNode<()> {
         )
         .with_child(
             "lookup",
-            crate::RouteNode::new().with_handler_async(hyper::Method::GET, page_lookup),
+            crate::RouteNode::new().with_handler_async(hyper::Method::GET, lookup::page_lookup),
         )
         .with_child("moderation", moderation::route_moderation())
         .with_child(
             "modlog",
             crate::RouteNode::new().with_handler_async(hyper::Method::GET, page_modlog),
         )
-        .with_child(
-            "my_invitations",
-            crate::RouteNode::new()
-                .with_handler_async(hyper::Method::GET, page_my_invitations)
-                .with_child(
-                    "create",
-                    crate::RouteNode::new()
-                        .with_handler_async(hyper::Method::POST, handler_my_invitations_create),
-                ),
-        )
+        .with_child("my_invitations", invitations::route_my_invitations())
         .with_child(
             "new_community",
             crate::RouteNode::new()
@@ -1964,6 +1763,7 @@ pub fn route_root() -> crate::RouteNode<()> {
             crate::RouteNode::new().with_handler_async(hyper::Method::GET, page_notifications),
         )
         .with_child("posts", posts::route_posts())
+        .with_child("preferences", preferences::route_preferences())
         .with_child(
             "signup",
             crate::RouteNode::new()
@@ -1975,11 +1775,13 @@ pub fn route_root() -> crate::RouteNode<()> {
                 ),
         )
         .with_child("static", r#static::route_static())
+        .with_child("u", lookup::route_user_handle())
         .with_child(
             "users",
             crate::RouteNode::new().with_child_parse::<i64, _>(
                 crate::RouteNode::new()
                     .with_handler_async(hyper::Method::GET, page_user)
+                    .with_child("delete", account::route_user_delete())
                     .with_child(
                         "edit",
                         crate::RouteNode::new()
@@ -1992,6 +1794,8 @@ pub fn route_root() -> crate::RouteNode<()> {
                                 ),
                             ),
                     )
+                    .with_child("security", account::route_user_security())
+                    .with_child("export", account::route_user_export())
                     .with_child(
                         "suspend",
                         crate::RouteNode::new()
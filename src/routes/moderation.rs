@@ -1,10 +1,8 @@
-use super::{
-    fetch_base_data, for_client, get_cookie_map_for_headers, get_cookie_map_for_req, html_response,
-    res_to_error,
-};
+use super::{fetch_base_data, get_cookie_map_for_headers, get_cookie_map_for_req, html_response};
+use crate::backend::Backend;
 use crate::components::{FlagItem, HTPage};
 use crate::lang;
-use crate::resp_types::{RespCommunityInfoMaybeYour, RespFlagInfo, RespList};
+use crate::query_types::FlagListQuery;
 use serde_derive::Deserialize;
 use std::ops::Deref;
 use std::sync::Arc;
@@ -15,7 +13,7 @@ async fn page_moderation(
     req: hyper::Request<hyper::Body>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
     let cookies = get_cookie_map_for_req(&req)?;
-    let lang = crate::get_lang_for_req(&req);
+    let lang = crate::get_lang_for_req(&ctx, &req);
 
     #[derive(Deserialize)]
     struct Query {
@@ -24,51 +22,25 @@ async fn page_moderation(
 
     let query: Query = serde_urlencoded::from_str(req.uri().query().unwrap_or(""))?;
 
-    let base_data =
-        fetch_base_data(&ctx.backend_host, &ctx.http_client, req.headers(), &cookies).await?;
+    let base_data = fetch_base_data(&ctx, req.headers(), &cookies).await?;
 
-    let communities_api_res = res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::get(format!(
-                    "{}/api/unstable/communities?you_are_moderator=true&include_your=true",
-                    ctx.backend_host,
-                ))
-                .body(Default::default())?,
-                req.headers(),
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await?;
+    let backend = Backend::for_request(&ctx, req.headers(), &cookies);
 
-    let communities_api_res = hyper::body::to_bytes(communities_api_res.into_body()).await?;
-    let communities: RespList<RespCommunityInfoMaybeYour> =
-        serde_json::from_slice(&communities_api_res)?;
+    let communities = backend.list_moderated_communities().await?;
 
-    let flags_api_res = if let Some(community) = query.community {
-        let api_res = res_to_error(
-            ctx.http_client
-                .request(for_client(
-                    hyper::Request::get(format!(
-                        "{}/api/unstable/flags?to_community={}&dismissed=false",
-                        ctx.backend_host, community,
-                    ))
-                    .body(Default::default())?,
-                    req.headers(),
-                    &cookies,
-                )?)
+    let flags = if let Some(community) = query.community {
+        Some(
+            backend
+                .list_flags(&FlagListQuery {
+                    to_community: Some(community),
+                    dismissed: Some(false),
+                    ..Default::default()
+                })
                 .await?,
         )
-        .await?;
-        Some(hyper::body::to_bytes(api_res.into_body()).await?)
     } else {
         None
     };
-    let flags: Option<RespList<RespFlagInfo>> = flags_api_res
-        .as_ref()
-        .map(|x| serde_json::from_slice(x))
-        .transpose()?;
 
     let title = lang.tr(&lang::MODERATION_DASHBOARD);
 
@@ -123,20 +95,9 @@ async fn handler_moderation_submit_dismiss(
 
     let cookies = get_cookie_map_for_headers(&req_parts.headers)?;
 
-    res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::patch(format!(
-                    "{}/api/unstable/flags/{}",
-                    ctx.backend_host, body.flag
-                ))
-                .body(r#"{"community_dismissed":true}"#.into())?,
-                &req_parts.headers,
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await?;
+    Backend::for_request(&ctx, &req_parts.headers, &cookies)
+        .dismiss_flag(body.flag)
+        .await?;
 
     Ok(hyper::Response::builder()
         .status(hyper::StatusCode::SEE_OTHER)
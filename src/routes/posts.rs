@@ -1,21 +1,19 @@
-use super::JustStringID;
 use super::{
-    fetch_base_data, for_client, get_cookie_map_for_headers, get_cookie_map_for_req, html_response,
-    res_to_error, CookieMap,
+    check_rate_limit, fetch_base_data, fetch_with_base_data, get_cookie_map_for_headers,
+    get_cookie_map_for_req, html_response, CookieMap,
 };
+use crate::backend::Backend;
 use crate::components::{
     Comment, CommunityLink, ContentView, HTPage, IconExt, MaybeFillCheckbox, MaybeFillTextArea,
     PollView, TimeAgo, UserLink,
 };
 use crate::lang;
-use crate::query_types::PollVoteBody;
-use crate::resp_types::{
-    JustContentHTML, JustID, JustUser, RespCommunityInfoMaybeYour, RespList, RespPostCommentInfo,
-    RespPostInfo,
-};
+use crate::preferences::Preferences;
+use crate::query_types::{PollVoteBody, ReplyListQuery};
+use crate::rate_limit::RateLimitClass;
 use crate::util::author_is_me;
 use render::Render;
-use serde_derive::{Deserialize, Serialize};
+use serde_derive::Deserialize;
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -54,96 +52,46 @@ async fn page_post_inner(
     prev_values: Option<&HashMap<Cow<'_, str>, serde_json::Value>>,
     display_preview: Option<&str>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
-    let lang = crate::get_lang_for_headers(headers);
+    let lang = crate::get_lang_for_headers(&ctx, headers);
 
     #[derive(Deserialize)]
     struct Query<'a> {
-        #[serde(default = "super::default_comments_sort")]
-        sort: crate::SortType,
+        sort: Option<crate::SortType>,
         page: Option<Cow<'a, str>>,
     }
 
     let query: Query = serde_urlencoded::from_str(query.unwrap_or(""))?;
+    let sort = query
+        .sort
+        .unwrap_or(Preferences::from_cookies(&ctx.cookie_key, cookies).comment_sort);
+
+    let backend = Backend::for_request(&ctx, headers, cookies);
+
+    let (base_data, (post, replies)) =
+        fetch_with_base_data(&ctx, headers, cookies, |include_your| {
+            let backend = &backend;
+            let query = &query;
+
+            async move {
+                let replies_query = ReplyListQuery {
+                    include_your: if include_your { Some(true) } else { None },
+                    sort: Some(sort),
+                    page: query.page.as_deref(),
+                };
 
-    let base_data = fetch_base_data(&ctx.backend_host, &ctx.http_client, headers, cookies).await?;
-
-    let api_res = res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::get(format!(
-                    "{}/api/unstable/posts/{}{}",
-                    ctx.backend_host,
-                    post_id,
-                    if base_data.login.is_some() {
-                        "?include_your=true"
-                    } else {
-                        ""
-                    },
-                ))
-                .body(Default::default())?,
-                headers,
-                cookies,
-            )?)
-            .await?,
-    )
-    .await?;
-    let api_res = hyper::body::to_bytes(api_res.into_body()).await?;
-    let post: RespPostInfo = serde_json::from_slice(&api_res)?;
-
-    #[derive(Serialize)]
-    struct RepliesListQuery<'a> {
-        include_your: Option<bool>,
-        sort: Option<crate::SortType>,
-        page: Option<&'a str>,
-    }
-    let api_req_query = RepliesListQuery {
-        include_your: if base_data.login.is_some() {
-            Some(true)
-        } else {
-            None
-        },
-        sort: Some(query.sort),
-        page: query.page.as_deref(),
-    };
-    let api_req_query = serde_urlencoded::to_string(&api_req_query)?;
-
-    let replies_api_res = res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::get(format!(
-                    "{}/api/unstable/posts/{}/replies?{}",
-                    ctx.backend_host, post_id, api_req_query,
-                ))
-                .body(Default::default())?,
-                headers,
-                cookies,
-            )?)
-            .await?,
-    )
-    .await?;
-    let replies_api_res = hyper::body::to_bytes(replies_api_res.into_body()).await?;
-    let replies: RespList<RespPostCommentInfo> = serde_json::from_slice(&replies_api_res)?;
+                futures_util::try_join!(
+                    backend.get_post(post_id, include_your),
+                    backend.list_post_replies(post_id, &replies_query),
+                )
+            }
+        })
+        .await?;
 
     let is_community_moderator = !post.as_ref().community.deleted
         && if base_data.login.is_some() {
-            let api_res = res_to_error(
-                ctx.http_client
-                    .request(for_client(
-                        hyper::Request::get(format!(
-                            "{}/api/unstable/communities/{}?include_your=true",
-                            ctx.backend_host,
-                            post.as_ref().community.id,
-                        ))
-                        .body(Default::default())?,
-                        headers,
-                        cookies,
-                    )?)
-                    .await?,
-            )
-            .await?;
-            let api_res = hyper::body::to_bytes(api_res.into_body()).await?;
-
-            let info: RespCommunityInfoMaybeYour = serde_json::from_slice(&api_res)?;
+            let info = backend
+                .get_community(post.as_ref().community.id, true)
+                .await?;
             info.you_are_moderator.unwrap()
         } else {
             false
@@ -266,7 +214,7 @@ async fn page_post_inner(
             {
                 post.as_ref().href.as_ref().map(|href| {
                     render::rsx! {
-                        <p><a rel={"ugc noopener"} href={href.as_ref()}>{href.as_ref()}</a></p>
+                        <p><a rel={"ugc noopener"} href={href.as_ref()} target={base_data.preferences.external_link_target()}>{href.as_ref()}</a></p>
                     }
                 })
             }
@@ -380,7 +328,7 @@ async fn page_post_inner(
                         crate::SortType::VALUES.iter()
                             .map(|value| {
                                 let name = lang.tr(&value.lang_key()).into_owned();
-                                if query.sort == *value {
+                                if sort == *value {
                                     render::rsx! { <span>{name}</span> }
                                 } else {
                                     render::rsx! { <a href={format!("/posts/{}?sort={}", post_id, value.as_str())}>{name}</a> }
@@ -393,7 +341,7 @@ async fn page_post_inner(
                     {
                         replies.items.iter().map(|comment| {
                             render::rsx! {
-                                <Comment comment={comment} sort={query.sort} root_sensitive={post.as_ref().as_ref().sensitive} base_data={&base_data} lang={&lang} />
+                                <Comment comment={comment} sort={sort} root_sensitive={post.as_ref().as_ref().sensitive} base_data={&base_data} lang={&lang} />
                             }
                         }).collect::<Vec<_>>()
                     }
@@ -417,29 +365,14 @@ async fn page_post_delete(
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
     let (post_id,) = params;
 
-    let lang = crate::get_lang_for_req(&req);
+    let lang = crate::get_lang_for_req(&ctx, &req);
     let cookies = get_cookie_map_for_req(&req)?;
 
-    let base_data =
-        fetch_base_data(&ctx.backend_host, &ctx.http_client, req.headers(), &cookies).await?;
-
-    let api_res = res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::get(format!(
-                    "{}/api/unstable/posts/{}",
-                    ctx.backend_host, post_id
-                ))
-                .body(Default::default())?,
-                req.headers(),
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await?;
-    let api_res = hyper::body::to_bytes(api_res.into_body()).await?;
+    let base_data = fetch_base_data(&ctx, req.headers(), &cookies).await?;
 
-    let post: RespPostInfo = serde_json::from_slice(&api_res)?;
+    let post = Backend::for_request(&ctx, req.headers(), &cookies)
+        .get_post(post_id, false)
+        .await?;
 
     Ok(html_response(render::html! {
         <HTPage base_data={&base_data} lang={&lang} title={&lang.tr(&lang::post_delete_title())}>
@@ -463,20 +396,9 @@ async fn handler_post_delete_confirm(
 
     let cookies = get_cookie_map_for_req(&req)?;
 
-    res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::delete(format!(
-                    "{}/api/unstable/posts/{}",
-                    ctx.backend_host, post_id,
-                ))
-                .body("".into())?,
-                req.headers(),
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await?;
+    Backend::for_request(&ctx, req.headers(), &cookies)
+        .delete_post(post_id)
+        .await?;
 
     Ok(hyper::Response::builder()
         .status(hyper::StatusCode::SEE_OTHER)
@@ -491,29 +413,14 @@ async fn page_post_site_block(
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
     let (post_id,) = params;
 
-    let lang = crate::get_lang_for_req(&req);
+    let lang = crate::get_lang_for_req(&ctx, &req);
     let cookies = get_cookie_map_for_req(&req)?;
 
-    let base_data =
-        fetch_base_data(&ctx.backend_host, &ctx.http_client, req.headers(), &cookies).await?;
-
-    let api_res = res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::get(format!(
-                    "{}/api/unstable/posts/{}",
-                    ctx.backend_host, post_id
-                ))
-                .body(Default::default())?,
-                req.headers(),
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await?;
-    let api_res = hyper::body::to_bytes(api_res.into_body()).await?;
+    let base_data = fetch_base_data(&ctx, req.headers(), &cookies).await?;
 
-    let post: RespPostInfo = serde_json::from_slice(&api_res)?;
+    let post = Backend::for_request(&ctx, req.headers(), &cookies)
+        .get_post(post_id, false)
+        .await?;
 
     Ok(html_response(render::html! {
         <HTPage base_data={&base_data} lang={&lang} title={&lang.tr(&lang::post_site_block_title())}>
@@ -538,42 +445,12 @@ async fn handler_post_site_block_confirm(
 
     let cookies = get_cookie_map_for_req(&req)?;
 
-    let api_res_get = res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::get(format!(
-                    "{}/api/unstable/posts/{}",
-                    ctx.backend_host, post_id
-                ))
-                .body(Default::default())?,
-                req.headers(),
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await?;
-    let api_res_get = hyper::body::to_bytes(api_res_get.into_body()).await?;
-    let post: RespPostInfo = serde_json::from_slice(&api_res_get)?;
+    let backend = Backend::for_request(&ctx, req.headers(), &cookies);
+
+    let post = backend.get_post(post_id, false).await?;
 
     if let Some(remote_url) = &post.as_ref().as_ref().remote_url {
-        res_to_error(
-            ctx.http_client
-                .request(for_client(
-                    hyper::Request::put(format!(
-                        "{}/api/unstable/objects:blocks/{}",
-                        ctx.backend_host,
-                        percent_encoding::utf8_percent_encode(
-                            &remote_url,
-                            percent_encoding::NON_ALPHANUMERIC
-                        ),
-                    ))
-                    .body("".into())?,
-                    req.headers(),
-                    &cookies,
-                )?)
-                .await?,
-        )
-        .await?;
+        backend.block_object(remote_url).await?;
 
         Ok(hyper::Response::builder()
             .status(hyper::StatusCode::SEE_OTHER)
@@ -595,29 +472,14 @@ async fn page_post_flag(
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
     let (post_id,) = params;
 
-    let lang = crate::get_lang_for_req(&req);
+    let lang = crate::get_lang_for_req(&ctx, &req);
     let cookies = get_cookie_map_for_req(&req)?;
 
-    let base_data =
-        fetch_base_data(&ctx.backend_host, &ctx.http_client, req.headers(), &cookies).await?;
-
-    let api_res = res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::get(format!(
-                    "{}/api/unstable/posts/{}",
-                    ctx.backend_host, post_id
-                ))
-                .body(Default::default())?,
-                req.headers(),
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await?;
-    let api_res = hyper::body::to_bytes(api_res.into_body()).await?;
+    let base_data = fetch_base_data(&ctx, req.headers(), &cookies).await?;
 
-    let post: RespPostInfo = serde_json::from_slice(&api_res)?;
+    let post = Backend::for_request(&ctx, req.headers(), &cookies)
+        .get_post(post_id, false)
+        .await?;
 
     Ok(html_response(render::html! {
         <HTPage base_data={&base_data} lang={&lang} title={&lang.tr(&lang::post_flag_title())}>
@@ -667,20 +529,9 @@ async fn handler_post_flag_submit(
         body.insert((*key).to_owned(), body.contains_key(*key).into());
     }
 
-    res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::post(format!(
-                    "{}/api/unstable/posts/{}/flags",
-                    ctx.backend_host, post_id
-                ))
-                .body(serde_json::to_vec(&body)?.into())?,
-                &req_parts.headers,
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await?;
+    Backend::for_request(&ctx, &req_parts.headers, &cookies)
+        .create_post_flag(post_id, &body)
+        .await?;
 
     Ok(hyper::Response::builder()
         .status(hyper::StatusCode::SEE_OTHER)
@@ -697,20 +548,9 @@ async fn handler_post_like(
 
     let cookies = get_cookie_map_for_req(&req)?;
 
-    res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::put(format!(
-                    "{}/api/unstable/posts/{}/your_vote",
-                    ctx.backend_host, post_id
-                ))
-                .body("{}".into())?,
-                req.headers(),
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await?;
+    Backend::for_request(&ctx, req.headers(), &cookies)
+        .like_post(post_id)
+        .await?;
 
     Ok(hyper::Response::builder()
         .status(hyper::StatusCode::SEE_OTHER)
@@ -725,28 +565,14 @@ async fn page_post_likes(
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
     let (post_id,) = params;
 
-    let lang = crate::get_lang_for_req(&req);
+    let lang = crate::get_lang_for_req(&ctx, &req);
     let cookies = get_cookie_map_for_req(&req)?;
 
-    let base_data =
-        fetch_base_data(&ctx.backend_host, &ctx.http_client, req.headers(), &cookies).await?;
-
-    let api_res = res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::get(format!(
-                    "{}/api/unstable/posts/{}/votes",
-                    ctx.backend_host, post_id,
-                ))
-                .body(Default::default())?,
-                req.headers(),
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await?;
-    let api_res = hyper::body::to_bytes(api_res.into_body()).await?;
-    let api_res: RespList<JustUser> = serde_json::from_slice(&api_res)?;
+    let base_data = fetch_base_data(&ctx, req.headers(), &cookies).await?;
+
+    let api_res = Backend::for_request(&ctx, req.headers(), &cookies)
+        .list_post_votes(post_id)
+        .await?;
 
     Ok(html_response(render::html! {
         <HTPage base_data={&base_data} lang={&lang} title={&lang.tr(&lang::likes())}>
@@ -824,20 +650,9 @@ async fn handler_post_poll_submit(
         PollVoteBody::Multiple { options: choices }
     };
 
-    let api_res = res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::put(format!(
-                    "{}/api/unstable/posts/{}/poll/your_vote",
-                    ctx.backend_host, post_id
-                ))
-                .body(serde_json::to_vec(&body)?.into())?,
-                &req_parts.headers,
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await;
+    let api_res = Backend::for_request(&ctx, &req_parts.headers, &cookies)
+        .vote_poll(post_id, &body)
+        .await;
 
     match api_res {
         Err(crate::Error::RemoteError((_, message))) => {
@@ -871,20 +686,9 @@ async fn handler_post_unlike(
 
     let cookies = get_cookie_map_for_req(&req)?;
 
-    res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::delete(format!(
-                    "{}/api/unstable/posts/{}/your_vote",
-                    ctx.backend_host, post_id
-                ))
-                .body(Default::default())?,
-                req.headers(),
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await?;
+    Backend::for_request(&ctx, req.headers(), &cookies)
+        .unlike_post(post_id)
+        .await?;
 
     Ok(hyper::Response::builder()
         .status(hyper::StatusCode::SEE_OTHER)
@@ -900,7 +704,9 @@ async fn handler_post_submit_reply(
     let (post_id,) = params;
 
     let (req_parts, body) = req.into_parts();
-    let lang = crate::get_lang_for_headers(&req_parts.headers);
+    check_rate_limit(&ctx, RateLimitClass::Submit, &req_parts)?;
+
+    let lang = crate::get_lang_for_headers(&ctx, &req_parts.headers);
     let cookies = get_cookie_map_for_headers(&req_parts.headers)?;
 
     let content_type = req_parts
@@ -957,21 +763,10 @@ async fn handler_post_submit_reply(
                         );
                     }
                     Some(mime) => {
-                        let res = res_to_error(
-                            ctx.http_client
-                                .request(for_client(
-                                    hyper::Request::post(format!(
-                                        "{}/api/unstable/media",
-                                        ctx.backend_host,
-                                    ))
-                                    .header(hyper::header::CONTENT_TYPE, mime.as_ref())
-                                    .body(hyper::Body::wrap_stream(stream))?,
-                                    &req_parts.headers,
-                                    &cookies,
-                                )?)
-                                .await?,
-                        )
-                        .await;
+                        let mime = mime.to_string();
+                        let res = Backend::for_request(&ctx, &req_parts.headers, &cookies)
+                            .upload_media(&mime, hyper::Body::wrap_stream(stream))
+                            .await;
 
                         match res {
                             Err(crate::Error::RemoteError((_, message))) => {
@@ -980,13 +775,10 @@ async fn handler_post_submit_reply(
                             Err(other) => {
                                 return Err(other);
                             }
-                            Ok(res) => {
-                                let res = hyper::body::to_bytes(res.into_body()).await?;
-                                let res: JustStringID = serde_json::from_slice(&res)?;
-
+                            Ok(media_id) => {
                                 body_values.insert(
                                     "attachment".into(),
-                                    format!("local-media://{}", res.id).into(),
+                                    format!("local-media://{}", media_id).into(),
                                 );
                             }
                         }
@@ -1022,27 +814,11 @@ async fn handler_post_submit_reply(
             .get("content_markdown")
             .and_then(|x| x.as_str())
             .unwrap_or("");
-        let preview_res = res_to_error(
-            ctx.http_client
-                .request(for_client(
-                    hyper::Request::post(format!(
-                        "{}/api/unstable/misc/render_markdown",
-                        ctx.backend_host
-                    ))
-                    .body(
-                        serde_json::to_vec(&serde_json::json!({ "content_markdown": md }))?.into(),
-                    )?,
-                    &req_parts.headers,
-                    &cookies,
-                )?)
-                .await?,
-        )
-        .await;
+        let preview_res = Backend::for_request(&ctx, &req_parts.headers, &cookies)
+            .render_markdown(md)
+            .await;
         return match preview_res {
             Ok(preview_res) => {
-                let preview_res = hyper::body::to_bytes(preview_res.into_body()).await?;
-                let preview_res: JustContentHTML = serde_json::from_slice(&preview_res)?;
-
                 page_post_inner(
                     post_id,
                     &req_parts.headers,
@@ -1079,20 +855,9 @@ async fn handler_post_submit_reply(
         body_values.contains_key("sensitive").into(),
     );
 
-    let api_res = res_to_error(
-        ctx.http_client
-            .request(for_client(
-                hyper::Request::post(format!(
-                    "{}/api/unstable/posts/{}/replies",
-                    ctx.backend_host, post_id
-                ))
-                .body(serde_json::to_vec(&body_values)?.into())?,
-                &req_parts.headers,
-                &cookies,
-            )?)
-            .await?,
-    )
-    .await;
+    let api_res = Backend::for_request(&ctx, &req_parts.headers, &cookies)
+        .create_comment(post_id, &body_values)
+        .await;
 
     match api_res {
         Err(crate::Error::RemoteError((_, message))) => {
@@ -1110,18 +875,13 @@ async fn handler_post_submit_reply(
             .await
         }
         Err(other) => Err(other),
-        Ok(api_res) => {
-            let api_res = hyper::body::to_bytes(api_res.into_body()).await?;
-            let api_res: JustID = serde_json::from_slice(&api_res)?;
-
-            Ok(hyper::Response::builder()
-                .status(hyper::StatusCode::SEE_OTHER)
-                .header(
-                    hyper::header::LOCATION,
-                    format!("/posts/{}#comment{}", post_id, api_res.id),
-                )
-                .body("Successfully posted.".into())?)
-        }
+        Ok(api_res) => Ok(hyper::Response::builder()
+            .status(hyper::StatusCode::SEE_OTHER)
+            .header(
+                hyper::header::LOCATION,
+                format!("/posts/{}#comment{}", post_id, api_res.id),
+            )
+            .body("Successfully posted.".into())?),
     }
 }
 
@@ -0,0 +1,266 @@
+use crate::components::{BoolCheckbox, HTPage};
+use crate::lang;
+use crate::preferences::{Density, Preferences, Theme, POSTS_PER_PAGE_VALUES};
+use crate::routes::{
+    fetch_base_data, get_cookie_map_for_req, html_response, COOKIE_AGE, PREFERENCES_COOKIE,
+};
+use serde_derive::Deserialize;
+use std::borrow::Cow;
+use std::sync::Arc;
+
+/// `<option>`s for a `<select>`, given each value, its label, and whether it's selected
+fn select_options(options: Vec<(String, String, bool)>) -> Vec<impl render::Render> {
+    options
+        .into_iter()
+        .map(|(value, name, selected)| {
+            if selected {
+                render::rsx! {
+                    <option value={value} selected={""}>{name}</option>
+                }
+            } else {
+                render::rsx! {
+                    <option value={value}>{name}</option>
+                }
+            }
+        })
+        .collect()
+}
+
+fn sort_options(lang: &crate::Translator, current: crate::SortType) -> Vec<impl render::Render> {
+    select_options(
+        crate::SortType::VALUES
+            .iter()
+            .map(|value| {
+                (
+                    value.as_str().to_owned(),
+                    lang.tr(&value.lang_key()).into_owned(),
+                    *value == current,
+                )
+            })
+            .collect(),
+    )
+}
+
+async fn page_preferences(
+    _: (),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let lang = crate::get_lang_for_req(&ctx, &req);
+    let cookies = get_cookie_map_for_req(&req)?;
+
+    let base_data = fetch_base_data(&ctx, req.headers(), &cookies).await?;
+    let preferences = &base_data.preferences;
+
+    let title = lang.tr(&lang::PREFERENCES);
+
+    let posts_per_page = std::iter::once((
+        String::new(),
+        lang.tr(&lang::PREFERENCES_POSTS_PER_PAGE_DEFAULT)
+            .into_owned(),
+        preferences.posts_per_page.is_none(),
+    ))
+    .chain(POSTS_PER_PAGE_VALUES.iter().map(|value| {
+        (
+            value.to_string(),
+            value.to_string(),
+            preferences.posts_per_page == Some(*value),
+        )
+    }))
+    .collect();
+
+    let densities = Density::VALUES
+        .iter()
+        .map(|value| {
+            (
+                value.as_str().to_owned(),
+                lang.tr(&value.lang_key()).into_owned(),
+                *value == preferences.density,
+            )
+        })
+        .collect();
+
+    let themes = Theme::VALUES
+        .iter()
+        .map(|value| {
+            (
+                value.as_str().to_owned(),
+                lang.tr(&value.lang_key()).into_owned(),
+                *value == preferences.theme,
+            )
+        })
+        .collect();
+
+    let time_displays = lang::TimeDisplay::VALUES
+        .iter()
+        .map(|display| {
+            (
+                display.as_str().to_owned(),
+                lang.tr(&display.lang_key()).into_owned(),
+                *display == preferences.time_display,
+            )
+        })
+        .collect();
+
+    let time_zones = chrono_tz::TZ_VARIANTS
+        .iter()
+        .map(|tz| {
+            (
+                tz.name().to_owned(),
+                tz.name().to_owned(),
+                *tz == preferences.time_zone,
+            )
+        })
+        .collect();
+
+    Ok(html_response(render::html! {
+        <HTPage base_data={&base_data} lang={&lang} title={&title}>
+            <h1>{title.as_ref()}</h1>
+            <form method={"POST"} action={"/preferences/submit"}>
+                <div>
+                    <label>
+                        {lang.tr(&lang::PREFERENCES_POST_SORT)}{" "}
+                        <select name={"post_sort"}>
+                            {sort_options(&lang, preferences.post_sort)}
+                        </select>
+                    </label>
+                </div>
+                <div>
+                    <label>
+                        {lang.tr(&lang::PREFERENCES_COMMENT_SORT)}{" "}
+                        <select name={"comment_sort"}>
+                            {sort_options(&lang, preferences.comment_sort)}
+                        </select>
+                    </label>
+                </div>
+                <div>
+                    <label>
+                        {lang.tr(&lang::PREFERENCES_POSTS_PER_PAGE)}{" "}
+                        <select name={"posts_per_page"}>
+                            {select_options(posts_per_page)}
+                        </select>
+                    </label>
+                </div>
+                <div>
+                    <label>
+                        {lang.tr(&lang::PREFERENCES_THEME)}{" "}
+                        <select name={"theme"}>
+                            {select_options(themes)}
+                        </select>
+                    </label>
+                </div>
+                <div>
+                    <label>
+                        {lang.tr(&lang::PREFERENCES_DENSITY)}{" "}
+                        <select name={"density"}>
+                            {select_options(densities)}
+                        </select>
+                    </label>
+                </div>
+                <div>
+                    <label>
+                        <BoolCheckbox name={"expand_sensitive"} value={preferences.expand_sensitive} />
+                        {lang.tr(&lang::PREFERENCES_EXPAND_SENSITIVE)}
+                    </label>
+                </div>
+                <div>
+                    <label>
+                        <BoolCheckbox name={"links_new_tab"} value={preferences.links_new_tab} />
+                        {lang.tr(&lang::PREFERENCES_LINKS_NEW_TAB)}
+                    </label>
+                </div>
+                <div>
+                    <label>
+                        {lang.tr(&lang::PREFERENCES_TIME_DISPLAY)}{" "}
+                        <select name={"time_display"}>
+                            {select_options(time_displays)}
+                        </select>
+                    </label>
+                </div>
+                <div>
+                    <label>
+                        {lang.tr(&lang::PREFERENCES_TIME_ZONE)}{" "}
+                        <select name={"time_zone"}>
+                            {select_options(time_zones)}
+                        </select>
+                    </label>
+                </div>
+                <button type={"submit"}>{lang.tr(&lang::SAVE)}</button>
+            </form>
+        </HTPage>
+    }))
+}
+
+async fn handler_preferences_submit(
+    _: (),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    #[derive(Deserialize)]
+    struct PreferencesForm<'a> {
+        post_sort: crate::SortType,
+        comment_sort: crate::SortType,
+        posts_per_page: Cow<'a, str>,
+        density: Density,
+        theme: Theme,
+        expand_sensitive: Option<Cow<'a, str>>,
+        links_new_tab: Option<Cow<'a, str>>,
+        time_display: lang::TimeDisplay,
+        time_zone: chrono_tz::Tz,
+    }
+
+    fn bad_request(message: &'static str) -> crate::Error {
+        crate::Error::UserError(crate::simple_response(
+            hyper::StatusCode::BAD_REQUEST,
+            message,
+        ))
+    }
+
+    let body = hyper::body::to_bytes(req.into_body()).await?;
+    let body: PreferencesForm = serde_urlencoded::from_bytes(&body)?;
+
+    let posts_per_page = if body.posts_per_page.is_empty() {
+        None
+    } else {
+        match body.posts_per_page.parse() {
+            Ok(value) if POSTS_PER_PAGE_VALUES.contains(&value) => Some(value),
+            _ => return Err(bad_request("Invalid number of posts per page")),
+        }
+    };
+
+    let preferences = Preferences {
+        post_sort: body.post_sort,
+        comment_sort: body.comment_sort,
+        posts_per_page,
+        expand_sensitive: body.expand_sensitive.is_some(),
+        density: body.density,
+        links_new_tab: body.links_new_tab.is_some(),
+        theme: body.theme,
+        time_display: body.time_display,
+        time_zone: body.time_zone,
+    };
+
+    Ok(hyper::Response::builder()
+        .status(hyper::StatusCode::SEE_OTHER)
+        .header(
+            hyper::header::SET_COOKIE,
+            format!(
+                "{}={}; Path=/; Max-Age={}",
+                PREFERENCES_COOKIE,
+                ctx.cookie_key.sign_json(&preferences)?,
+                COOKIE_AGE
+            ),
+        )
+        .header(hyper::header::LOCATION, "/preferences")
+        .body("Successfully saved preferences.".into())?)
+}
+
+pub fn route_preferences() -> crate::RouteNode<()> {
+    crate::RouteNode::new()
+        .with_handler_async(hyper::Method::GET, page_preferences)
+        .with_child(
+            "submit",
+            crate::RouteNode::new()
+                .with_handler_async(hyper::Method::POST, handler_preferences_submit),
+        )
+}
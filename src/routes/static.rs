@@ -1,27 +1,59 @@
+use crate::assets::Asset;
 use hitide_icons::ICONS_MAP;
 use std::sync::Arc;
 
-const FILE_MAIN_CSS: &[u8] = include_bytes!("../../res/main.css");
-
 pub fn route_static() -> crate::RouteNode<()> {
     crate::RouteNode::new().with_child_str(
         crate::RouteNode::new().with_handler_async(hyper::Method::GET, handler_static_get),
     )
 }
 
-async fn handler_static_get(
-    params: (String,),
-    _ctx: Arc<crate::RouteContext>,
-    _req: hyper::Request<hyper::Body>,
-) -> Result<hyper::Response<hyper::Body>, crate::Error> {
-    if params.0 == "main.css" {
-        let mut resp = hyper::Response::new(FILE_MAIN_CSS.into());
+fn asset_response(
+    asset: &Asset,
+    req: &hyper::Request<hyper::Body>,
+    cache_control: &'static str,
+) -> hyper::Response<hyper::Body> {
+    let (content, encoding) = asset.encoded(crate::compression::negotiate(req.headers()));
+
+    let mut resp = hyper::Response::new(content.into());
+    resp.headers_mut().insert(
+        hyper::header::CONTENT_TYPE,
+        hyper::header::HeaderValue::from_static(asset.content_type),
+    );
+    resp.headers_mut().insert(
+        hyper::header::CACHE_CONTROL,
+        hyper::header::HeaderValue::from_static(cache_control),
+    );
+    if asset.gzip.is_some() || asset.brotli.is_some() {
         resp.headers_mut().insert(
-            hyper::header::CONTENT_TYPE,
-            hyper::header::HeaderValue::from_static("text/css"),
+            hyper::header::VARY,
+            hyper::header::HeaderValue::from_static("Accept-Encoding"),
+        );
+    }
+    if let Some(encoding) = encoding {
+        resp.headers_mut().insert(
+            hyper::header::CONTENT_ENCODING,
+            hyper::header::HeaderValue::from_static(encoding.as_str()),
         );
+    }
 
-        Ok(resp)
+    resp
+}
+
+async fn handler_static_get(
+    params: (String,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    if let Some(asset) = ctx.assets.get_hashed(&params.0) {
+        Ok(asset_response(
+            asset,
+            &req,
+            "public, max-age=31536000, immutable",
+        ))
+    } else if let Some(asset) = ctx.assets.get(&params.0) {
+        // unhashed names still work, but may change at any time
+        Ok(asset_response(asset, &req, "no-cache"))
     } else if let Some(icon) = ICONS_MAP.get(params.0.as_str()) {
         let mut resp = hyper::Response::new(icon.content.into());
         resp.headers_mut().insert(
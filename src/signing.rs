@@ -0,0 +1,64 @@
+use hmac::{Hmac, Mac};
+
+type HmacSha256 = Hmac<sha2::Sha256>;
+
+const BASE64_CONFIG: base64::Config = base64::URL_SAFE_NO_PAD;
+
+/// Key for signing values that are handed to the client and must come back unmodified
+#[derive(Clone)]
+pub struct SigningKey(Vec<u8>);
+
+impl SigningKey {
+    pub fn new(secret: &[u8]) -> Self {
+        Self(secret.to_vec())
+    }
+
+    /// Creates a random key, which won't accept anything signed before a restart
+    pub fn generate() -> Self {
+        use rand::RngCore;
+
+        let mut secret = vec![0; 32];
+        rand::thread_rng().fill_bytes(&mut secret);
+
+        Self(secret)
+    }
+
+    fn mac(&self, value: &str) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.0).expect("HMAC accepts any key length");
+        mac.update(value.as_bytes());
+        mac
+    }
+
+    /// Signature for `value`, for when the value itself shouldn't be included
+    pub fn signature(&self, value: &str) -> String {
+        base64::encode_config(self.mac(value).finalize().into_bytes(), BASE64_CONFIG)
+    }
+
+    /// Appends a signature to `value`, which must not contain `.`
+    pub fn sign(&self, value: &str) -> String {
+        format!("{}.{}", value, self.signature(value))
+    }
+
+    /// Checks the signature added by [`sign`](Self::sign), returning the original value if it's valid
+    pub fn verify<'a>(&self, signed: &'a str) -> Option<&'a str> {
+        let (value, signature) = signed.rsplit_once('.')?;
+        let signature = base64::decode_config(signature, BASE64_CONFIG).ok()?;
+
+        self.mac(value).verify_slice(&signature).ok()?;
+
+        Some(value)
+    }
+
+    /// Serializes and signs `value`, producing a string safe to use in a cookie or URL
+    pub fn sign_json<T: serde::Serialize>(&self, value: &T) -> Result<String, serde_json::Error> {
+        let json = serde_json::to_vec(value)?;
+
+        Ok(self.sign(&base64::encode_config(&json, BASE64_CONFIG)))
+    }
+
+    pub fn verify_json<T: serde::de::DeserializeOwned>(&self, signed: &str) -> Option<T> {
+        let json = base64::decode_config(self.verify(signed)?, BASE64_CONFIG).ok()?;
+
+        serde_json::from_slice(&json).ok()
+    }
+}
@@ -0,0 +1,239 @@
+use crate::lang::{self, Catalog, ResourceMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+const BUILTIN: &[(&str, &str)] = &[
+    ("de", include_str!("../res/lang/de.ftl")),
+    ("en", include_str!("../res/lang/en.ftl")),
+    ("eo", include_str!("../res/lang/eo.ftl")),
+    ("fr", include_str!("../res/lang/fr.ftl")),
+    ("fa", include_str!("../res/lang/fa.ftl")),
+];
+
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+
+lazy_static::lazy_static! {
+    static ref CURRENT: RwLock<Arc<Catalog>> = RwLock::new(Arc::new(Catalog::new(builtin_resources())));
+}
+
+fn builtin_resources() -> ResourceMap {
+    BUILTIN
+        .iter()
+        .map(|(lang, src)| {
+            (
+                lang.parse().unwrap(),
+                vec![Arc::new(
+                    fluent::FluentResource::try_new((*src).to_owned())
+                        .expect("Failed to parse translation"),
+                )],
+            )
+        })
+        .collect()
+}
+
+/// Gets the currently loaded translations
+pub fn current() -> Arc<Catalog> {
+    CURRENT.read().unwrap().clone()
+}
+
+/// Removes the given messages from Fluent source, along with any attributes or continuation lines
+fn strip_messages(src: &str, ids: &[String]) -> String {
+    let mut out = String::with_capacity(src.len());
+    let mut skipping = false;
+
+    for line in src.split_inclusive('\n') {
+        if !line.starts_with(|c: char| c.is_whitespace()) {
+            let id_len = line
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+                .unwrap_or(line.len());
+            let (id, rest) = line.split_at(id_len);
+
+            skipping = rest.trim_start().starts_with('=') && ids.iter().any(|skip| skip == id);
+        }
+
+        if !skipping {
+            out.push_str(line);
+        }
+    }
+
+    out
+}
+
+fn parse_file(path: &Path, src: String) -> fluent::FluentResource {
+    // keep whatever messages could be parsed, rather than dropping the whole file
+    fluent::FluentResource::try_new(src).unwrap_or_else(|(resource, errors)| {
+        for err in errors {
+            log::error!("Failed to parse translation in {}: {}", path.display(), err);
+        }
+
+        resource
+    })
+}
+
+fn load_file(path: &Path) -> Result<fluent::FluentResource, std::io::Error> {
+    let src = std::fs::read_to_string(path)?;
+    let mut resource = parse_file(path, src.clone());
+
+    // a message with only attributes would hide the built-in value it overrides, so leave it out
+    let valueless: Vec<String> = resource
+        .entries()
+        .filter_map(|entry| match entry {
+            fluent_syntax::ast::Entry::Message(msg) if msg.value.is_none() => {
+                log::warn!(
+                    "Ignoring translation key {} in {}, since it has no value",
+                    msg.id.name,
+                    path.display()
+                );
+
+                Some(msg.id.name.to_owned())
+            }
+            _ => None,
+        })
+        .collect();
+    if !valueless.is_empty() {
+        resource = parse_file(path, strip_messages(&src, &valueless));
+    }
+
+    let mut found = 0;
+    for entry in resource.entries() {
+        if let fluent_syntax::ast::Entry::Message(msg) = entry {
+            if lang::ALL_KEYS.contains(&msg.id.name) {
+                found += 1;
+            } else {
+                log::warn!(
+                    "Unknown translation key {} in {}",
+                    msg.id.name,
+                    path.display()
+                );
+            }
+        }
+    }
+
+    log::info!(
+        "Loaded {} ({}/{} keys)",
+        path.display(),
+        found,
+        lang::ALL_KEYS.len()
+    );
+
+    Ok(resource)
+}
+
+fn translation_files(dir: &Path) -> Result<Vec<PathBuf>, std::io::Error> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension() == Some(std::ffi::OsStr::new("ftl")) {
+            files.push(path);
+        }
+    }
+
+    files.sort();
+
+    Ok(files)
+}
+
+/// Loads the built-in translations, extended or overridden by files in `dir`
+pub fn load(dir: &Path) -> Catalog {
+    let mut resources = builtin_resources();
+
+    let files = match translation_files(dir) {
+        Ok(files) => files,
+        Err(err) => {
+            log::error!(
+                "Failed to read translations directory {}: {}",
+                dir.display(),
+                err
+            );
+            Vec::new()
+        }
+    };
+
+    for path in files {
+        let lang: unic_langid::LanguageIdentifier = match path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.parse().ok())
+        {
+            Some(lang) => lang,
+            None => {
+                log::warn!(
+                    "Skipping {}, file name is not a language identifier",
+                    path.display()
+                );
+                continue;
+            }
+        };
+
+        match load_file(&path) {
+            Ok(resource) => {
+                resources
+                    .entry(lang)
+                    .or_default()
+                    .insert(0, Arc::new(resource));
+            }
+            Err(err) => {
+                log::error!("Failed to read {}: {}", path.display(), err);
+            }
+        }
+    }
+
+    Catalog::new(resources)
+}
+
+pub fn reload(dir: &Path) {
+    let catalog = load(dir);
+    *CURRENT.write().unwrap() = Arc::new(catalog);
+}
+
+type Snapshot = Vec<(PathBuf, Option<std::time::SystemTime>, u64)>;
+
+fn snapshot(dir: &Path) -> Option<Snapshot> {
+    translation_files(dir)
+        .ok()?
+        .into_iter()
+        .map(|path| {
+            let meta = std::fs::metadata(&path).ok()?;
+            Some((path, meta.modified().ok(), meta.len()))
+        })
+        .collect()
+}
+
+/// Reloads translations from `dir` whenever its files change, or on SIGHUP
+pub fn spawn_watcher(dir: PathBuf) {
+    #[cfg(unix)]
+    {
+        let dir = dir.clone();
+        tokio::spawn(async move {
+            let mut hangup =
+                match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()) {
+                    Ok(hangup) => hangup,
+                    Err(err) => {
+                        log::error!("Failed to listen for SIGHUP: {}", err);
+                        return;
+                    }
+                };
+
+            while hangup.recv().await.is_some() {
+                log::info!("Received SIGHUP, reloading translations");
+                reload(&dir);
+            }
+        });
+    }
+
+    tokio::spawn(async move {
+        let mut last = snapshot(&dir);
+
+        loop {
+            tokio::time::delay_for(POLL_INTERVAL).await;
+
+            let current = snapshot(&dir);
+            if current != last {
+                log::info!("Translations changed, reloading");
+                reload(&dir);
+
+                last = current;
+            }
+        }
+    });
+}
@@ -0,0 +1,305 @@
+//! Minimal ZIP archive writer, producing the archive one entry at a time so it can be streamed.
+//!
+//! Entries are deflated in memory, so sizes and checksums are known before each local header is
+//! written. ZIP64 is not supported, which limits archives to 4 GiB and 65535 entries, less one
+//! since readers take an all-ones size, offset or count to mean that ZIP64 fields follow.
+
+use hyper::body::Bytes;
+use std::io::Write;
+
+const LOCAL_FILE_HEADER_SIGNATURE: u32 = 0x04034b50;
+const CENTRAL_DIRECTORY_HEADER_SIGNATURE: u32 = 0x02014b50;
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x06054b50;
+
+const VERSION: u16 = 20;
+const FLAG_UTF8_NAME: u16 = 1 << 11;
+const METHOD_DEFLATE: u16 = 8;
+
+const LOCAL_FILE_HEADER_SIZE: usize = 30;
+const CENTRAL_DIRECTORY_HEADER_SIZE: usize = 46;
+const END_OF_CENTRAL_DIRECTORY_SIZE: usize = 22;
+
+const MAX_SIZE: u32 = u32::MAX - 1;
+const MAX_ENTRIES: u16 = u16::MAX - 1;
+
+#[derive(Debug)]
+pub enum ZipError {
+    NameTooLong,
+    TooLarge,
+    TooManyEntries,
+}
+
+impl std::fmt::Display for ZipError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ZipError::NameTooLong => write!(f, "ZIP entry name too long"),
+            ZipError::TooLarge => write!(f, "ZIP archive too large"),
+            ZipError::TooManyEntries => write!(f, "Too many entries in ZIP archive"),
+        }
+    }
+}
+
+impl std::error::Error for ZipError {}
+
+struct CentralEntry {
+    name: String,
+    crc: u32,
+    compressed_size: u32,
+    size: u32,
+    offset: u32,
+}
+
+pub struct ZipWriter {
+    entries: Vec<CentralEntry>,
+    offset: u32,
+    directory_size: u32,
+    dos_time: u16,
+    dos_date: u16,
+}
+
+fn put_u16(dest: &mut Vec<u8>, value: u16) {
+    dest.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u32(dest: &mut Vec<u8>, value: u32) {
+    dest.extend_from_slice(&value.to_le_bytes());
+}
+
+fn to_u32(value: usize) -> Result<u32, ZipError> {
+    match std::convert::TryInto::<u32>::try_into(value) {
+        Ok(value) if value <= MAX_SIZE => Ok(value),
+        _ => Err(ZipError::TooLarge),
+    }
+}
+
+fn add_size(a: u32, b: usize) -> Result<u32, ZipError> {
+    a.checked_add(to_u32(b)?)
+        .filter(|&sum| sum <= MAX_SIZE)
+        .ok_or(ZipError::TooLarge)
+}
+
+impl ZipWriter {
+    /// Creates a writer whose entries are all timestamped with `modified`
+    pub fn new(modified: chrono::NaiveDateTime) -> Self {
+        use chrono::{Datelike, Timelike};
+
+        // DOS timestamps start at 1980 and only have two-second precision
+        let year = (modified.year() - 1980).clamp(0, 127) as u16;
+
+        Self {
+            entries: Vec::new(),
+            offset: 0,
+            directory_size: 0,
+            dos_time: ((modified.hour() as u16) << 11)
+                | ((modified.minute() as u16) << 5)
+                | (modified.second() as u16 / 2),
+            dos_date: (year << 9) | ((modified.month() as u16) << 5) | (modified.day() as u16),
+        }
+    }
+
+    /// Adds a file, returning the bytes to write for it
+    ///
+    /// Fails without writing anything if the archive, including the central directory still to
+    /// come, would no longer fit the limits of a non-ZIP64 archive.
+    pub fn add_file(&mut self, name: &str, content: &[u8]) -> Result<Bytes, ZipError> {
+        if self.entries.len() >= usize::from(MAX_ENTRIES) {
+            return Err(ZipError::TooManyEntries);
+        }
+        let name_len: u16 =
+            std::convert::TryInto::try_into(name.len()).map_err(|_| ZipError::NameTooLong)?;
+
+        let mut crc = flate2::Crc::new();
+        crc.update(content);
+
+        let mut encoder =
+            flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder
+            .write_all(content)
+            .expect("writing to a Vec can't fail");
+        let compressed = encoder.finish().expect("writing to a Vec can't fail");
+
+        let entry = CentralEntry {
+            name: name.to_owned(),
+            crc: crc.sum(),
+            compressed_size: to_u32(compressed.len())?,
+            size: to_u32(content.len())?,
+            offset: self.offset,
+        };
+
+        let entry_size = LOCAL_FILE_HEADER_SIZE + name.len() + compressed.len();
+        let offset = add_size(self.offset, entry_size)?;
+        let directory_size = add_size(
+            self.directory_size,
+            CENTRAL_DIRECTORY_HEADER_SIZE + name.len(),
+        )?;
+        add_size(
+            add_size(offset, directory_size as usize)?,
+            END_OF_CENTRAL_DIRECTORY_SIZE,
+        )?;
+
+        let mut dest = Vec::with_capacity(entry_size);
+        put_u32(&mut dest, LOCAL_FILE_HEADER_SIGNATURE);
+        put_u16(&mut dest, VERSION);
+        put_u16(&mut dest, FLAG_UTF8_NAME);
+        put_u16(&mut dest, METHOD_DEFLATE);
+        put_u16(&mut dest, self.dos_time);
+        put_u16(&mut dest, self.dos_date);
+        put_u32(&mut dest, entry.crc);
+        put_u32(&mut dest, entry.compressed_size);
+        put_u32(&mut dest, entry.size);
+        put_u16(&mut dest, name_len);
+        put_u16(&mut dest, 0); // extra field length
+        dest.extend_from_slice(name.as_bytes());
+        dest.extend_from_slice(&compressed);
+
+        self.offset = offset;
+        self.directory_size = directory_size;
+        self.entries.push(entry);
+
+        Ok(dest.into())
+    }
+
+    /// Writes the central directory, which must come after every entry
+    ///
+    /// `add_file` has already checked that this fits.
+    pub fn finish(self) -> Bytes {
+        let mut dest =
+            Vec::with_capacity(self.directory_size as usize + END_OF_CENTRAL_DIRECTORY_SIZE);
+
+        for entry in &self.entries {
+            put_u32(&mut dest, CENTRAL_DIRECTORY_HEADER_SIGNATURE);
+            put_u16(&mut dest, VERSION); // version made by
+            put_u16(&mut dest, VERSION); // version needed to extract
+            put_u16(&mut dest, FLAG_UTF8_NAME);
+            put_u16(&mut dest, METHOD_DEFLATE);
+            put_u16(&mut dest, self.dos_time);
+            put_u16(&mut dest, self.dos_date);
+            put_u32(&mut dest, entry.crc);
+            put_u32(&mut dest, entry.compressed_size);
+            put_u32(&mut dest, entry.size);
+            put_u16(&mut dest, entry.name.len() as u16);
+            put_u16(&mut dest, 0); // extra field length
+            put_u16(&mut dest, 0); // comment length
+            put_u16(&mut dest, 0); // disk number
+            put_u16(&mut dest, 0); // internal attributes
+            put_u32(&mut dest, 0); // external attributes
+            put_u32(&mut dest, entry.offset);
+            dest.extend_from_slice(entry.name.as_bytes());
+        }
+
+        let count = self.entries.len() as u16;
+
+        put_u32(&mut dest, END_OF_CENTRAL_DIRECTORY_SIGNATURE);
+        put_u16(&mut dest, 0); // this disk
+        put_u16(&mut dest, 0); // disk with central directory
+        put_u16(&mut dest, count);
+        put_u16(&mut dest, count);
+        put_u32(&mut dest, self.directory_size);
+        put_u32(&mut dest, self.offset);
+        put_u16(&mut dest, 0); // comment length
+
+        dest.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn test_writer() -> ZipWriter {
+        ZipWriter::new(
+            chrono::NaiveDate::from_ymd_opt(2021, 6, 15)
+                .and_then(|date| date.and_hms_opt(12, 30, 10))
+                .unwrap(),
+        )
+    }
+
+    #[test]
+    fn round_trips_through_reader() {
+        let files: &[(&str, &[u8])] = &[
+            ("README.md", b"Data export\n"),
+            ("posts/1.json", br#"{"title": "Hello"}"#),
+            ("empty.txt", b""),
+            ("caf\u{e9}.md", &[b'a'; 100_000]),
+        ];
+
+        let mut writer = test_writer();
+        let mut archive = Vec::new();
+        for (name, content) in files {
+            archive.extend_from_slice(&writer.add_file(name, content).unwrap());
+        }
+        archive.extend_from_slice(&writer.finish());
+
+        let mut reader = zip::ZipArchive::new(std::io::Cursor::new(archive)).unwrap();
+        assert_eq!(reader.len(), files.len());
+
+        for (i, (name, content)) in files.iter().enumerate() {
+            let mut file = reader.by_index(i).unwrap();
+            assert_eq!(file.name(), *name);
+            assert_eq!(file.size(), content.len() as u64);
+
+            let mut crc = flate2::Crc::new();
+            crc.update(content);
+            assert_eq!(file.crc32(), crc.sum());
+
+            let mut read = Vec::new();
+            file.read_to_end(&mut read).unwrap();
+            assert_eq!(&read, content);
+        }
+    }
+
+    #[test]
+    fn rejects_too_many_entries() {
+        let mut writer = test_writer();
+        let mut archive = Vec::new();
+        for i in 0..MAX_ENTRIES {
+            archive.extend_from_slice(&writer.add_file(&i.to_string(), b"").unwrap());
+        }
+
+        assert!(matches!(
+            writer.add_file("extra", b""),
+            Err(ZipError::TooManyEntries)
+        ));
+
+        archive.extend_from_slice(&writer.finish());
+        let reader = zip::ZipArchive::new(std::io::Cursor::new(archive)).unwrap();
+        assert_eq!(reader.len(), usize::from(MAX_ENTRIES));
+    }
+
+    #[test]
+    fn rejects_archive_over_limit() {
+        let mut writer = test_writer();
+        writer.add_file("first", b"first").unwrap();
+
+        // Pretend earlier entries have used up nearly all the space
+        writer.offset = MAX_SIZE - 200;
+
+        // The name is stored uncompressed in both the entry and the central directory
+        assert!(matches!(
+            writer.add_file(&"b".repeat(100), b""),
+            Err(ZipError::TooLarge)
+        ));
+        assert_eq!(writer.entries.len(), 1);
+
+        // Room is still left for the central directory of a small entry
+        writer.add_file("small", b"").unwrap();
+        assert!(
+            u64::from(writer.offset)
+                + u64::from(writer.directory_size)
+                + END_OF_CENTRAL_DIRECTORY_SIZE as u64
+                <= u64::from(MAX_SIZE)
+        );
+    }
+
+    #[test]
+    fn rejects_long_name() {
+        let mut writer = test_writer();
+        let name = "a".repeat(usize::from(u16::MAX) + 1);
+
+        assert!(matches!(
+            writer.add_file(&name, b""),
+            Err(ZipError::NameTooLong)
+        ));
+    }
+}